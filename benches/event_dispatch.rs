@@ -0,0 +1,82 @@
+//! Compares the dispatch on the event type with trial deserialization into an untagged enum.
+//!
+//! cargo bench --bench event_dispatch
+
+use binance::websocket_stream::spot::events::{
+  AccountUpdateEvent, AggTradesEvent, BalanceUpdateEvent, BookTickerEvent, DayTickerEvent,
+  DepthOrderBookEvent, KlineEvent, OrderBook, OrderTradeEvent, TradeEvent, WebsocketSpotEvent,
+  WindowTickerEvent,
+};
+use serde::Deserialize;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100_000;
+
+const TRADE: &str = r#"{"e":"trade","E":1672515782136,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":1672515782136,"m":true,"M":true}"#;
+const AGG_TRADE: &str = r#"{"e":"aggTrade","E":1672515782136,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":1672515782136,"m":true,"M":true}"#;
+const BOOK_TICKER: &str = r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+const DEPTH_UPDATE: &str = r#"{"e":"depthUpdate","E":1672515782136,"s":"BNBBTC","U":157,"u":160,"b":[["0.0024","10"]],"a":[["0.0026","100"]]}"#;
+const KLINE: &str = r#"{"e":"kline","E":1672515782136,"s":"BNBBTC","k":{"t":1672515780000,"T":1672515839999,"s":"BNBBTC","i":"1m","f":100,"L":200,"o":"0.0010","c":"0.0020","h":"0.0025","l":"0.0015","v":"1000","n":100,"x":false,"q":"1.0000","V":"500","Q":"0.500","B":"123456"}}"#;
+const DAY_TICKER: &str = r#"{"e":"24hrTicker","E":1672515782136,"s":"BNBBTC","p":"0.0015","P":"250.00","w":"0.0018","x":"0.0009","c":"0.0025","Q":"10","b":"0.0024","B":"10","a":"0.0026","A":"100","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18","O":0,"C":86400000,"F":0,"L":18150,"n":18151}"#;
+
+/// Dispatch before the event type was used, every shape is tried in order
+#[allow(dead_code)]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UntaggedEvents {
+  DayTickerEventAll(Vec<DayTickerEvent>),
+  WindowTickerEventAll(Vec<WindowTickerEvent>),
+  BalanceUpdateEvent(BalanceUpdateEvent),
+  DayTickerEvent(DayTickerEvent),
+  WindowTickerEvent(WindowTickerEvent),
+  BookTickerEvent(BookTickerEvent),
+  AccountUpdateEvent(AccountUpdateEvent),
+  OrderTradeEvent(OrderTradeEvent),
+  AggTradesEvent(AggTradesEvent),
+  TradeEvent(TradeEvent),
+  KlineEvent(KlineEvent),
+  OrderBook(OrderBook),
+  DepthOrderBookEvent(DepthOrderBookEvent),
+}
+
+fn measure<F: FnMut()>(mut parse: F) -> Duration {
+  let started_at = Instant::now();
+  for _ in 0..ITERATIONS {
+    parse();
+  }
+  started_at.elapsed() / ITERATIONS
+}
+
+fn main() {
+  let messages = [
+    ("trade", TRADE),
+    ("aggTrade", AGG_TRADE),
+    ("bookTicker", BOOK_TICKER),
+    ("depthUpdate", DEPTH_UPDATE),
+    ("kline", KLINE),
+    ("24hrTicker", DAY_TICKER),
+  ];
+
+  println!("{:<12} {:>12} {:>12}", "message", "untagged", "dispatch");
+  for (name, msg) in messages {
+    let event = WebsocketSpotEvent::from_message(None, msg);
+    assert!(
+      !matches!(event, WebsocketSpotEvent::Unknown(_)),
+      "{} is not recognized",
+      name
+    );
+
+    let untagged = measure(|| {
+      let event = serde_json::from_value::<UntaggedEvents>(
+        serde_json::from_str::<serde_json::Value>(black_box(msg)).unwrap(),
+      );
+      let _ = black_box(event);
+    });
+    let dispatch = measure(|| {
+      black_box(WebsocketSpotEvent::from_message(None, black_box(msg)));
+    });
+
+    println!("{:<12} {:>12?} {:>12?}", name, untagged, dispatch);
+  }
+}
@@ -1,7 +1,9 @@
 use binance::client::*;
 use binance::config::Config;
 use binance::rest::core::rate_limiter::ip_rate_limit_manager::IpRateLimitManager;
+use binance::rest::core::rate_limiter::ip_rate_limit_manager::planner::PlannedCall;
 use binance::rest::core::rate_limiter::unfilled_order_rate_limit_manager::UnfilledOrderRateLimitManager;
+use binance::rest::endpoints::{SpotV3, API};
 use binance::rest::spot::v3::account::SpotAccountManagerV3;
 use binance::rest::spot::v3::general::GeneralManagerV3;
 use binance::rest::spot::v3::market::SpotMarketV3Manager;
@@ -51,6 +53,36 @@ async fn main() -> AnyhowResult<()> {
   print_rate_limits(ip_rate_limiter.clone(), unfilled_order_rate_limiter.clone()).await?;
   println!("=========");
 
+  // Estimate and run a small depth backfill plan
+  let symbols = ["BTCUSDT", "ETHUSDT", "SOLUSDT"];
+  let plan: Vec<PlannedCall> = symbols
+    .iter()
+    .map(|symbol| {
+      PlannedCall::new(
+        API::SpotV3(SpotV3::Depth),
+        Some(format!("limit=1000&symbol={}", symbol)),
+      )
+    })
+    .collect();
+  let estimate = ip_rate_limiter.estimate_plan(&plan).await?;
+  println!(
+    "Plan weight: {}, completes in: {:?}",
+    estimate.total_weight, estimate.completion_in
+  );
+
+  let mut next_symbol = symbols.iter();
+  let books = ip_rate_limiter
+    .execute_plan(plan, |_| {
+      let client = client.clone();
+      let symbol = next_symbol.next().unwrap().to_string();
+      async move { client.fetch_depth_with_limit(symbol, 1000u16).await }
+    })
+    .await?;
+  println!("Fetched {} order books", books.len());
+
+  print_rate_limits(ip_rate_limiter.clone(), unfilled_order_rate_limiter.clone()).await?;
+  println!("=========");
+
   Ok(())
 }
 
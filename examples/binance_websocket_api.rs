@@ -0,0 +1,59 @@
+use anyhow::Result;
+use binance::client::*;
+use binance::rest::spot::v3::trade::enums::{OrderSide, OrderType, TimeInForce};
+use binance::rest::spot::v3::trade::requests::PlaceOrderRequest;
+use binance::websocket_api::WebSocketApiClient;
+use dotenvy::dotenv;
+use std::env;
+
+pub type AnyhowResult<T> = Result<T>;
+
+macro_rules! handle_result {
+  ($expression:expr) => {
+    match $expression {
+      Ok(answer) => println!("{:#?}", answer),
+      Err(e) => println!("Error: {:?}", e),
+    }
+  };
+}
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  dotenv().expect("Failed to read .env file");
+
+  let api_key = Some(env::var("API_KEY").unwrap_or("YOUR_API_KEY".into()));
+  let secret_key = Some(env::var("API_SECRET").unwrap_or("YOUR_API_KEY".into()));
+
+  // Requests share one connection, opened by the first of them
+  let mut ws_api: WebSocketApiClient = Binance::new(api_key, secret_key);
+
+  // Ed25519 keys can authenticate the connection, HMAC keys sign every request
+  if let Ok(private_key_pem) = env::var("API_ED25519_PRIVATE_KEY") {
+    ws_api = ws_api.set_ed25519_private_key(&private_key_pem)?;
+    handle_result!(ws_api.session_logon().await);
+  }
+
+  handle_result!(ws_api.session_status().await);
+
+  handle_result!(ws_api.fetch_info_summary().await);
+
+  // Validated only, not sent to the matching engine
+  handle_result!(
+    ws_api
+      .test_place_order(PlaceOrderRequest {
+        symbol: "BTCUSDT".into(),
+        order_side: OrderSide::Buy,
+        order_type: OrderType::Limit,
+        time_in_force: Some(TimeInForce::GTC),
+        qty: Some(0.001),
+        price: Some(10_000.0),
+        ..Default::default()
+      })
+      .await
+  );
+
+  ws_api.shutdown().await?;
+  ws_api.wait_for_end().await?;
+
+  Ok(())
+}
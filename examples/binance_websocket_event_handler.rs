@@ -0,0 +1,53 @@
+use anyhow::Result;
+use binance::websocket_stream::spot::events::{BookTickerEvent, ConnectionState, TradeEvent};
+use binance::websocket_stream::spot::handler::WebsocketSpotEventHandler;
+use binance::websocket_stream::spot::stream_name::StreamName;
+use binance::websocket_stream::spot::WebSocketSpotStream;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+#[derive(Default)]
+struct PriceTracker {
+  last_trade_price: Option<String>,
+}
+
+impl WebsocketSpotEventHandler for PriceTracker {
+  async fn on_trade(&mut self, event: TradeEvent) -> Result<()> {
+    println!("Trade: {} {} {}", event.symbol, event.price, event.qty);
+    self.last_trade_price = Some(event.price.to_string());
+    Ok(())
+  }
+
+  async fn on_book_ticker(&mut self, event: BookTickerEvent) -> Result<()> {
+    println!(
+      "Book: {} {} / {}, last trade {:?}",
+      event.symbol, event.best_bid, event.best_ask, self.last_trade_price
+    );
+    Ok(())
+  }
+
+  async fn on_connection_state(&mut self, state: ConnectionState) -> Result<()> {
+    println!("Connection state: {:?}", state);
+    Ok(())
+  }
+}
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  let web_socket = WebSocketSpotStream::new_with_event_handler(PriceTracker::default());
+
+  web_socket
+    .subscribe(vec![
+      StreamName::trade("BTCUSDT"),
+      StreamName::book_ticker("BTCUSDT"),
+    ])
+    .await?;
+
+  sleep(Duration::from_secs(10)).await;
+
+  web_socket.shutdown().await?;
+
+  Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use binance::websocket_stream::spot::config::WebSocketSpotConfig;
+use binance::websocket_stream::spot::enums::TimeUnit;
+use binance::websocket_stream::spot::events::WebsocketSpotEvent;
+use binance::websocket_stream::spot::stream_name::StreamName;
+use binance::websocket_stream::spot::WebSocketSpotStream;
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  channel_stream().await?;
+  broadcast_stream().await?;
+
+  Ok(())
+}
+
+/// Single consumer reading events as an async stream
+async fn channel_stream() -> AnyhowResult<()> {
+  let (web_socket, mut events) = WebSocketSpotStream::new_with_channel(100);
+
+  web_socket
+    .subscribe(vec![StreamName::trade("BTCUSDT")])
+    .await?;
+
+  let mut received = 0;
+  while let Some(event) = events.next().await {
+    if let WebsocketSpotEvent::Trade(trade) = event {
+      // Handlers can await here
+      println!("{} - {} - {}", trade.symbol, trade.price, trade.qty);
+      received += 1;
+    }
+    if received == 10 {
+      break;
+    }
+  }
+
+  web_socket.shutdown().await?;
+
+  Ok(())
+}
+
+/// Several consumers reading the same connection
+async fn broadcast_stream() -> AnyhowResult<()> {
+  // Market data only endpoint, timestamps in microseconds
+  let config = WebSocketSpotConfig::market_data_only().set_time_unit(TimeUnit::Microsecond);
+  let web_socket = WebSocketSpotStream::new_with_broadcast_and_config(1000, config);
+
+  for consumer in ["strategy", "recorder"] {
+    let mut events = web_socket.event_receiver()?;
+    tokio::spawn(async move {
+      while let Ok(event) = events.recv().await {
+        if let WebsocketSpotEvent::BookTicker(ticker) = event {
+          println!("[{}] {} - {}", consumer, ticker.symbol, ticker.best_bid);
+        }
+      }
+    });
+  }
+
+  web_socket
+    .subscribe(vec![StreamName::book_ticker("ETHUSDT")])
+    .await?;
+
+  sleep(Duration::from_secs(5)).await;
+
+  web_socket.shutdown().await?;
+
+  Ok(())
+}
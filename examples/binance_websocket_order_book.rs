@@ -0,0 +1,52 @@
+use anyhow::Result;
+use binance::client::*;
+use binance::rest::spot::v3::market::SpotMarketV3Manager;
+use binance::websocket_stream::spot::enums::DepthUpdateSpeed;
+use binance::websocket_stream::spot::order_book::{LocalOrderBook, OrderBookUpdate};
+use binance::websocket_stream::spot::WebSocketSpotStream;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  let market: SpotMarketV3Manager = Binance::new(None, None);
+  let order_book = Arc::new(LocalOrderBook::new(market).set_snapshot_limit(1000));
+
+  let web_socket = WebSocketSpotStream::new_with_broadcast(10_000);
+  order_book.spawn(web_socket.event_receiver()?);
+
+  let mut updates = order_book.updates();
+  tokio::spawn(async move {
+    while let Ok(update) = updates.recv().await {
+      match update {
+        OrderBookUpdate::Updated {
+          symbol,
+          best_bid,
+          best_ask,
+          ..
+        } => println!("{} - {:?} - {:?}", symbol, best_bid, best_ask),
+        update => println!("{:?}", update),
+      }
+    }
+  });
+
+  // Books of several symbols share one stream
+  for symbol in ["BTCUSDT", "ETHUSDT"] {
+    order_book
+      .track(&web_socket, symbol, DepthUpdateSpeed::Ms100)
+      .await?;
+  }
+
+  sleep(Duration::from_secs(10)).await;
+
+  if let Some(depth) = order_book.depth("BTCUSDT", 5).await {
+    println!("{:#?}", depth);
+  }
+
+  web_socket.shutdown().await?;
+
+  Ok(())
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use binance::websocket_stream::spot::events::WebsocketSpotEvent;
+use binance::websocket_stream::spot::stream_name::StreamName;
 use binance::websocket_stream::spot::WebSocketSpotStream;
 use dotenvy::dotenv;
 use std::time::Duration;
@@ -37,12 +38,12 @@ async fn last_price_for_one_symbol() -> AnyhowResult<()> {
   println!("[SUBSCRIB BTC + ETH]");
   web_socket
     .subscribe(vec![
-      "btcusdt@ticker".to_string(),
-      "solusdt@ticker".to_string(),
+      StreamName::ticker("BTCUSDT"),
+      StreamName::ticker("SOLUSDT"),
     ])
     .await?; // check error
   web_socket
-    .subscribe(vec!["ethusdt@ticker".to_string()])
+    .subscribe(vec![StreamName::ticker("ETHUSDT")])
     .await?;
   println!("[SUBSCRIBED BTC + ETH]");
 
@@ -50,7 +51,7 @@ async fn last_price_for_one_symbol() -> AnyhowResult<()> {
 
   println!("[UNSUBSCRIBE BTCUSDT]");
   web_socket
-    .unsubscribe(vec!["btcusdt@ticker".to_string()])
+    .unsubscribe(vec![StreamName::ticker("BTCUSDT")])
     .await?;
   println!("[UNSUBSCRIBED BTCUSDT]");
 
@@ -59,6 +60,8 @@ async fn last_price_for_one_symbol() -> AnyhowResult<()> {
   println!("[LIST SUBSCRIPTIONS]");
   let res = web_socket.list_subscriptions().await?;
   println!("{:?}", res);
+  let res = web_socket.list_server_subscriptions().await?;
+  println!("Server: {:?}", res);
   tokio::time::sleep(Duration::from_secs(5)).await;
 
   // Use the reconnect method with a new handler
@@ -74,7 +77,7 @@ async fn last_price_for_one_symbol() -> AnyhowResult<()> {
 }
 
 async fn market_websocket() -> AnyhowResult<()> {
-  let btc_trade = vec!["btcusdt@trade".to_string()];
+  let btc_trade = vec![StreamName::trade("BTCUSDT")];
   let web_socket = WebSocketSpotStream::new(move |event: WebsocketSpotEvent| {
     match event {
       WebsocketSpotEvent::Trade(trade) => {
@@ -95,6 +98,9 @@ async fn market_websocket() -> AnyhowResult<()> {
           order_book.last_update_id, order_book.bids, order_book.asks
         );
       }
+      WebsocketSpotEvent::ConnectionState(state) => {
+        println!("Connection state: {:?}", state);
+      }
       _ => (),
     };
 
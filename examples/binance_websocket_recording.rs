@@ -0,0 +1,62 @@
+use anyhow::Result;
+use binance::websocket_stream::spot::config::WebSocketSpotConfig;
+use binance::websocket_stream::spot::events::WebsocketSpotEvent;
+use binance::websocket_stream::spot::recording::{
+  ReplaySpeed, WebsocketRecorder, WebsocketReplayer,
+};
+use binance::websocket_stream::spot::stream_name::StreamName;
+use binance::websocket_stream::spot::WebSocketSpotStream;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+const RECORDING: &str = "btcusdt_trades.rec";
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  record().await?;
+  replay().await?;
+
+  Ok(())
+}
+
+async fn record() -> AnyhowResult<()> {
+  let recorder = WebsocketRecorder::create(RECORDING)?;
+  let config = WebSocketSpotConfig::default().set_recorder(recorder.clone());
+
+  let web_socket = WebSocketSpotStream::new_with_config(
+    |event: WebsocketSpotEvent| {
+      if let WebsocketSpotEvent::Trade(trade) = event {
+        println!("Live: {} {} {}", trade.symbol, trade.price, trade.qty);
+      }
+      Ok(())
+    },
+    config,
+  );
+
+  web_socket
+    .subscribe(vec![StreamName::trade("BTCUSDT")])
+    .await?;
+  sleep(Duration::from_secs(10)).await;
+  web_socket.shutdown().await?;
+  recorder.flush()?;
+
+  Ok(())
+}
+
+async fn replay() -> AnyhowResult<()> {
+  let replayer = WebsocketReplayer::new(RECORDING).set_speed(ReplaySpeed::Recorded);
+
+  let events = replayer
+    .replay(|event| {
+      if let WebsocketSpotEvent::Trade(trade) = event {
+        println!("Replay: {} {} {}", trade.symbol, trade.price, trade.qty);
+      }
+      Ok(())
+    })
+    .await?;
+  println!("Replayed {} events", events);
+
+  Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use binance::websocket_stream::spot::events::WebsocketSpotEvent;
+use binance::websocket_stream::spot::router::EventKind;
+use binance::websocket_stream::spot::stream_name::StreamName;
+use binance::websocket_stream::spot::WebSocketSpotStream;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  let web_socket = WebSocketSpotStream::new_with_router();
+
+  let btc_trades = web_socket
+    .route_stream(StreamName::trade("BTCUSDT"), |event| {
+      if let WebsocketSpotEvent::Trade(trade) = event {
+        println!("BTC trade: {} {}", trade.price, trade.qty);
+      }
+      Ok(())
+    })
+    .await?;
+
+  let eth_book = web_socket
+    .route_stream(StreamName::book_ticker("ETHUSDT"), |event| {
+      if let WebsocketSpotEvent::BookTicker(ticker) = event {
+        println!("ETH book: {} / {}", ticker.best_bid, ticker.best_ask);
+      }
+      Ok(())
+    })
+    .await?;
+
+  // Any trade event of BTCUSDT, whichever route subscribed its stream
+  let btc_watcher = web_socket.route_events(EventKind::Trade, "BTCUSDT", |event| {
+    match event {
+      WebsocketSpotEvent::Trade(trade) => println!("Watcher saw trade {}", trade.trade_id),
+      WebsocketSpotEvent::ConnectionState(state) => println!("Connection state: {:?}", state),
+      _ => (),
+    }
+    Ok(())
+  })?;
+
+  sleep(Duration::from_secs(10)).await;
+
+  // Unsubscribes btcusdt@trade, ethusdt@bookTicker stays
+  drop(btc_trades);
+  drop(btc_watcher);
+  sleep(Duration::from_secs(5)).await;
+  println!("{:?}", web_socket.list_subscriptions().await?);
+
+  drop(eth_book);
+  web_socket.shutdown().await?;
+
+  Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use binance::client::*;
+use binance::rest::spot::v3::user_stream::SpotUserStreamManagerV3;
+use binance::websocket_stream::spot::events::WebsocketSpotEvent;
+use binance::websocket_stream::spot::user_data::UserDataStream;
+use dotenvy::dotenv;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub type AnyhowResult<T> = Result<T>;
+
+#[tokio::main]
+async fn main() -> AnyhowResult<()> {
+  dotenv().expect("Failed to read .env file");
+
+  let api_key = Some(env::var("API_KEY").unwrap_or("YOUR_API_KEY".into()));
+  let secret_key = Some(env::var("API_SECRET").unwrap_or("YOUR_API_KEY".into()));
+
+  let user_stream: SpotUserStreamManagerV3 = Binance::new(api_key, secret_key);
+  let user_data = Arc::new(UserDataStream::new(user_stream));
+
+  let mut events = user_data.event_receiver()?;
+  tokio::spawn(async move {
+    while let Ok(event) = events.recv().await {
+      match event {
+        WebsocketSpotEvent::OrderTrade(report) => println!(
+          "{} {} {} - {}",
+          report.symbol, report.side, report.execution_type, report.order_status
+        ),
+        WebsocketSpotEvent::OutboundAccountPosition(position) => {
+          for balance in position.balances {
+            println!(
+              "{} - free {} - locked {}",
+              balance.asset, balance.free, balance.locked
+            );
+          }
+        }
+        WebsocketSpotEvent::SpotBalanceUpdate(update) => {
+          println!("{} - {}", update.asset, update.balance_delta)
+        }
+        event => println!("{:?}", event),
+      }
+    }
+  });
+
+  user_data.start().await?;
+  println!("Listen key: {:?}", user_data.listen_key().await);
+
+  sleep(Duration::from_secs(60)).await;
+
+  user_data.stop().await?;
+
+  Ok(())
+}
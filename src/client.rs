@@ -39,7 +39,7 @@ pub trait Binance {
 }
 
 macro_rules! impl_binance_for {
-  ($typename:ident) => {
+  ($typename:ident $(, $field:ident: $value:expr)*) => {
     impl Binance for $typename {
       fn new(api_key: Option<String>, secret_key: Option<String>) -> Self {
         Self::new_with_config(api_key, secret_key, &Config::default())
@@ -63,6 +63,7 @@ macro_rules! impl_binance_for {
         $typename {
           client: inner_client,
           recv_window: config.recv_window,
+          $($field: $value,)*
         }
       }
     }
@@ -70,7 +71,7 @@ macro_rules! impl_binance_for {
 }
 
 impl_binance_for!(SpotAccountManagerV3);
-impl_binance_for!(SpotTradeV3Manager);
+impl_binance_for!(SpotTradeV3Manager, order_validator: None);
 impl_binance_for!(SpotMarketV3Manager);
 impl_binance_for!(GeneralManagerV3);
 impl_binance_for!(SpotUserStreamManagerV3);
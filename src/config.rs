@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 pub const REST_API_HOST: &str = "https://api.binance.com";
 const WS_HOST: &str = "wss://stream.binance.com/ws";
+const WS_API_HOST: &str = "wss://ws-api.binance.com:443/ws-api/v3";
 const FUTURES_REST_API_HOST: &str = "https://fapi.binance.com";
 const FUTURES_WS_HOST: &str = "wss://fstream.binance.com/ws";
 
@@ -11,6 +12,7 @@ const FUTURES_WS_HOST: &str = "wss://fstream.binance.com/ws";
 pub struct Config {
   pub rest_api_host: String,
   pub ws_host: String,
+  pub ws_api_host: String,
   pub futures_rest_api_host: String,
   pub futures_ws_host: String,
   pub recv_window: u64,
@@ -23,6 +25,7 @@ impl Default for Config {
     Self {
       rest_api_host: REST_API_HOST.into(),
       ws_host: WS_HOST.into(),
+      ws_api_host: WS_API_HOST.into(),
       futures_rest_api_host: FUTURES_REST_API_HOST.into(),
       futures_ws_host: FUTURES_WS_HOST.into(),
       recv_window: 5000,
@@ -37,6 +40,7 @@ impl Config {
     Self::default()
       .set_rest_api_endpoint("https://testnet.binance.vision")
       .set_ws_endpoint("wss://testnet.binance.vision/ws")
+      .set_ws_api_endpoint("wss://ws-api.testnet.binance.vision/ws-api/v3")
       .set_futures_rest_api_endpoint("https://testnet.binancefuture.com")
       .set_futures_ws_endpoint("https://testnet.binancefuture.com/ws")
   }
@@ -51,6 +55,11 @@ impl Config {
     self
   }
 
+  pub fn set_ws_api_endpoint<T: Into<String>>(mut self, ws_api_host: T) -> Self {
+    self.ws_api_host = ws_api_host.into();
+    self
+  }
+
   pub fn set_futures_rest_api_endpoint<T: Into<String>>(
     mut self,
     futures_rest_api_host: T,
@@ -14,4 +14,5 @@ pub mod rest;
 mod result;
 pub(crate) mod serde_helpers;
 mod util;
+pub mod websocket_api;
 pub mod websocket_stream;
@@ -3,15 +3,11 @@ use crate::rest::core::inner_client::rate_limit_manage::extract_and_update_rate_
 use crate::rest::core::inner_client::InnerClient;
 use crate::rest::endpoints::API;
 use crate::result::AnyhowResult;
+use crate::util::sign_query;
 use anyhow::bail;
-use hex::encode as hex_encode;
-use hmac::{Hmac, Mac};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, USER_AGENT};
 use reqwest::{Response, StatusCode};
 use serde::de::DeserializeOwned;
-use sha2::Sha256;
-
-type HmacSha256 = Hmac<Sha256>;
 
 impl InnerClient {
   pub async fn get_signed<T: DeserializeOwned>(
@@ -148,17 +144,9 @@ impl InnerClient {
   }
 
   fn build_signed_url(&self, endpoint: &API, query: Option<String>) -> String {
-    let mut signed_key =
-      HmacSha256::new_from_slice(self.secret_key.as_ref().unwrap().as_bytes()).unwrap();
-    let signed_query;
-    if let Some(params) = query {
-      signed_key.update(params.as_bytes());
-      let signature = hex_encode(signed_key.finalize().into_bytes());
-      signed_query = format!("{}&signature={}", params, signature);
-    } else {
-      let signature = hex_encode(signed_key.finalize().into_bytes());
-      signed_query = format!("&signature={}", signature);
-    }
+    let params = query.unwrap_or_default();
+    let signature = sign_query(self.secret_key.as_ref().unwrap(), &params).unwrap();
+    let signed_query = format!("{}&signature={}", params, signature);
 
     format!("{}{}?{}", self.server_host, endpoint.as_ref(), signed_query)
   }
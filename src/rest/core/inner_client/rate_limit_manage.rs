@@ -8,13 +8,35 @@ use reqwest::header::HeaderMap;
 use std::sync::Arc;
 
 /// Check if the endpoint is order-related (subject to order rate limiting)
+/// Test endpoints don't reach the matching engine and don't count as orders
 pub(crate) fn is_order_endpoint(endpoint: &API) -> bool {
   match endpoint {
-    API::SpotV3(spot_v3) => matches!(spot_v3, SpotV3::Order | SpotV3::OrderTest | SpotV3::Oco),
+    API::SpotV3(spot_v3) => matches!(
+      spot_v3,
+      SpotV3::Order
+        | SpotV3::Oco
+        | SpotV3::OrderListOco
+        | SpotV3::OrderListOto
+        | SpotV3::OrderListOtoco
+        | SpotV3::OrderCancelReplace
+        | SpotV3::SorOrder
+    ),
     _ => false,
   }
 }
 
+/// Number of orders a request to the endpoint places
+pub(crate) fn orders_per_request(endpoint: &API) -> u64 {
+  match endpoint {
+    API::SpotV3(spot_v3) => match spot_v3 {
+      SpotV3::Oco | SpotV3::OrderListOco | SpotV3::OrderListOto => 2,
+      SpotV3::OrderListOtoco => 3,
+      _ => 1,
+    },
+    _ => 1,
+  }
+}
+
 pub(crate) async fn extract_and_update_rate_limiter_counts(
   ip_rate_limit_manager: &Option<Arc<IpRateLimitManager>>,
   unfilled_order_rate_limit_manager: &Option<Arc<UnfilledOrderRateLimitManager>>,
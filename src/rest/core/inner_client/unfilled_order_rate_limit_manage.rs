@@ -1,4 +1,5 @@
 use crate::rest::core::inner_client::InnerClient;
+use crate::rest::core::inner_client::rate_limit_manage::{is_order_endpoint, orders_per_request};
 use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::{
   OrderIntervalAndNum, UnfilledOrderRateLimitManager,
 };
@@ -20,8 +21,12 @@ impl InnerClient {
   ) -> AnyhowResult<()> {
     self.acquire_ip_limit_permit(endpoint, query).await?;
 
-    if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager {
-      order_rate_limiter.acquire().await?;
+    if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager
+      && is_order_endpoint(endpoint)
+    {
+      order_rate_limiter
+        .acquire_orders(orders_per_request(endpoint))
+        .await?;
     }
 
     Ok(())
@@ -33,11 +38,11 @@ pub(crate) async fn extract_and_update_order_count(
   headers: &HeaderMap,
 ) -> AnyhowResult<()> {
   if let Some(unfilled_order_limit_manager) = unfilled_order_rate_limit_manager {
-    // Look for all headers starting with X-MBX-USED-WEIGHT
+    // Look for all headers starting with X-MBX-ORDER-COUNT
     for (header_name, header_value) in headers.iter() {
       let header_name_str = header_name.as_str().to_lowercase();
-      // Check for X-MBX-USED-WEIGHT-(intervalNum)(intervalLetter) pattern
-      if header_name_str.starts_with("X-mbx-order-count-") {
+      // Check for X-MBX-ORDER-COUNT-(intervalNum)(intervalLetter) pattern
+      if header_name_str.starts_with("x-mbx-order-count-") {
         // Extract the weight value from the header
         if let Ok(weight_str) = header_value.to_str() {
           if let Ok(weight) = weight_str.parse::<u64>() {
@@ -3,15 +3,19 @@ use crate::rest::core::rate_limiter::ip_rate_limit_manager::{
 };
 use crate::result::AnyhowResult;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::sync::Mutex;
 
 impl IpRateLimitManager {
   pub(crate) fn add_interval_and_limit(&mut self, interval: IpIntervalAndNum, weight_limit: u64) {
+    let window_index = interval.window_index_at(SystemTime::now());
+
     self.intervals.insert(
       interval,
       IpIntervalSetup {
         weight_limit: Arc::new(Mutex::new(weight_limit)),
         weight_count: Arc::new(Mutex::new(0)),
+        window_index: Arc::new(Mutex::new(window_index)),
       },
     );
   }
@@ -36,10 +40,26 @@ impl IpRateLimitManager {
     used_weight: u64,
   ) -> AnyhowResult<()> {
     if let Some(set) = self.intervals.get(interval) {
+      let mut window_index = set.window_index.lock().await;
       let mut ff = set.weight_count.lock().await;
-      *ff = used_weight
+      *ff = used_weight;
+      *window_index = interval.window_index_at(SystemTime::now());
     }
 
     Ok(())
   }
+
+  /// Reset the weight count once the interval window it was counted in has passed.
+  /// Binance resets the counters on window boundaries, headers only arrive with the next response
+  pub(crate) async fn roll_window(&self, interval: &IpIntervalAndNum) {
+    if let Some(set) = self.intervals.get(interval) {
+      let current_index = interval.window_index_at(SystemTime::now());
+      let mut window_index = set.window_index.lock().await;
+
+      if *window_index < current_index {
+        *set.weight_count.lock().await = 0;
+        *window_index = current_index;
+      }
+    }
+  }
 }
@@ -9,10 +9,11 @@ use crate::result::AnyhowResult;
 use anyhow::anyhow;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 
 pub mod manage;
+pub mod planner;
 pub mod read;
 pub mod weight_calculators;
 
@@ -63,12 +64,49 @@ impl IpIntervalAndNum {
       interval_num,
     })
   }
+
+  /// Length of the interval window, e.g. 1 minute for "1M"
+  pub fn duration(&self) -> Duration {
+    let unit_secs = match self.interval {
+      RateLimitIntervalResponse::Second => 1,
+      RateLimitIntervalResponse::Minute => 60,
+      RateLimitIntervalResponse::Day => 86_400,
+    };
+
+    Duration::from_secs(unit_secs * self.interval_num.max(1))
+  }
+
+  /// Index of the window the given time falls into.
+  /// Binance windows are aligned to the UNIX epoch, so the index changes on every reset
+  pub(crate) fn window_index_at(&self, time: SystemTime) -> u64 {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    (since_epoch.as_millis() / self.duration().as_millis()) as u64
+  }
+
+  /// Time left until the current window resets
+  pub fn time_until_reset(&self) -> Duration {
+    self.time_until_reset_at(SystemTime::now())
+  }
+
+  /// Time left at the given time until its window resets, a whole window on the boundary
+  pub(crate) fn time_until_reset_at(&self, time: SystemTime) -> Duration {
+    let since_epoch = time
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_millis();
+    let window = self.duration().as_millis();
+
+    Duration::from_millis((window - since_epoch % window) as u64)
+  }
 }
 
 #[derive(Default, Debug)]
 pub(crate) struct IpIntervalSetup {
   pub weight_limit: Arc<Mutex<u64>>,
   pub weight_count: Arc<Mutex<u64>>,
+  /// Window the weight count belongs to, see `IpIntervalAndNum::window_index_at`
+  pub window_index: Arc<Mutex<u64>>,
 }
 
 /// RateLimiter based on token bucket algorithm for Binance API
@@ -128,25 +166,118 @@ impl IpRateLimitManager {
   }
 
   /// Acquire permission to make a request with a certain weight based on endpoint and query
-  /// Returns Ok(()) if the request can proceed, or an error if rate limited
+  /// Returns Ok(()) if the request can proceed, or an error if rate limited.
+  ///
+  /// The weight has to fit into every interval, only then it is counted in all of them
   pub(crate) async fn acquire(&self, api: &API, query: Option<String>) -> AnyhowResult<()> {
-    let future_spent_weight = self.calc_endpoint_weight(api, query);
+    self
+      .acquire_weight(self.calc_endpoint_weight(api, query))
+      .await
+  }
 
-    for (key, interval_limit) in self.intervals.iter() {
-      let mut weight_count = interval_limit.weight_count.lock().await;
+  /// Acquire permission for a request of a known weight, such as a WebSocket API request
+  pub(crate) async fn acquire_weight(&self, future_spent_weight: u64) -> AnyhowResult<()> {
+    for key in self.intervals.keys() {
+      self.roll_window(key).await;
+    }
 
-      return if *weight_count < *interval_limit.weight_limit.lock().await {
-        *weight_count += future_spent_weight;
+    // Counts stay locked between the check and the charge, always in the same order
+    let mut weight_counts = Vec::with_capacity(self.intervals.len());
+    for (key, interval_limit) in self.intervals.iter() {
+      let weight_count = interval_limit.weight_count.lock().await;
 
-        Ok(())
-      } else {
-        Err(anyhow!(RateLimitError::LimitExceeded {
+      if *weight_count + future_spent_weight > *interval_limit.weight_limit.lock().await {
+        return Err(anyhow!(RateLimitError::LimitExceeded {
           interval: key.interval.to_string(),
           interval_num: key.interval_num,
-        }))
-      };
+        }));
+      }
+      weight_counts.push(weight_count);
+    }
+
+    for mut weight_count in weight_counts {
+      *weight_count += future_spent_weight;
     }
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  pub(crate) fn interval(
+    interval: RateLimitIntervalResponse,
+    interval_num: u64,
+  ) -> IpIntervalAndNum {
+    IpIntervalAndNum {
+      interval,
+      interval_num,
+    }
+  }
+
+  /// Manager without weight calculators, every call weighs 1
+  pub(crate) fn manager(limits: &[(RateLimitIntervalResponse, u64, u64)]) -> IpRateLimitManager {
+    let mut manager = IpRateLimitManager {
+      intervals: HashMap::new(),
+      last_updated_limits: Arc::new(Mutex::new(Instant::now())),
+      last_updated_used_count: Arc::new(Mutex::new(Instant::now())),
+      endpoint_weight_calculators: HashMap::new(),
+      default_calculators: HashMap::new(),
+    };
+    for (interval_type, interval_num, limit) in limits {
+      manager.add_interval_and_limit(interval(interval_type.clone(), *interval_num), *limit);
+    }
+    manager
+  }
+
+  fn at_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn indexes_windows_from_the_epoch() {
+    let minute = interval(RateLimitIntervalResponse::Minute, 1);
+    assert_eq!(minute.window_index_at(at_millis(0)), 0);
+    assert_eq!(minute.window_index_at(at_millis(59_999)), 0);
+    assert_eq!(minute.window_index_at(at_millis(60_000)), 1);
+    assert_eq!(minute.window_index_at(at_millis(150_000)), 2);
+
+    let five_minutes = interval(RateLimitIntervalResponse::Minute, 5);
+    assert_eq!(five_minutes.window_index_at(at_millis(299_999)), 0);
+    assert_eq!(five_minutes.window_index_at(at_millis(300_000)), 1);
+
+    let ten_seconds = interval(RateLimitIntervalResponse::Second, 10);
+    assert_eq!(ten_seconds.window_index_at(at_millis(25_000)), 2);
+
+    // Times before the epoch fall into the first window
+    assert_eq!(
+      minute.window_index_at(UNIX_EPOCH - Duration::from_secs(1)),
+      0
+    );
+  }
+
+  #[test]
+  fn counts_time_until_the_window_boundary() {
+    let minute = interval(RateLimitIntervalResponse::Minute, 1);
+    assert_eq!(
+      minute.time_until_reset_at(at_millis(60_000)),
+      Duration::from_secs(60)
+    );
+    assert_eq!(
+      minute.time_until_reset_at(at_millis(119_999)),
+      Duration::from_millis(1)
+    );
+    assert_eq!(
+      minute.time_until_reset_at(at_millis(61_500)),
+      Duration::from_millis(58_500)
+    );
+
+    let day = interval(RateLimitIntervalResponse::Day, 1);
+    assert_eq!(
+      day.time_until_reset_at(at_millis(86_400_000 + 3_600_000)),
+      Duration::from_secs(23 * 3_600)
+    );
+  }
+}
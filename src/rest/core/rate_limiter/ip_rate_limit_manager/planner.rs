@@ -0,0 +1,322 @@
+use crate::rest::core::rate_limiter::ip_rate_limit_manager::{
+  IpIntervalAndNum, IpRateLimitManager,
+};
+use crate::rest::endpoints::API;
+use crate::result::AnyhowResult;
+use anyhow::bail;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+/// A call that is planned to be made, used to estimate its cost before running it
+#[derive(Debug, Clone)]
+pub struct PlannedCall {
+  pub api: API,
+  pub query: Option<String>,
+}
+
+impl PlannedCall {
+  pub fn new(api: API, query: Option<String>) -> Self {
+    Self { api, query }
+  }
+}
+
+impl From<(API, Option<String>)> for PlannedCall {
+  fn from((api, query): (API, Option<String>)) -> Self {
+    Self::new(api, query)
+  }
+}
+
+/// Budget of the plan within one rate limit interval
+#[derive(Debug, Clone)]
+pub struct IntervalBudgetEstimate {
+  pub weight_limit: u64,
+  /// Weight already used in the current window
+  pub weight_count: u64,
+  /// Weight the plan will consume in this interval
+  pub planned_weight: u64,
+  /// Windows that have to reset before the plan fits, 0 if it fits in the current one
+  pub windows_to_wait: u64,
+  /// Time until the last call of the plan can be made under this interval
+  pub completion_in: Duration,
+}
+
+/// Estimated cost of running a plan under current limits
+#[derive(Debug, Clone)]
+pub struct RateBudgetEstimate {
+  pub total_weight: u64,
+  pub intervals: HashMap<IpIntervalAndNum, IntervalBudgetEstimate>,
+  /// Time until the last call of the plan can be made, the slowest interval wins
+  pub completion_in: Duration,
+  pub earliest_completion: SystemTime,
+}
+
+impl IpRateLimitManager {
+  /// Estimate weight per interval and the earliest completion time of the planned calls.
+  ///
+  /// Calls are assumed to be made one after another as soon as the limits allow it.
+  /// The time the requests themselves take is not included.
+  pub async fn estimate_plan(&self, plan: &[PlannedCall]) -> AnyhowResult<RateBudgetEstimate> {
+    let weights: Vec<u64> = plan
+      .iter()
+      .map(|call| self.calc_endpoint_weight(&call.api, call.query.clone()))
+      .collect();
+    let total_weight = weights.iter().sum();
+
+    let mut intervals = HashMap::new();
+    let mut completion_in = Duration::ZERO;
+
+    for (key, setup) in self.intervals.iter() {
+      self.roll_window(key).await;
+      let weight_limit = *setup.weight_limit.lock().await;
+      let weight_count = *setup.weight_count.lock().await;
+
+      // Fill the current window first, then every next one up to the limit
+      let mut used = weight_count;
+      let mut windows_to_wait = 0;
+      for weight in weights.iter() {
+        if *weight > weight_limit {
+          bail!(
+            "Call weight {} exceeds the limit {} per {} {}",
+            weight,
+            weight_limit,
+            key.interval_num,
+            key.interval
+          );
+        }
+        if used + weight > weight_limit {
+          windows_to_wait += 1;
+          used = 0;
+        }
+        used += weight;
+      }
+
+      let interval_completion_in = if windows_to_wait == 0 {
+        Duration::ZERO
+      } else {
+        key.time_until_reset() + key.duration() * (windows_to_wait - 1) as u32
+      };
+      completion_in = completion_in.max(interval_completion_in);
+
+      intervals.insert(
+        key.clone(),
+        IntervalBudgetEstimate {
+          weight_limit,
+          weight_count,
+          planned_weight: total_weight,
+          windows_to_wait,
+          completion_in: interval_completion_in,
+        },
+      );
+    }
+
+    Ok(RateBudgetEstimate {
+      total_weight,
+      intervals,
+      completion_in,
+      earliest_completion: SystemTime::now() + completion_in,
+    })
+  }
+
+  /// Run the planned calls one by one at the maximum rate the limits allow.
+  ///
+  /// Before each call the plan waits until its weight fits into every interval.
+  /// The weight is not reserved here: `call` has to use clients configured with this manager,
+  /// they count the weight when sending and correct it from the response headers.
+  /// Stops at the first failed call.
+  pub async fn execute_plan<F, Fut, T>(
+    &self,
+    plan: Vec<PlannedCall>,
+    mut call: F,
+  ) -> AnyhowResult<Vec<T>>
+  where
+    F: FnMut(PlannedCall) -> Fut,
+    Fut: Future<Output = AnyhowResult<T>>,
+  {
+    let mut results = Vec::with_capacity(plan.len());
+
+    for planned in plan {
+      let weight = self.calc_endpoint_weight(&planned.api, planned.query.clone());
+
+      loop {
+        let wait = self.wait_time_for_weight(weight).await?;
+        if wait.is_zero() {
+          break;
+        }
+        tokio::time::sleep(wait).await;
+      }
+
+      results.push(call(planned).await?);
+    }
+
+    Ok(results)
+  }
+
+  /// Time to wait until the weight fits into every interval, zero if it fits now
+  async fn wait_time_for_weight(&self, weight: u64) -> AnyhowResult<Duration> {
+    let mut wait = Duration::ZERO;
+
+    for (key, setup) in self.intervals.iter() {
+      self.roll_window(key).await;
+      let weight_limit = *setup.weight_limit.lock().await;
+      let weight_count = *setup.weight_count.lock().await;
+
+      if weight > weight_limit {
+        bail!(
+          "Call weight {} exceeds the limit {} per {} {}",
+          weight,
+          weight_limit,
+          key.interval_num,
+          key.interval
+        );
+      }
+      if weight_count + weight > weight_limit {
+        wait = wait.max(key.time_until_reset());
+      }
+    }
+
+    Ok(wait)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rest::core::rate_limiter::ip_rate_limit_manager::tests::{interval, manager};
+  use crate::rest::endpoints::SpotV3;
+  use crate::rest::spot::v3::market::responses::RateLimitIntervalResponse;
+  use anyhow::anyhow;
+  use std::time::Instant;
+
+  const DAY: u64 = 86_400;
+
+  fn plan(calls: usize) -> Vec<PlannedCall> {
+    (0..calls)
+      .map(|_| PlannedCall::new(API::SpotV3(SpotV3::Ping), None))
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn estimates_plans_fitting_the_current_window() {
+    let manager = manager(&[(RateLimitIntervalResponse::Day, 1, 10)]);
+    let day = interval(RateLimitIntervalResponse::Day, 1);
+    manager.set_weight_count(&day, 4).await.unwrap();
+
+    let estimate = manager.estimate_plan(&plan(6)).await.unwrap();
+    assert_eq!(estimate.total_weight, 6);
+    assert_eq!(estimate.completion_in, Duration::ZERO);
+    let budget = &estimate.intervals[&day];
+    assert_eq!(budget.weight_count, 4);
+    assert_eq!(budget.planned_weight, 6);
+    assert_eq!(budget.windows_to_wait, 0);
+  }
+
+  #[tokio::test]
+  async fn estimates_waits_across_several_windows() {
+    let manager = manager(&[
+      (RateLimitIntervalResponse::Day, 1, 10),
+      (RateLimitIntervalResponse::Minute, 1, 100),
+    ]);
+    let day = interval(RateLimitIntervalResponse::Day, 1);
+    manager.set_weight_count(&day, 8).await.unwrap();
+
+    // 2 calls fit the current window, 10 the next one and the last 3 the one after
+    let estimate = manager.estimate_plan(&plan(15)).await.unwrap();
+    let budget = &estimate.intervals[&day];
+    assert_eq!(budget.windows_to_wait, 2);
+    assert!(budget.completion_in > Duration::from_secs(DAY));
+    assert!(budget.completion_in <= Duration::from_secs(2 * DAY));
+
+    let minute = &estimate.intervals[&interval(RateLimitIntervalResponse::Minute, 1)];
+    assert_eq!(minute.windows_to_wait, 0);
+    assert_eq!(minute.completion_in, Duration::ZERO);
+
+    // The slowest interval decides
+    assert_eq!(estimate.completion_in, budget.completion_in);
+  }
+
+  #[tokio::test]
+  async fn rejects_calls_heavier_than_the_limit() {
+    let manager = manager(&[(RateLimitIntervalResponse::Day, 1, 0)]);
+
+    assert!(manager.estimate_plan(&plan(1)).await.is_err());
+    assert!(manager.wait_time_for_weight(1).await.is_err());
+
+    let mut calls = 0;
+    let result = manager
+      .execute_plan(plan(1), |_| {
+        calls += 1;
+        async { Ok(()) }
+      })
+      .await;
+    assert!(result.is_err());
+    assert_eq!(calls, 0);
+  }
+
+  #[tokio::test]
+  async fn waits_for_the_slowest_interval() {
+    let manager = manager(&[
+      (RateLimitIntervalResponse::Day, 1, 10),
+      (RateLimitIntervalResponse::Minute, 1, 10),
+    ]);
+    let day = interval(RateLimitIntervalResponse::Day, 1);
+    let minute = interval(RateLimitIntervalResponse::Minute, 1);
+    manager.set_weight_count(&day, 8).await.unwrap();
+
+    assert_eq!(
+      manager.wait_time_for_weight(2).await.unwrap(),
+      Duration::ZERO
+    );
+
+    manager.set_weight_count(&minute, 10).await.unwrap();
+    let wait = manager.wait_time_for_weight(2).await.unwrap();
+    assert!(wait > Duration::ZERO && wait <= Duration::from_secs(60));
+
+    let wait = manager.wait_time_for_weight(3).await.unwrap();
+    assert!(wait > Duration::from_secs(60) && wait <= Duration::from_secs(DAY));
+  }
+
+  #[tokio::test]
+  async fn executes_plans_as_fast_as_the_limits_allow() {
+    let manager = manager(&[(RateLimitIntervalResponse::Second, 1, 2)]);
+    let started = Instant::now();
+    let first_reset = interval(RateLimitIntervalResponse::Second, 1).time_until_reset();
+
+    // The calls count their weight like clients configured with the manager
+    let results = manager
+      .execute_plan(plan(3), |planned| {
+        let manager = &manager;
+        async move {
+          manager.acquire(&planned.api, planned.query).await?;
+          Ok(started.elapsed())
+        }
+      })
+      .await
+      .unwrap();
+
+    // Only 2 calls fit a window, the third one waited for the reset
+    assert_eq!(results.len(), 3);
+    assert!(results[2] >= first_reset - Duration::from_millis(1));
+  }
+
+  #[tokio::test]
+  async fn stops_plans_at_the_first_failed_call() {
+    let manager = manager(&[(RateLimitIntervalResponse::Day, 1, 10)]);
+
+    let mut calls = 0;
+    let result: AnyhowResult<Vec<()>> = manager
+      .execute_plan(plan(3), |_| {
+        calls += 1;
+        let failed = calls == 2;
+        async move {
+          if failed {
+            return Err(anyhow!("call failed"));
+          }
+          Ok(())
+        }
+      })
+      .await;
+    assert!(result.is_err());
+    assert_eq!(calls, 2);
+  }
+}
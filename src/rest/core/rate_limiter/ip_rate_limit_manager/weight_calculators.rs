@@ -42,21 +42,30 @@ impl IpRateLimitManager {
         }
       });
 
-    // Ticker24hr - weight depends on if symbol is specified
+    // Ticker24hr - 2 for up to 20 symbols, 40 for up to 100, 80 above or for all symbols
     self
       .endpoint_weight_calculators
-      .insert(API::SpotV3(SpotV3::Ticker24hr), |_, query| {
-        if let Some(query_str) = query {
-          // If symbol is specified, weight is 1
-          if query_str.contains("symbol=") {
-            1
-          } else {
-            // Without symbol, or with symbols parameter, weight is 40
-            40
-          }
-        } else {
-          40 // Default to higher weight if no query
-        }
+      .insert(
+        API::SpotV3(SpotV3::Ticker24hr),
+        |_, query| match Self::requested_symbols(query) {
+          Some(1..=20) => 2,
+          Some(21..=100) => 40,
+          _ => 80,
+        },
+      );
+
+    // Ticker (rolling window) - 4 per symbol, at most 200
+    self
+      .endpoint_weight_calculators
+      .insert(API::SpotV3(SpotV3::Ticker), |_, query| {
+        Self::symbols_count(query) * 4
+      });
+
+    // TickerTradingDay - 4 per symbol, at most 200
+    self
+      .endpoint_weight_calculators
+      .insert(API::SpotV3(SpotV3::TickerTradingDay), |_, query| {
+        Self::symbols_count(query) * 4
       });
 
     // Price - weight depends on if symbol is specified
@@ -209,12 +218,15 @@ impl IpRateLimitManager {
             SpotV3::HistoricalTrades => 5,
             SpotV3::AggTrades => 1,
             SpotV3::Klines => 1,
+            SpotV3::UiKlines => 2,
             SpotV3::AvgPrice => 1,
 
             // These endpoints have dedicated calculators for accurate weight calculation
             // but we provide defaults here as fallback
             SpotV3::Depth => 5,
-            SpotV3::Ticker24hr => 40,
+            SpotV3::Ticker24hr => 80,
+            SpotV3::Ticker => 4,
+            SpotV3::TickerTradingDay => 4,
             SpotV3::Price => 2,
             SpotV3::BookTicker => 2,
 
@@ -224,6 +236,12 @@ impl IpRateLimitManager {
             SpotV3::OpenOrders => 3, // 3 with no symbol, 1 with symbol
             SpotV3::AllOrders => 10,
             SpotV3::Oco => 1,
+            SpotV3::OrderListOco => 1,
+            SpotV3::OrderListOto => 1,
+            SpotV3::OrderListOtoco => 1,
+            SpotV3::OrderCancelReplace => 1,
+            SpotV3::SorOrder => 1,
+            SpotV3::SorOrderTest => 1,
             SpotV3::OrderList => 2,
             SpotV3::AllOrderList => 10,
             SpotV3::OpenOrderList => 3,
@@ -341,6 +359,20 @@ impl IpRateLimitManager {
       .insert("AccountGeneral".to_string(), account_general_calculator);
   }
 
+  /// Number of symbols of a "symbol" or "symbols" query, up to 50
+  fn symbols_count(query: Option<String>) -> u64 {
+    Self::requested_symbols(query).unwrap_or(1).min(50)
+  }
+
+  /// Number of symbols of a "symbol" or "symbols" query, `None` if it has neither
+  fn requested_symbols(query: Option<String>) -> Option<u64> {
+    let query = query?;
+    if let Some(symbols) = Self::extract_param(query.clone(), "symbols") {
+      return Some(symbols.split(',').count() as u64);
+    }
+    Self::extract_param(query, "symbol").map(|_| 1)
+  }
+
   /// Helper function to extract a parameter value from a query string
   fn extract_param(query: String, param_name: &str) -> Option<String> {
     let param_prefix = format!("{}=", param_name);
@@ -1,17 +1,21 @@
 use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::{
-  OrderIntervalAndNum, OrderRateLimitInterval, UnfilledOrderRateLimitManager,
+  OrderExecution, OrderIntervalAndNum, OrderRateLimitInterval, UnfilledOrderRateLimitManager,
 };
 use crate::result::AnyhowResult;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::sync::Mutex;
 
 impl UnfilledOrderRateLimitManager {
   pub fn add_interval_and_limit(&mut self, interval: OrderIntervalAndNum, limit: u64) {
+    let window_index = interval.window_index_at(SystemTime::now());
+
     self.intervals.insert(
       interval,
       OrderRateLimitInterval {
         order_limit: Arc::new(Mutex::new(limit)),
         order_count: Arc::new(Mutex::new(0)),
+        window_index: Arc::new(Mutex::new(window_index)),
       },
     );
   }
@@ -36,10 +40,46 @@ impl UnfilledOrderRateLimitManager {
     used_weight: u64,
   ) -> AnyhowResult<()> {
     if let Some(set) = self.intervals.get(interval) {
+      let mut window_index = set.window_index.lock().await;
       let mut ff = set.order_count.lock().await;
-      *ff = used_weight
+      *ff = used_weight;
+      *window_index = interval.window_index_at(SystemTime::now());
     }
 
     Ok(())
   }
+
+  /// Decrement the unfilled order count of every interval.
+  /// Binance does so when an order gets its first fill
+  pub async fn release_filled_order(&self) {
+    for (key, set) in self.intervals.iter() {
+      self.roll_window(key).await;
+      let mut count = set.order_count.lock().await;
+      *count = count.saturating_sub(1);
+    }
+  }
+
+  /// Update counts from an `executionReport` of the user data stream.
+  /// Only the first fill of an order releases it from the unfilled count
+  pub async fn handle_execution_report(&self, execution: &OrderExecution) {
+    if execution.execution_type == "TRADE"
+      && execution.last_filled_qty > 0.0
+      && execution.last_filled_qty >= execution.accumulated_filled_qty
+    {
+      self.release_filled_order().await;
+    }
+  }
+
+  /// Reset the order count once the interval window it was counted in has passed
+  pub(crate) async fn roll_window(&self, interval: &OrderIntervalAndNum) {
+    if let Some(set) = self.intervals.get(interval) {
+      let current_index = interval.window_index_at(SystemTime::now());
+      let mut window_index = set.window_index.lock().await;
+
+      if *window_index < current_index {
+        *set.order_count.lock().await = 0;
+        *window_index = current_index;
+      }
+    }
+  }
 }
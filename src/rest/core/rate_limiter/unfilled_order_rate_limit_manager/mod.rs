@@ -11,6 +11,7 @@ use crate::util::build_signed_query;
 use anyhow::anyhow;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use tokio::time::Instant;
 
@@ -61,12 +62,42 @@ impl OrderIntervalAndNum {
       interval_num,
     })
   }
+
+  /// Length of the interval window, e.g. 10 seconds for "10S"
+  pub fn duration(&self) -> Duration {
+    let unit_secs = match self.interval {
+      AccountRateLimitIntervalResponse::Second => 1,
+      AccountRateLimitIntervalResponse::Minute => 60,
+      AccountRateLimitIntervalResponse::Day => 86_400,
+    };
+
+    Duration::from_secs(unit_secs * self.interval_num.max(1))
+  }
+
+  /// Index of the window the given time falls into.
+  /// Windows are aligned to the UNIX epoch, so "1D" resets at 00:00 UTC
+  pub(crate) fn window_index_at(&self, time: SystemTime) -> u64 {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    (since_epoch.as_millis() / self.duration().as_millis()) as u64
+  }
+}
+
+/// Fill of an order as reported by an `executionReport` of the user data stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderExecution {
+  /// Execution type, e.g. "NEW" or "TRADE"
+  pub execution_type: String,
+  pub last_filled_qty: f64,
+  pub accumulated_filled_qty: f64,
 }
 
 #[derive(Default, Debug)]
 pub struct OrderRateLimitInterval {
   order_limit: Arc<Mutex<u64>>,
   order_count: Arc<Mutex<u64>>,
+  /// Window the order count belongs to, see `OrderIntervalAndNum::window_index_at`
+  window_index: Arc<Mutex<u64>>,
 }
 
 /// UnfilledOrderRateLimitManager handles Binance's multi-tier order rate limits:
@@ -118,13 +149,14 @@ impl UnfilledOrderRateLimitManager {
 
     for rate_limit in account_order_rate_limits {
       if rate_limit.rate_limit_type.eq("ORDERS") {
-        unwrapped.add_interval_and_limit(
-          OrderIntervalAndNum {
-            interval: rate_limit.interval.clone(),
-            interval_num: rate_limit.interval_num,
-          },
-          rate_limit.limit,
-        );
+        let interval = OrderIntervalAndNum {
+          interval: rate_limit.interval.clone(),
+          interval_num: rate_limit.interval_num,
+        };
+        unwrapped.add_interval_and_limit(interval.clone(), rate_limit.limit);
+        unwrapped
+          .set_order_count(&interval, rate_limit.count)
+          .await?;
       }
     }
 
@@ -132,6 +164,7 @@ impl UnfilledOrderRateLimitManager {
   }
 
   pub async fn orders_this_period(&self, interval: &OrderIntervalAndNum) -> AnyhowResult<u64> {
+    self.roll_window(interval).await;
     let count_rates = self.intervals.get(interval);
 
     if let Some(count_rates) = count_rates {
@@ -144,21 +177,119 @@ impl UnfilledOrderRateLimitManager {
   /// Acquire permission to place an order
   /// Returns Ok(()) if the order can proceed, or an error if rate limited
   pub async fn acquire(&self) -> AnyhowResult<()> {
-    for (key, interval_limit) in self.intervals.iter() {
-      let mut count = interval_limit.order_count.lock().await;
+    self.acquire_orders(1).await
+  }
 
-      return if *count < *interval_limit.order_limit.lock().await {
-        *count += 1;
+  /// Acquire permission to place several orders at once, e.g. an OCO order list
+  /// Counts are only increased if every interval has room for all of them
+  pub async fn acquire_orders(&self, orders: u64) -> AnyhowResult<()> {
+    for key in self.intervals.keys() {
+      self.roll_window(key).await;
+    }
 
-        Ok(())
-      } else {
-        Err(anyhow!(RateLimitError::LimitExceeded {
+    // Counts stay locked between the check and the charge, always in the same order
+    let mut order_counts = Vec::with_capacity(self.intervals.len());
+    for (key, interval_limit) in self.intervals.iter() {
+      let order_count = interval_limit.order_count.lock().await;
+
+      if *order_count + orders > *interval_limit.order_limit.lock().await {
+        return Err(anyhow!(RateLimitError::LimitExceeded {
           interval: key.interval.to_string(),
           interval_num: key.interval_num,
-        }))
-      };
+        }));
+      }
+      order_counts.push(order_count);
+    }
+
+    for mut order_count in order_counts {
+      *order_count += orders;
     }
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn manager(
+    limits: &[(AccountRateLimitIntervalResponse, u64, u64)],
+  ) -> UnfilledOrderRateLimitManager {
+    let mut manager = UnfilledOrderRateLimitManager {
+      intervals: HashMap::new(),
+      last_updated_limits: Arc::new(Mutex::new(Instant::now())),
+      last_updated_used_count: Arc::new(Mutex::new(Instant::now())),
+    };
+    for (interval, interval_num, limit) in limits {
+      manager.add_interval_and_limit(
+        OrderIntervalAndNum {
+          interval: interval.clone(),
+          interval_num: *interval_num,
+        },
+        *limit,
+      );
+    }
+    manager
+  }
+
+  #[tokio::test]
+  async fn counts_orders_only_if_every_interval_has_room() {
+    let manager = manager(&[
+      (AccountRateLimitIntervalResponse::Second, 10, 5),
+      (AccountRateLimitIntervalResponse::Day, 1, 3),
+    ]);
+    let ten_seconds = OrderIntervalAndNum {
+      interval: AccountRateLimitIntervalResponse::Second,
+      interval_num: 10,
+    };
+
+    manager.acquire_orders(2).await.unwrap();
+    assert!(manager.acquire_orders(2).await.is_err());
+    assert_eq!(manager.orders_this_period(&ten_seconds).await.unwrap(), 2);
+    manager.acquire().await.unwrap();
+    assert!(manager.acquire().await.is_err());
+    assert_eq!(manager.orders_this_period(&ten_seconds).await.unwrap(), 3);
+  }
+
+  #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+  async fn concurrent_orders_stay_within_the_limit() {
+    let day = OrderIntervalAndNum {
+      interval: AccountRateLimitIntervalResponse::Day,
+      interval_num: 1,
+    };
+    let manager = Arc::new(manager(&[
+      (AccountRateLimitIntervalResponse::Second, 10, 1_000),
+      (AccountRateLimitIntervalResponse::Minute, 1, 1_000),
+      (AccountRateLimitIntervalResponse::Day, 1, 50),
+    ]));
+
+    // Every order waits at the last interval, so all of them are in flight at once
+    let last_count = manager
+      .intervals
+      .values()
+      .last()
+      .unwrap()
+      .order_count
+      .lock()
+      .await;
+    let tasks: Vec<_> = (0..200)
+      .map(|_| {
+        let manager = manager.clone();
+        tokio::spawn(async move { manager.acquire().await.is_ok() })
+      })
+      .collect();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(last_count);
+
+    let mut acquired = 0;
+    for task in tasks {
+      if task.await.unwrap() {
+        acquired += 1;
+      }
+    }
+
+    assert_eq!(acquired, 50);
+    assert_eq!(manager.orders_this_period(&day).await.unwrap(), 50);
+  }
+}
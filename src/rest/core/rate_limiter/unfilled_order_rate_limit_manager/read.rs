@@ -14,6 +14,7 @@ pub struct OrderRateIntervalSetup {
 impl UnfilledOrderRateLimitManager {
   /// Get the weight count for a specific interval in this period
   pub async fn order_count_this_period(&self, interval: &OrderIntervalAndNum) -> AnyhowResult<u64> {
+    self.roll_window(interval).await;
     let count_rates = self.intervals.get(interval);
 
     if let Some(count_rates) = count_rates {
@@ -28,7 +29,7 @@ impl UnfilledOrderRateLimitManager {
     let count_rates = self.intervals.get(interval);
 
     if let Some(count_rates) = count_rates {
-      Ok(*count_rates.order_count.lock().await)
+      Ok(*count_rates.order_limit.lock().await)
     } else {
       Err(anyhow!("Interval not found"))
     }
@@ -46,6 +47,7 @@ impl UnfilledOrderRateLimitManager {
     let mut map = HashMap::new();
 
     for (key, value) in self.intervals.iter() {
+      self.roll_window(key).await;
       map.insert(
         key.clone(),
         OrderRateIntervalSetup {
@@ -1,4 +1,4 @@
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum API {
   SpotV3(SpotV3),
   Savings(Savings),
@@ -19,8 +19,11 @@ pub enum SpotV3 {
   HistoricalTrades,
   AggTrades,
   Klines,
+  UiKlines,
   AvgPrice,
   Ticker24hr,
+  Ticker,
+  TickerTradingDay,
   Price,
   BookTicker,
   Order,
@@ -28,6 +31,12 @@ pub enum SpotV3 {
   OpenOrders,
   AllOrders,
   Oco,
+  OrderListOco,
+  OrderListOto,
+  OrderListOtoco,
+  OrderCancelReplace,
+  SorOrder,
+  SorOrderTest,
   OrderList,
   AllOrderList,
   OpenOrderList,
@@ -40,7 +49,7 @@ pub enum SpotV3 {
   UserDataStream,
 }
 
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum Savings {
   AllCoins,
   AssetDetail,
@@ -48,7 +57,7 @@ pub enum Savings {
   SpotFuturesTransfer,
 }
 
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum Futures {
   Ping,
   Time,
@@ -91,7 +100,7 @@ pub enum Futures {
   Income,
 }
 
-#[derive(Eq, Hash, PartialEq, Debug)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum AccountGeneral {
   ApiRestrictions,
 }
@@ -108,8 +117,11 @@ impl AsRef<str> for API {
         SpotV3::HistoricalTrades => "/api/v3/historicalTrades",
         SpotV3::AggTrades => "/api/v3/aggTrades",
         SpotV3::Klines => "/api/v3/klines",
+        SpotV3::UiKlines => "/api/v3/uiKlines",
         SpotV3::AvgPrice => "/api/v3/avgPrice",
         SpotV3::Ticker24hr => "/api/v3/ticker/24hr",
+        SpotV3::Ticker => "/api/v3/ticker",
+        SpotV3::TickerTradingDay => "/api/v3/ticker/tradingDay",
         SpotV3::Price => "/api/v3/ticker/price",
         SpotV3::BookTicker => "/api/v3/ticker/bookTicker",
         SpotV3::Order => "/api/v3/order",
@@ -117,6 +129,12 @@ impl AsRef<str> for API {
         SpotV3::OpenOrders => "/api/v3/openOrders",
         SpotV3::AllOrders => "/api/v3/allOrders",
         SpotV3::Oco => "/api/v3/order/oco",
+        SpotV3::OrderListOco => "/api/v3/orderList/oco",
+        SpotV3::OrderListOto => "/api/v3/orderList/oto",
+        SpotV3::OrderListOtoco => "/api/v3/orderList/otoco",
+        SpotV3::OrderCancelReplace => "/api/v3/order/cancelReplace",
+        SpotV3::SorOrder => "/api/v3/sor/order",
+        SpotV3::SorOrderTest => "/api/v3/sor/order/test",
         SpotV3::OrderList => "/api/v3/orderList",
         SpotV3::AllOrderList => "/api/v3/allOrderList",
         SpotV3::OpenOrderList => "/api/v3/openOrderList",
@@ -3,6 +3,6 @@ pub mod futures;
 pub mod savings;
 pub mod spot;
 
-mod endpoints;
+pub mod endpoints;
 
 pub mod core;
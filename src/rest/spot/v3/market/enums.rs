@@ -1,4 +1,5 @@
 use crate::create_enum_with_fmt;
+use std::fmt;
 
 create_enum_with_fmt!(KlineInterval, {
   Sec1 => "1s",
@@ -19,6 +20,31 @@ create_enum_with_fmt!(KlineInterval, {
   Month1 => "1M",
 });
 
+impl KlineInterval {
+  /// Length of a candle in milliseconds, the shortest month (28 days) for `Month1`
+  pub fn duration_ms(&self) -> u64 {
+    const MINUTE: u64 = 60_000;
+    match self {
+      KlineInterval::Sec1 => 1_000,
+      KlineInterval::Min1 => MINUTE,
+      KlineInterval::Min3 => 3 * MINUTE,
+      KlineInterval::Min5 => 5 * MINUTE,
+      KlineInterval::Min15 => 15 * MINUTE,
+      KlineInterval::Min30 => 30 * MINUTE,
+      KlineInterval::Hour1 => 60 * MINUTE,
+      KlineInterval::Hour2 => 2 * 60 * MINUTE,
+      KlineInterval::Hour4 => 4 * 60 * MINUTE,
+      KlineInterval::Hour6 => 6 * 60 * MINUTE,
+      KlineInterval::Hour8 => 8 * 60 * MINUTE,
+      KlineInterval::Hour12 => 12 * 60 * MINUTE,
+      KlineInterval::Day1 => 24 * 60 * MINUTE,
+      KlineInterval::Day3 => 3 * 24 * 60 * MINUTE,
+      KlineInterval::Week1 => 7 * 24 * 60 * MINUTE,
+      KlineInterval::Month1 => 28 * 24 * 60 * MINUTE,
+    }
+  }
+}
+
 create_enum_with_fmt!(ExchangeSymbolPermission, {
      Spot => "SPOT",
    Margin => "MARGIN",
@@ -50,8 +76,55 @@ TrdGrp025 => "TRD_GRP_025",
 });
 
 create_enum_with_fmt!(ExchangeSymbolStatus, {
-   Trading => "TRADING",
-EndOfDay => "END_OF_DAY",
-      Halt => "HALT",
-     Break => "BREAK",
+     Trading => "TRADING",
+    EndOfDay => "END_OF_DAY",
+        Halt => "HALT",
+       Break => "BREAK",
+  PreTrading => "PRE_TRADING",
+ PostTrading => "POST_TRADING",
+AuctionMatch => "AUCTION_MATCH",
+});
+
+create_enum_with_fmt!(TickerType, {
+Full => "FULL",
+Mini => "MINI",
 });
+
+/// Window of the rolling window price change statistics, `windowSize`.
+/// Streams only offer the windows of `websocket_stream::spot::enums::TickerWindowSize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollingWindowSize {
+  /// 1 to 59 minutes
+  Minutes(u8),
+  /// 1 to 23 hours
+  Hours(u8),
+  /// 1 to 7 days
+  Days(u8),
+}
+
+impl RollingWindowSize {
+  /// Whether Binance accepts the window
+  pub fn is_valid(&self) -> bool {
+    match self {
+      RollingWindowSize::Minutes(m) => (1..=59).contains(m),
+      RollingWindowSize::Hours(h) => (1..=23).contains(h),
+      RollingWindowSize::Days(d) => (1..=7).contains(d),
+    }
+  }
+}
+
+impl Default for RollingWindowSize {
+  fn default() -> Self {
+    RollingWindowSize::Days(1)
+  }
+}
+
+impl fmt::Display for RollingWindowSize {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RollingWindowSize::Minutes(m) => write!(f, "{}m", m),
+      RollingWindowSize::Hours(h) => write!(f, "{}h", h),
+      RollingWindowSize::Days(d) => write!(f, "{}d", d),
+    }
+  }
+}
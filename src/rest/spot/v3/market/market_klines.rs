@@ -1,10 +1,15 @@
-use super::SpotMarketV3Manager;
+use super::{flatten_pages, SpotMarketV3Manager};
 use crate::rest::endpoints::{SpotV3, API};
 use crate::rest::spot::v3::market::enums::KlineInterval;
 use crate::rest::spot::v3::market::requests::KlinesRequest;
 use crate::rest::spot::v3::market::responses::KlineSummaryResponse;
 use crate::util::build_query;
 use anyhow::Result;
+use futures_util::{future, stream, Stream, StreamExt};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most klines Binance returns per request
+const KLINES_PAGE_LIMIT: u64 = 1000;
 
 impl SpotMarketV3Manager {
   /// Returns klines for given symbol and interval ("1m", "5m", ...)
@@ -43,4 +48,91 @@ impl SpotMarketV3Manager {
       .get(API::SpotV3(SpotV3::Klines), Some(query))
       .await
   }
+
+  /// Klines modified for presentation of candlestick charts, same parameters and response as klines
+  pub async fn list_ui_klines_custom(
+    &self,
+    request: KlinesRequest,
+  ) -> Result<Vec<KlineSummaryResponse>> {
+    let params_btree = request.build_params_bree();
+    let query = build_query(params_btree);
+
+    self
+      .client
+      .get(API::SpotV3(SpotV3::UiKlines), Some(query))
+      .await
+  }
+
+  /// Klines of the symbol with an open time in the range, oldest first,
+  /// one request of up to 1000 klines at a time.
+  ///
+  /// - `end_time` defaults to now
+  pub fn stream_klines<S>(
+    &self,
+    symbol: S,
+    interval: KlineInterval,
+    start_time: u64,
+    end_time: Option<u64>,
+  ) -> impl Stream<Item = Result<KlineSummaryResponse>> + Send + 'static
+  where
+    S: Into<String>,
+  {
+    self.stream_klines_concurrent(symbol, interval, start_time, end_time, 1)
+  }
+
+  /// Klines of the symbol with an open time in the range, oldest first.
+  ///
+  /// The range is split into chunks of 1000 klines, up to `concurrency` chunks are
+  /// requested at once and delivered in order. Requests go through the rate limiter.
+  /// The stream ends after the first failed request.
+  /// - `end_time` defaults to now
+  pub fn stream_klines_concurrent<S>(
+    &self,
+    symbol: S,
+    interval: KlineInterval,
+    start_time: u64,
+    end_time: Option<u64>,
+    concurrency: usize,
+  ) -> impl Stream<Item = Result<KlineSummaryResponse>> + Send + 'static
+  where
+    S: Into<String>,
+  {
+    let manager = self.clone();
+    let symbol = symbol.into();
+    let end_time = end_time.unwrap_or_else(|| {
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+    });
+
+    // A chunk can't hold more klines than fit into one request
+    let chunk_length = interval.duration_ms() * KLINES_PAGE_LIMIT;
+    let chunks = (start_time..=end_time)
+      .step_by(chunk_length as usize)
+      .map(move |chunk_start| (chunk_start, (chunk_start + chunk_length - 1).min(end_time)));
+
+    let pages = stream::iter(chunks)
+      .map(move |(chunk_start, chunk_end)| {
+        let manager = manager.clone();
+        let request = KlinesRequest {
+          symbol: symbol.clone(),
+          interval,
+          start_time: Some(chunk_start),
+          end_time: Some(chunk_end),
+          limit: Some(KLINES_PAGE_LIMIT as u16),
+        };
+        async move { manager.list_klines_custom(request).await }
+      })
+      .buffered(concurrency.max(1))
+      .scan(false, |failed, page| {
+        if *failed {
+          return future::ready(None);
+        }
+        *failed = page.is_err();
+        future::ready(Some(page))
+      });
+
+    flatten_pages(pages)
+  }
 }
@@ -1,10 +1,11 @@
 use super::SpotMarketV3Manager;
 use crate::rest::endpoints::{SpotV3, API};
+use crate::rest::spot::v3::market::enums::{RollingWindowSize, TickerType};
 use crate::rest::spot::v3::market::responses::{
-  BookTickerResponse, BookTickersMultiResponse, TickerDaySummaryResponse,
+  BookTickerResponse, BookTickersMultiResponse, TickerDaySummaryResponse, TickerStatsResponse,
 };
 use crate::util::{build_query, vec_to_string_array};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::collections::BTreeMap;
 
 impl SpotMarketV3Manager {
@@ -63,4 +64,148 @@ impl SpotMarketV3Manager {
   pub async fn list_all_tickers_day_stats(&self) -> Result<Vec<TickerDaySummaryResponse>> {
     self.client.get(API::SpotV3(SpotV3::Ticker24hr), None).await
   }
+
+  // 24hr ticker price change statistics of the FULL or MINI type
+  pub async fn fetch_ticker_day_stats_typed<S>(
+    &self,
+    symbol: S,
+    ticker_type: TickerType,
+  ) -> Result<TickerStatsResponse>
+  where
+    S: Into<String>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.into());
+    parameters.insert("type".into(), ticker_type.to_string());
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::Ticker24hr), Some(request))
+      .await
+  }
+
+  // 24hr ticker price change statistics of MULTI symbols, FULL or MINI type
+  pub async fn list_tickers_day_stats_multi<S>(
+    &self,
+    symbols: S,
+    ticker_type: TickerType,
+  ) -> Result<Vec<TickerStatsResponse>>
+  where
+    S: Into<Vec<String>>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbols".into(), vec_to_string_array(symbols.into()));
+    parameters.insert("type".into(), ticker_type.to_string());
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::Ticker24hr), Some(request))
+      .await
+  }
+
+  /// Rolling window price change statistics for ONE symbol,
+  /// e.g. `RollingWindowSize::Hours(4)` or `RollingWindowSize::Days(7)`
+  pub async fn fetch_ticker_window_stats<S>(
+    &self,
+    symbol: S,
+    window_size: RollingWindowSize,
+    ticker_type: TickerType,
+  ) -> Result<TickerStatsResponse>
+  where
+    S: Into<String>,
+  {
+    let mut parameters = window_parameters(window_size, ticker_type)?;
+    parameters.insert("symbol".into(), symbol.into());
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::Ticker), Some(request))
+      .await
+  }
+
+  /// Rolling window price change statistics for MULTI symbols, up to 100
+  pub async fn list_tickers_window_stats_multi<S>(
+    &self,
+    symbols: S,
+    window_size: RollingWindowSize,
+    ticker_type: TickerType,
+  ) -> Result<Vec<TickerStatsResponse>>
+  where
+    S: Into<Vec<String>>,
+  {
+    let mut parameters = window_parameters(window_size, ticker_type)?;
+    parameters.insert("symbols".into(), vec_to_string_array(symbols.into()));
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::Ticker), Some(request))
+      .await
+  }
+
+  /// Price change statistics of the current trading day for ONE symbol.
+  /// - `time_zone` e.g. "-1:00" or "05:45", UTC by default
+  pub async fn fetch_ticker_trading_day_stats<S, TZ>(
+    &self,
+    symbol: S,
+    time_zone: TZ,
+    ticker_type: TickerType,
+  ) -> Result<TickerStatsResponse>
+  where
+    S: Into<String>,
+    TZ: Into<Option<String>>,
+  {
+    let mut parameters = trading_day_parameters(time_zone.into(), ticker_type);
+    parameters.insert("symbol".into(), symbol.into());
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::TickerTradingDay), Some(request))
+      .await
+  }
+
+  /// Price change statistics of the current trading day for MULTI symbols, up to 100
+  pub async fn list_tickers_trading_day_stats_multi<S, TZ>(
+    &self,
+    symbols: S,
+    time_zone: TZ,
+    ticker_type: TickerType,
+  ) -> Result<Vec<TickerStatsResponse>>
+  where
+    S: Into<Vec<String>>,
+    TZ: Into<Option<String>>,
+  {
+    let mut parameters = trading_day_parameters(time_zone.into(), ticker_type);
+    parameters.insert("symbols".into(), vec_to_string_array(symbols.into()));
+    let request = build_query(parameters);
+    self
+      .client
+      .get(API::SpotV3(SpotV3::TickerTradingDay), Some(request))
+      .await
+  }
+}
+
+fn window_parameters(
+  window_size: RollingWindowSize,
+  ticker_type: TickerType,
+) -> Result<BTreeMap<String, String>> {
+  if !window_size.is_valid() {
+    bail!("Invalid ticker window size {}", window_size);
+  }
+
+  let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+  parameters.insert("windowSize".into(), window_size.to_string());
+  parameters.insert("type".into(), ticker_type.to_string());
+  Ok(parameters)
+}
+
+fn trading_day_parameters(
+  time_zone: Option<String>,
+  ticker_type: TickerType,
+) -> BTreeMap<String, String> {
+  let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+  if let Some(tz) = time_zone {
+    parameters.insert("timeZone".into(), tz);
+  }
+  parameters.insert("type".into(), ticker_type.to_string());
+  parameters
 }
@@ -1,10 +1,14 @@
-use super::SpotMarketV3Manager;
+use super::{flatten_pages, SpotMarketV3Manager};
 use crate::rest::endpoints::{SpotV3, API};
 use crate::rest::spot::v3::trade::responses::TradeRecordResponse;
 use crate::util::build_query;
 use anyhow::Result;
+use futures_util::{stream, Stream};
 use std::collections::BTreeMap;
 
+/// Most trades Binance returns per historical trades request
+const TRADES_PAGE_LIMIT: u16 = 1000;
+
 impl SpotMarketV3Manager {
   /// Read Recent 24h Trade history
   /// This potentially can be faster than "trades_history_recent"
@@ -89,4 +93,49 @@ impl SpotMarketV3Manager {
       .get_signed(API::SpotV3(SpotV3::HistoricalTrades), Some(request))
       .await
   }
+
+  /// Trade history of the symbol from an id on, in order.
+  ///
+  /// Pages of 1000 trades are chained by the last id until `to_id` (inclusive)
+  /// or the most recent trade. The stream ends after the first failed request
+  pub fn stream_trades_history_from_id<S>(
+    &self,
+    symbol: S,
+    from_id: u64,
+    to_id: Option<u64>,
+  ) -> impl Stream<Item = Result<TradeRecordResponse>> + Send + 'static
+  where
+    S: Into<String>,
+  {
+    let manager = self.clone();
+    let symbol = symbol.into();
+    let to_id = to_id.unwrap_or(u64::MAX);
+
+    let pages = stream::unfold(Some(from_id), move |next_id| {
+      let manager = manager.clone();
+      let symbol = symbol.clone();
+      async move {
+        let from_id = next_id.filter(|id| *id <= to_id)?;
+        let page = match manager
+          .list_trades_history_custom(symbol, from_id, TRADES_PAGE_LIMIT)
+          .await
+        {
+          Ok(page) => page,
+          Err(e) => return Some((Err(e), None)),
+        };
+
+        let is_last = page.len() < TRADES_PAGE_LIMIT as usize;
+        let next_id = page.last().map(|trade| trade.id + 1);
+        // Skip ids of the previous page, in case the server returns them again
+        let trades: Vec<TradeRecordResponse> = page
+          .into_iter()
+          .filter(|trade| trade.id >= from_id && trade.id <= to_id)
+          .collect();
+
+        Some((Ok(trades), if is_last { None } else { next_id }))
+      }
+    });
+
+    flatten_pages(pages)
+  }
 }
@@ -1,9 +1,25 @@
 use super::responses::AggregatedTradeResponse;
-use super::SpotMarketV3Manager;
+use super::{flatten_pages, SpotMarketV3Manager};
 use crate::rest::endpoints::{SpotV3, API};
 use crate::util::{build_query, is_start_time_valid};
 use anyhow::{bail, Result};
+use futures_util::{stream, Stream};
 use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most aggregate trades Binance returns per request
+const AGG_TRADES_PAGE_LIMIT: u16 = 1000;
+/// Longest window of a time bounded aggregate trades request
+const AGG_TRADES_WINDOW_MS: u64 = 60 * 60 * 1000;
+
+/// Position of `stream_agg_trades_by_time`
+enum AggTradesCursor {
+  /// Next time window to search for the first trade
+  Time(u64),
+  /// Next aggregate trade id
+  Id(u64),
+  Done,
+}
 
 impl SpotMarketV3Manager {
   /// Get compressed, aggregate Recent 24h trades.
@@ -128,4 +144,142 @@ impl SpotMarketV3Manager {
       .get(API::SpotV3(SpotV3::AggTrades), Some(request))
       .await
   }
+
+  /// Aggregate trades of the symbol from an id on, in order.
+  ///
+  /// Pages of 1000 trades are chained by the last id until `to_id` (inclusive)
+  /// or the most recent trade. The stream ends after the first failed request
+  pub fn stream_agg_trades_from_id<S>(
+    &self,
+    symbol: S,
+    from_id: u64,
+    to_id: Option<u64>,
+  ) -> impl Stream<Item = Result<AggregatedTradeResponse>> + Send + 'static
+  where
+    S: Into<String>,
+  {
+    let manager = self.clone();
+    let symbol = symbol.into();
+    let to_id = to_id.unwrap_or(u64::MAX);
+
+    let pages = stream::unfold(Some(from_id), move |next_id| {
+      let manager = manager.clone();
+      let symbol = symbol.clone();
+      async move {
+        let from_id = next_id.filter(|id| *id <= to_id)?;
+        let page = match manager
+          .list_agg_trades_custom(symbol, from_id, None, None, AGG_TRADES_PAGE_LIMIT)
+          .await
+        {
+          Ok(page) => page,
+          Err(e) => return Some((Err(e), None)),
+        };
+
+        let is_last = page.len() < AGG_TRADES_PAGE_LIMIT as usize;
+        let next_id = page.last().map(|trade| trade.agg_id + 1);
+        // Skip ids of the previous page, in case the server returns them again
+        let trades: Vec<AggregatedTradeResponse> = page
+          .into_iter()
+          .filter(|trade| trade.agg_id >= from_id && trade.agg_id <= to_id)
+          .collect();
+
+        Some((Ok(trades), if is_last { None } else { next_id }))
+      }
+    });
+
+    flatten_pages(pages)
+  }
+
+  /// Aggregate trades of the symbol in the time range, in order.
+  ///
+  /// Binance limits time bounded requests to one hour, so the range is searched hour by hour
+  /// for the first trade, then pages of 1000 trades are chained by the last id.
+  /// The stream ends after the first failed request.
+  /// - `end_time` defaults to now, later times are treated as now
+  pub fn stream_agg_trades_by_time<S>(
+    &self,
+    symbol: S,
+    start_time: u64,
+    end_time: Option<u64>,
+  ) -> impl Stream<Item = Result<AggregatedTradeResponse>> + Send + 'static
+  where
+    S: Into<String>,
+  {
+    let manager = self.clone();
+    let symbol = symbol.into();
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_millis() as u64;
+    // Windows starting in the future would fail the start time check
+    let end_time = end_time.map_or(now, |end_time| end_time.min(now));
+
+    let pages = stream::unfold(AggTradesCursor::Time(start_time), move |mut cursor| {
+      let manager = manager.clone();
+      let symbol = symbol.clone();
+      async move {
+        loop {
+          match cursor {
+            AggTradesCursor::Done => return None,
+            AggTradesCursor::Time(window_start) => {
+              if window_start > end_time {
+                return None;
+              }
+              let window_end = (window_start + AGG_TRADES_WINDOW_MS - 1).min(end_time);
+              let page = match manager
+                .list_agg_trades_custom(
+                  symbol.clone(),
+                  None,
+                  window_start,
+                  window_end,
+                  AGG_TRADES_PAGE_LIMIT,
+                )
+                .await
+              {
+                Ok(page) => page,
+                Err(e) => return Some((Err(e), AggTradesCursor::Done)),
+              };
+
+              // No trades in this hour, search the next one
+              let Some(last) = page.last() else {
+                cursor = AggTradesCursor::Time(window_end + 1);
+                continue;
+              };
+              let next = if page.len() < AGG_TRADES_PAGE_LIMIT as usize {
+                AggTradesCursor::Time(window_end + 1)
+              } else {
+                AggTradesCursor::Id(last.agg_id + 1)
+              };
+              return Some((Ok(page), next));
+            }
+            AggTradesCursor::Id(from_id) => {
+              let page = match manager
+                .list_agg_trades_custom(symbol, from_id, None, None, AGG_TRADES_PAGE_LIMIT)
+                .await
+              {
+                Ok(page) => page,
+                Err(e) => return Some((Err(e), AggTradesCursor::Done)),
+              };
+
+              let is_full = page.len() == AGG_TRADES_PAGE_LIMIT as usize;
+              let passed_end = page.last().is_some_and(|trade| trade.time > end_time);
+              let next_id = page.last().map(|trade| trade.agg_id + 1);
+              let trades: Vec<AggregatedTradeResponse> = page
+                .into_iter()
+                .filter(|trade| trade.agg_id >= from_id && trade.time <= end_time)
+                .collect();
+
+              let next = match next_id {
+                Some(id) if is_full && !passed_end => AggTradesCursor::Id(id),
+                _ => AggTradesCursor::Done,
+              };
+              return Some((Ok(trades), next));
+            }
+          }
+        }
+      }
+    });
+
+    flatten_pages(pages)
+  }
 }
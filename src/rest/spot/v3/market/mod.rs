@@ -1,4 +1,6 @@
 use crate::rest::core::inner_client::InnerClient;
+use anyhow::Result;
+use futures_util::{stream, Stream, StreamExt};
 
 pub mod enums;
 pub mod market_depth;
@@ -10,9 +12,25 @@ pub mod market_trades_history;
 pub mod market_trades_history_agg;
 pub mod requests;
 pub mod responses;
+pub mod symbol_registry;
 
 #[derive(Clone)]
 pub struct SpotMarketV3Manager {
   pub(crate) client: InnerClient,
   pub(crate) recv_window: u64,
 }
+
+/// Items of the pages, ending after the first error
+pub(crate) fn flatten_pages<T, P>(pages: P) -> impl Stream<Item = Result<T>> + Send + 'static
+where
+  T: Send + 'static,
+  P: Stream<Item = Result<Vec<T>>> + Send + 'static,
+{
+  pages.flat_map(|page| {
+    let items = match page {
+      Ok(items) => items.into_iter().map(Ok).collect(),
+      Err(e) => vec![Err(e)],
+    };
+    stream::iter(items)
+  })
+}
@@ -1,4 +1,4 @@
-use crate::serde_helpers::string_to_float;
+use crate::serde_helpers::{string_to_float, string_to_float_opt};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Display;
@@ -77,6 +77,7 @@ pub struct TickerPriceResponse {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct AveragePrice {
   pub mins: u64,
   #[serde(with = "string_to_float")]
@@ -135,6 +136,49 @@ pub struct TickerDaySummaryResponse {
   pub count: u64,
 }
 
+/// Price change statistics of the rolling window, trading day and typed 24hr tickers.
+/// Fields marked FULL are `None` for the MINI ticker type
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TickerStatsResponse {
+  pub symbol: String,
+  /// FULL
+  #[serde(default, with = "string_to_float_opt")]
+  pub price_change: Option<f64>,
+  /// FULL
+  #[serde(default, with = "string_to_float_opt")]
+  pub price_change_percent: Option<f64>,
+  /// FULL
+  #[serde(default, with = "string_to_float_opt")]
+  pub weighted_avg_price: Option<f64>,
+  /// FULL, 24hr ticker only
+  #[serde(default, with = "string_to_float_opt")]
+  pub prev_close_price: Option<f64>,
+  /// FULL, 24hr ticker only
+  #[serde(default, with = "string_to_float_opt")]
+  pub bid_price: Option<f64>,
+  /// FULL, 24hr ticker only
+  #[serde(default, with = "string_to_float_opt")]
+  pub ask_price: Option<f64>,
+  #[serde(with = "string_to_float")]
+  pub open_price: f64,
+  #[serde(with = "string_to_float")]
+  pub high_price: f64,
+  #[serde(with = "string_to_float")]
+  pub low_price: f64,
+  #[serde(with = "string_to_float")]
+  pub last_price: f64,
+  #[serde(with = "string_to_float")]
+  pub volume: f64,
+  #[serde(with = "string_to_float")]
+  pub quote_volume: f64,
+  pub open_time: u64,
+  pub close_time: u64,
+  pub first_id: i64,
+  pub last_id: i64,
+  pub count: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GeneralExchangeInfoResponse {
@@ -254,6 +298,9 @@ pub enum InstrumentFilters {
     min_notional: Option<String>,
     apply_to_market: Option<bool>,
     avg_price_mins: Option<f64>,
+    max_notional: Option<String>,
+    apply_min_to_market: Option<bool>,
+    apply_max_to_market: Option<bool>,
   },
   #[serde(rename = "ICEBERG_PARTS")]
   #[serde(rename_all = "camelCase")]
@@ -0,0 +1,714 @@
+use super::SpotMarketV3Manager;
+use crate::rest::spot::v3::market::enums::{ExchangeSymbolPermission, ExchangeSymbolStatus};
+use crate::rest::spot::v3::market::responses::{
+  GeneralExchangeInfoResponse, InstrumentFilters, InstrumentInfoResponse,
+};
+use crate::rest::spot::v3::trade::enums::OrderType;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// PRICE_FILTER, a value of 0 disables the rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceFilter {
+  pub min_price: f64,
+  pub max_price: f64,
+  pub tick_size: f64,
+}
+
+/// PERCENT_PRICE, price range relative to the average price of the last `avg_price_mins`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PercentPriceFilter {
+  pub multiplier_up: f64,
+  pub multiplier_down: f64,
+  pub avg_price_mins: Option<u64>,
+}
+
+/// PERCENT_PRICE_BY_SIDE, price range relative to the average price by order side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PercentPriceBySideFilter {
+  pub bid_multiplier_up: f64,
+  pub bid_multiplier_down: f64,
+  pub ask_multiplier_up: f64,
+  pub ask_multiplier_down: f64,
+  pub avg_price_mins: Option<u64>,
+}
+
+/// LOT_SIZE and MARKET_LOT_SIZE, a step size of 0 disables the step rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LotSizeFilter {
+  pub min_qty: f64,
+  pub max_qty: f64,
+  pub step_size: f64,
+}
+
+/// MIN_NOTIONAL, minimum of price * quantity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinNotionalFilter {
+  pub min_notional: f64,
+  pub apply_to_market: bool,
+  pub avg_price_mins: Option<u64>,
+}
+
+/// NOTIONAL, range of price * quantity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotionalFilter {
+  pub min_notional: f64,
+  pub apply_min_to_market: bool,
+  pub max_notional: Option<f64>,
+  pub apply_max_to_market: bool,
+  pub avg_price_mins: Option<u64>,
+}
+
+/// TRAILING_DELTA, in basis points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrailingDeltaFilter {
+  pub min_trailing_above_delta: u16,
+  pub max_trailing_above_delta: u16,
+  pub min_trailing_below_delta: u16,
+  pub max_trailing_below_delta: u16,
+}
+
+/// Filters of a symbol, `None` if the symbol doesn't have the filter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolFilters {
+  pub price: Option<PriceFilter>,
+  pub percent_price: Option<PercentPriceFilter>,
+  pub percent_price_by_side: Option<PercentPriceBySideFilter>,
+  pub lot_size: Option<LotSizeFilter>,
+  pub market_lot_size: Option<LotSizeFilter>,
+  pub min_notional: Option<MinNotionalFilter>,
+  pub notional: Option<NotionalFilter>,
+  /// ICEBERG_PARTS
+  pub iceberg_parts: Option<u16>,
+  /// MAX_NUM_ORDERS, open orders of the account on the symbol
+  pub max_num_orders: Option<u16>,
+  pub max_num_algo_orders: Option<u16>,
+  pub max_num_iceberg_orders: Option<u16>,
+  /// MAX_POSITION, base asset balance plus open buy orders
+  pub max_position: Option<f64>,
+  pub trailing_delta: Option<TrailingDeltaFilter>,
+}
+
+impl TryFrom<&[InstrumentFilters]> for SymbolFilters {
+  type Error = anyhow::Error;
+
+  fn try_from(filters: &[InstrumentFilters]) -> Result<Self> {
+    let mut result = SymbolFilters::default();
+
+    for filter in filters {
+      match filter {
+        InstrumentFilters::PriceFilter {
+          min_price,
+          max_price,
+          tick_size,
+        } => {
+          result.price = Some(PriceFilter {
+            min_price: number(min_price, "minPrice")?,
+            max_price: number(max_price, "maxPrice")?,
+            tick_size: number(tick_size, "tickSize")?,
+          })
+        }
+        InstrumentFilters::PercentPrice {
+          multiplier_up,
+          multiplier_down,
+          avg_price_mins,
+        } => {
+          result.percent_price = Some(PercentPriceFilter {
+            multiplier_up: number(multiplier_up, "multiplierUp")?,
+            multiplier_down: number(multiplier_down, "multiplierDown")?,
+            avg_price_mins: avg_price_mins.map(|m| m as u64),
+          })
+        }
+        InstrumentFilters::PercentPriceBySide {
+          bid_multiplier_up,
+          bid_multiplier_down,
+          ask_multiplier_up,
+          ask_multiplier_down,
+          avg_price_mins,
+        } => {
+          result.percent_price_by_side = Some(PercentPriceBySideFilter {
+            bid_multiplier_up: number(bid_multiplier_up, "bidMultiplierUp")?,
+            bid_multiplier_down: number(bid_multiplier_down, "bidMultiplierDown")?,
+            ask_multiplier_up: number(ask_multiplier_up, "askMultiplierUp")?,
+            ask_multiplier_down: number(ask_multiplier_down, "askMultiplierDown")?,
+            avg_price_mins: avg_price_mins.map(|m| m as u64),
+          })
+        }
+        InstrumentFilters::LotSize {
+          min_qty,
+          max_qty,
+          step_size,
+        } => result.lot_size = Some(lot_size(min_qty, max_qty, step_size)?),
+        InstrumentFilters::MarketLotSize {
+          min_qty,
+          max_qty,
+          step_size,
+        } => result.market_lot_size = Some(lot_size(min_qty, max_qty, step_size)?),
+        InstrumentFilters::MinNotional {
+          notional,
+          min_notional,
+          apply_to_market,
+          avg_price_mins,
+        } => {
+          let min_notional = min_notional
+            .as_ref()
+            .or(notional.as_ref())
+            .ok_or_else(|| anyhow!("MIN_NOTIONAL filter without minNotional"))?;
+          result.min_notional = Some(MinNotionalFilter {
+            min_notional: number(min_notional, "minNotional")?,
+            apply_to_market: apply_to_market.unwrap_or(false),
+            avg_price_mins: avg_price_mins.map(|m| m as u64),
+          })
+        }
+        InstrumentFilters::Notional {
+          notional,
+          min_notional,
+          apply_to_market,
+          avg_price_mins,
+          max_notional,
+          apply_min_to_market,
+          apply_max_to_market,
+        } => {
+          let min_notional = match min_notional.as_ref().or(notional.as_ref()) {
+            Some(value) => number(value, "minNotional")?,
+            None => 0.0,
+          };
+          let max_notional = match max_notional {
+            Some(value) => Some(number(value, "maxNotional")?),
+            None => None,
+          };
+          result.notional = Some(NotionalFilter {
+            min_notional,
+            apply_min_to_market: apply_min_to_market.or(*apply_to_market).unwrap_or(false),
+            max_notional,
+            apply_max_to_market: apply_max_to_market.unwrap_or(false),
+            avg_price_mins: avg_price_mins.map(|m| m as u64),
+          })
+        }
+        InstrumentFilters::IcebergParts { limit } => result.iceberg_parts = *limit,
+        InstrumentFilters::MaxNumOrders { max_num_orders } => {
+          result.max_num_orders = *max_num_orders
+        }
+        InstrumentFilters::MaxNumAlgoOrders {
+          max_num_algo_orders,
+        } => result.max_num_algo_orders = *max_num_algo_orders,
+        InstrumentFilters::MaxNumIcebergOrders {
+          max_num_iceberg_orders,
+        } => result.max_num_iceberg_orders = Some(*max_num_iceberg_orders),
+        InstrumentFilters::MaxPosition { max_position } => {
+          result.max_position = Some(number(max_position, "maxPosition")?)
+        }
+        InstrumentFilters::TrailingData {
+          min_trailing_above_delta,
+          max_trailing_above_delta,
+          min_trailing_below_delta,
+          max_trailing_below_delta,
+        } => {
+          result.trailing_delta = Some(TrailingDeltaFilter {
+            min_trailing_above_delta: min_trailing_above_delta.unwrap_or(0),
+            max_trailing_above_delta: max_trailing_above_delta.unwrap_or(0),
+            min_trailing_below_delta: min_trailing_below_delta.unwrap_or(0),
+            max_trailing_below_delta: max_trailing_below_delta.unwrap_or(0),
+          })
+        }
+      }
+    }
+
+    Ok(result)
+  }
+}
+
+/// Permission of a permission set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolPermission {
+  Known(ExchangeSymbolPermission),
+  /// Permission unknown to `ExchangeSymbolPermission`, no account is assumed to have it
+  Unknown(String),
+}
+
+impl From<&str> for SymbolPermission {
+  fn from(permission: &str) -> Self {
+    match permission.parse() {
+      Ok(permission) => SymbolPermission::Known(permission),
+      Err(_) => SymbolPermission::Unknown(permission.to_string()),
+    }
+  }
+}
+
+/// Trading rules of a symbol with typed values
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+  pub symbol: String,
+  pub status: ExchangeSymbolStatus,
+  pub base_asset: String,
+  pub base_asset_precision: u64,
+  pub quote_asset: String,
+  pub quote_precision: u64,
+  /// Order types unknown to `OrderType` are left out
+  pub order_types: Vec<OrderType>,
+  pub iceberg_allowed: bool,
+  pub oco_allowed: bool,
+  pub oto_allowed: bool,
+  pub quote_order_qty_market_allowed: bool,
+  pub allow_trailing_stop: bool,
+  pub cancel_replace_allowed: bool,
+  pub is_spot_trading_allowed: bool,
+  pub is_margin_trading_allowed: bool,
+  /// An account needs one permission of every set to trade the symbol,
+  /// empty sets don't restrict the symbol
+  pub permission_sets: Vec<Vec<SymbolPermission>>,
+  pub filters: SymbolFilters,
+}
+
+impl SymbolInfo {
+  pub fn is_trading(&self) -> bool {
+    self.status == ExchangeSymbolStatus::Trading
+  }
+
+  pub fn is_order_type_allowed(&self, order_type: OrderType) -> bool {
+    self.order_types.contains(&order_type)
+  }
+
+  /// Whether an account with the permissions may trade the symbol.
+  /// Sets of unknown permissions only are never satisfied
+  pub fn is_permitted(&self, account_permissions: &[ExchangeSymbolPermission]) -> bool {
+    self.permission_sets.iter().all(|set| {
+      set.is_empty()
+        || set.iter().any(|permission| match permission {
+          SymbolPermission::Known(permission) => account_permissions.contains(permission),
+          SymbolPermission::Unknown(_) => false,
+        })
+    })
+  }
+
+  /// PRICE_FILTER tick size, `None` without the filter or with the rule disabled
+  pub fn tick_size(&self) -> Option<f64> {
+    self.filters.price.map(|f| f.tick_size).filter(|t| *t > 0.0)
+  }
+
+  /// LOT_SIZE step size, `None` without the filter or with the rule disabled
+  pub fn step_size(&self) -> Option<f64> {
+    self
+      .filters
+      .lot_size
+      .map(|f| f.step_size)
+      .filter(|s| *s > 0.0)
+  }
+}
+
+impl TryFrom<&InstrumentInfoResponse> for SymbolInfo {
+  type Error = anyhow::Error;
+
+  fn try_from(info: &InstrumentInfoResponse) -> Result<Self> {
+    let permission_sets = info
+      .permission_sets
+      .iter()
+      .map(|set| {
+        set
+          .iter()
+          .map(|p| SymbolPermission::from(p.as_str()))
+          .collect()
+      })
+      .collect();
+
+    Ok(SymbolInfo {
+      symbol: info.symbol.clone(),
+      status: info.status.parse()?,
+      base_asset: info.base_asset.clone(),
+      base_asset_precision: info.base_asset_precision,
+      quote_asset: info.quote_asset.clone(),
+      quote_precision: info.quote_precision,
+      order_types: info
+        .order_types
+        .iter()
+        .filter_map(|order_type| order_type.parse().ok())
+        .collect(),
+      iceberg_allowed: info.iceberg_allowed,
+      oco_allowed: info.oco_allowed,
+      oto_allowed: info.oto_allowed,
+      quote_order_qty_market_allowed: info.quote_order_qty_market_allowed,
+      allow_trailing_stop: info.allow_trailing_stop,
+      cancel_replace_allowed: info.cancel_replace_allowed,
+      is_spot_trading_allowed: info.is_spot_trading_allowed,
+      is_margin_trading_allowed: info.is_margin_trading_allowed,
+      permission_sets,
+      filters: SymbolFilters::try_from(info.filters.as_slice())?,
+    })
+  }
+}
+
+/// Trading rules of the exchange symbols, looked up by symbol, base asset or quote asset
+#[derive(Debug, Clone, Default)]
+pub struct SymbolRegistry {
+  symbols: HashMap<String, SymbolInfo>,
+  by_base_asset: HashMap<String, Vec<String>>,
+  by_quote_asset: HashMap<String, Vec<String>>,
+}
+
+impl SymbolRegistry {
+  pub fn from_exchange_info(info: &GeneralExchangeInfoResponse) -> Self {
+    Self::from_instruments(&info.symbols)
+  }
+
+  /// Symbols that can't be converted, e.g. with an unknown status, are skipped with a warning
+  pub fn from_instruments(instruments: &[InstrumentInfoResponse]) -> Self {
+    let mut registry = SymbolRegistry::default();
+    for instrument in instruments {
+      match SymbolInfo::try_from(instrument) {
+        Ok(info) => registry.insert(info),
+        Err(e) => eprintln!("Skipping symbol {}: {:?}", instrument.symbol, e),
+      }
+    }
+    registry
+  }
+
+  /// Add or replace a symbol
+  pub fn insert(&mut self, info: SymbolInfo) {
+    if let Some(previous) = self.symbols.remove(&info.symbol) {
+      self.unindex(&previous);
+    }
+
+    self
+      .by_base_asset
+      .entry(info.base_asset.clone())
+      .or_default()
+      .push(info.symbol.clone());
+    self
+      .by_quote_asset
+      .entry(info.quote_asset.clone())
+      .or_default()
+      .push(info.symbol.clone());
+    self.symbols.insert(info.symbol.clone(), info);
+  }
+
+  fn unindex(&mut self, info: &SymbolInfo) {
+    if let Some(symbols) = self.by_base_asset.get_mut(&info.base_asset) {
+      symbols.retain(|s| s != &info.symbol);
+    }
+    if let Some(symbols) = self.by_quote_asset.get_mut(&info.quote_asset) {
+      symbols.retain(|s| s != &info.symbol);
+    }
+  }
+
+  /// Symbol e.g. "BTCUSDT", case insensitive
+  pub fn get(&self, symbol: &str) -> Option<&SymbolInfo> {
+    self.symbols.get(&symbol.to_uppercase())
+  }
+
+  /// Symbols with the base asset, e.g. "BTC"
+  pub fn by_base_asset(&self, asset: &str) -> Vec<&SymbolInfo> {
+    self.lookup(&self.by_base_asset, asset)
+  }
+
+  /// Symbols with the quote asset, e.g. "USDT"
+  pub fn by_quote_asset(&self, asset: &str) -> Vec<&SymbolInfo> {
+    self.lookup(&self.by_quote_asset, asset)
+  }
+
+  fn lookup(&self, index: &HashMap<String, Vec<String>>, asset: &str) -> Vec<&SymbolInfo> {
+    index
+      .get(&asset.to_uppercase())
+      .map(|symbols| symbols.iter().filter_map(|s| self.symbols.get(s)).collect())
+      .unwrap_or_default()
+  }
+
+  pub fn symbols(&self) -> impl Iterator<Item = &SymbolInfo> {
+    self.symbols.values()
+  }
+
+  pub fn len(&self) -> usize {
+    self.symbols.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.symbols.is_empty()
+  }
+}
+
+impl SpotMarketV3Manager {
+  /// Load the trading rules of all symbols from exchange information
+  pub async fn load_symbol_registry(&self) -> Result<SymbolRegistry> {
+    let info = self.fetch_general_exchange_info().await?;
+    Ok(SymbolRegistry::from_exchange_info(&info))
+  }
+}
+
+fn number(value: &str, name: &str) -> Result<f64> {
+  value
+    .parse()
+    .map_err(|e| anyhow!("Invalid filter value {} = {:?}: {}", name, value, e))
+}
+
+fn lot_size(min_qty: &str, max_qty: &str, step_size: &str) -> Result<LotSizeFilter> {
+  Ok(LotSizeFilter {
+    min_qty: number(min_qty, "minQty")?,
+    max_qty: number(max_qty, "maxQty")?,
+    step_size: number(step_size, "stepSize")?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::{json, Value};
+
+  fn filters(filters: Value) -> Result<SymbolFilters> {
+    let filters: Vec<InstrumentFilters> = serde_json::from_value(filters).unwrap();
+    SymbolFilters::try_from(filters.as_slice())
+  }
+
+  fn instrument(symbol: &str, base_asset: &str, quote_asset: &str) -> Value {
+    json!({
+      "symbol": symbol,
+      "status": "TRADING",
+      "baseAsset": base_asset,
+      "baseAssetPrecision": 8,
+      "quoteAsset": quote_asset,
+      "quotePrecision": 8,
+      "orderTypes": ["LIMIT", "MARKET", "NEW_ORDER_TYPE"],
+      "icebergAllowed": true,
+      "ocoAllowed": true,
+      "otoAllowed": true,
+      "quoteOrderQtyMarketAllowed": true,
+      "allowTrailingStop": true,
+      "cancelReplaceAllowed": true,
+      "isSpotTradingAllowed": true,
+      "isMarginTradingAllowed": false,
+      "filters": [],
+      "permissions": [],
+      "permissionSets": [["SPOT", "MARGIN"]],
+      "defaultSelfTradePreventionMode": "EXPIRE_MAKER",
+      "allowedSelfTradePreventionModes": ["EXPIRE_MAKER"]
+    })
+  }
+
+  fn symbol_info(instrument: Value) -> SymbolInfo {
+    let instrument: InstrumentInfoResponse = serde_json::from_value(instrument).unwrap();
+    SymbolInfo::try_from(&instrument).unwrap()
+  }
+
+  #[test]
+  fn converts_filters() {
+    let filters = filters(json!([
+      {"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "0.01"},
+      {"filterType": "LOT_SIZE", "minQty": "0.00001", "maxQty": "9000.00", "stepSize": "0.00001"},
+      {"filterType": "MARKET_LOT_SIZE", "minQty": "0.00", "maxQty": "100.00", "stepSize": "0.00"},
+      {"filterType": "ICEBERG_PARTS", "limit": 10},
+      {"filterType": "MAX_NUM_ORDERS", "maxNumOrders": 200},
+      {"filterType": "MAX_NUM_ICEBERG_ORDERS", "maxNumIcebergOrders": 5},
+      {"filterType": "MAX_POSITION", "maxPosition": "10.00"},
+      {"filterType": "TRAILING_DELTA", "minTrailingAboveDelta": 10, "maxTrailingAboveDelta": 2000,
+        "minTrailingBelowDelta": 10, "maxTrailingBelowDelta": 2000},
+      {"filterType": "PERCENT_PRICE_BY_SIDE", "bidMultiplierUp": "5", "bidMultiplierDown": "0.2",
+        "askMultiplierUp": "5", "askMultiplierDown": "0.2", "avgPriceMins": 5}
+    ]))
+    .unwrap();
+
+    assert_eq!(
+      filters.price,
+      Some(PriceFilter {
+        min_price: 0.01,
+        max_price: 1_000_000.0,
+        tick_size: 0.01,
+      })
+    );
+    assert_eq!(filters.lot_size.map(|f| f.step_size), Some(0.00001));
+    assert_eq!(filters.market_lot_size.map(|f| f.max_qty), Some(100.0));
+    assert_eq!(filters.iceberg_parts, Some(10));
+    assert_eq!(filters.max_num_orders, Some(200));
+    assert_eq!(filters.max_num_iceberg_orders, Some(5));
+    assert_eq!(filters.max_position, Some(10.0));
+    assert_eq!(
+      filters.trailing_delta.map(|f| f.max_trailing_below_delta),
+      Some(2000)
+    );
+    assert_eq!(
+      filters.percent_price_by_side.and_then(|f| f.avg_price_mins),
+      Some(5)
+    );
+    assert_eq!(filters.percent_price, None);
+    assert_eq!(filters.notional, None);
+  }
+
+  #[test]
+  fn falls_back_to_notional_for_the_minimum() {
+    let min_notional = filters(json!([
+      {"filterType": "MIN_NOTIONAL", "notional": "10.00", "applyToMarket": true, "avgPriceMins": 5}
+    ]))
+    .unwrap();
+    assert_eq!(
+      min_notional.min_notional,
+      Some(MinNotionalFilter {
+        min_notional: 10.0,
+        apply_to_market: true,
+        avg_price_mins: Some(5),
+      })
+    );
+    assert_eq!(min_notional.notional, None);
+
+    let min_notional = filters(json!([
+      {"filterType": "MIN_NOTIONAL", "minNotional": "5.00", "notional": "10.00"}
+    ]))
+    .unwrap();
+    assert_eq!(min_notional.min_notional.map(|f| f.min_notional), Some(5.0));
+    assert_eq!(
+      min_notional.min_notional.map(|f| f.apply_to_market),
+      Some(false)
+    );
+
+    assert!(filters(json!([{"filterType": "MIN_NOTIONAL", "applyToMarket": true}])).is_err());
+
+    let notional = filters(json!([
+      {"filterType": "NOTIONAL", "notional": "5.00", "applyToMarket": true,
+        "maxNotional": "9000000.00", "applyMaxToMarket": false}
+    ]))
+    .unwrap();
+    assert_eq!(
+      notional.notional,
+      Some(NotionalFilter {
+        min_notional: 5.0,
+        apply_min_to_market: true,
+        max_notional: Some(9_000_000.0),
+        apply_max_to_market: false,
+        avg_price_mins: None,
+      })
+    );
+
+    let notional = filters(json!([{"filterType": "NOTIONAL", "applyMinToMarket": false}])).unwrap();
+    assert_eq!(notional.notional.map(|f| f.min_notional), Some(0.0));
+    assert_eq!(notional.notional.and_then(|f| f.max_notional), None);
+  }
+
+  #[test]
+  fn rejects_invalid_filter_values() {
+    let error = filters(json!([
+      {"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "one"}
+    ]))
+    .unwrap_err();
+    assert!(error.to_string().contains("tickSize"));
+  }
+
+  #[test]
+  fn treats_zero_rules_as_disabled() {
+    let mut disabled = instrument("BTCUSDT", "BTC", "USDT");
+    disabled["filters"] = json!([
+      {"filterType": "PRICE_FILTER", "minPrice": "0.00", "maxPrice": "0.00", "tickSize": "0.00"},
+      {"filterType": "LOT_SIZE", "minQty": "0.00", "maxQty": "9000.00", "stepSize": "0.00"}
+    ]);
+    let info = symbol_info(disabled);
+    assert_eq!(info.tick_size(), None);
+    assert_eq!(info.step_size(), None);
+
+    let mut enabled = instrument("BTCUSDT", "BTC", "USDT");
+    enabled["filters"] = json!([
+      {"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "0.01"},
+      {"filterType": "LOT_SIZE", "minQty": "0.00001", "maxQty": "9000.00", "stepSize": "0.00001"}
+    ]);
+    let info = symbol_info(enabled);
+    assert_eq!(info.tick_size(), Some(0.01));
+    assert_eq!(info.step_size(), Some(0.00001));
+  }
+
+  #[test]
+  fn skips_unknown_order_types() {
+    let info = symbol_info(instrument("BTCUSDT", "BTC", "USDT"));
+    assert_eq!(info.order_types, vec![OrderType::Limit, OrderType::Market]);
+    assert!(info.is_order_type_allowed(OrderType::Market));
+    assert!(!info.is_order_type_allowed(OrderType::StopLoss));
+  }
+
+  #[test]
+  fn checks_permission_sets() {
+    let mut instrument = instrument("BTCUSDT", "BTC", "USDT");
+    instrument["permissionSets"] = json!([["SPOT", "NEW_PERMISSION"], ["TRD_GRP_002", "MARGIN"]]);
+    let info = symbol_info(instrument.clone());
+    assert_eq!(
+      info.permission_sets[0],
+      vec![
+        SymbolPermission::Known(ExchangeSymbolPermission::Spot),
+        SymbolPermission::Unknown("NEW_PERMISSION".into()),
+      ]
+    );
+    assert!(info.is_permitted(&[
+      ExchangeSymbolPermission::Spot,
+      ExchangeSymbolPermission::Margin,
+    ]));
+    assert!(!info.is_permitted(&[ExchangeSymbolPermission::Spot]));
+
+    // Nobody is known to have a permission of a set of unknown ones only
+    instrument["permissionSets"] = json!([["SPOT"], ["NEW_PERMISSION"]]);
+    let info = symbol_info(instrument.clone());
+    assert!(!info.is_permitted(&[ExchangeSymbolPermission::Spot]));
+
+    instrument["permissionSets"] = json!([[], ["SPOT"]]);
+    let info = symbol_info(instrument);
+    assert!(info.is_permitted(&[ExchangeSymbolPermission::Spot]));
+  }
+
+  #[test]
+  fn skips_symbols_with_unknown_status() {
+    let mut unknown_status = instrument("OLDUSDT", "OLD", "USDT");
+    unknown_status["status"] = json!("NEW_STATUS");
+    let instruments: Vec<InstrumentInfoResponse> = serde_json::from_value(json!([
+      instrument("BTCUSDT", "BTC", "USDT"),
+      unknown_status
+    ]))
+    .unwrap();
+
+    let registry = SymbolRegistry::from_instruments(&instruments);
+    assert_eq!(registry.len(), 1);
+    assert!(registry.get("OLDUSDT").is_none());
+  }
+
+  #[test]
+  fn looks_up_case_insensitively() {
+    let mut registry = SymbolRegistry::default();
+    registry.insert(symbol_info(instrument("BTCUSDT", "BTC", "USDT")));
+    registry.insert(symbol_info(instrument("ETHUSDT", "ETH", "USDT")));
+    registry.insert(symbol_info(instrument("ETHBTC", "ETH", "BTC")));
+
+    assert_eq!(
+      registry.get("btcusdt").map(|info| info.symbol.as_str()),
+      Some("BTCUSDT")
+    );
+    assert!(registry.get("BTCEUR").is_none());
+
+    let symbols = |infos: Vec<&SymbolInfo>| {
+      let mut symbols: Vec<String> = infos.into_iter().map(|info| info.symbol.clone()).collect();
+      symbols.sort();
+      symbols
+    };
+    assert_eq!(
+      symbols(registry.by_base_asset("eth")),
+      vec!["ETHBTC", "ETHUSDT"]
+    );
+    assert_eq!(
+      symbols(registry.by_quote_asset("Usdt")),
+      vec!["BTCUSDT", "ETHUSDT"]
+    );
+    assert_eq!(symbols(registry.by_quote_asset("btc")), vec!["ETHBTC"]);
+    assert!(registry.by_base_asset("XRP").is_empty());
+  }
+
+  #[test]
+  fn reindexes_replaced_symbols() {
+    let mut registry = SymbolRegistry::default();
+    registry.insert(symbol_info(instrument("BTCUSDT", "BTC", "USDT")));
+    registry.insert(symbol_info(instrument("BTCUSDT", "BTC", "USDT")));
+    assert_eq!(registry.len(), 1);
+    assert_eq!(registry.by_base_asset("BTC").len(), 1);
+
+    // Same symbol with other assets, e.g. after a migration
+    registry.insert(symbol_info(instrument("BTCUSDT", "WBTC", "FDUSD")));
+    assert_eq!(registry.len(), 1);
+    assert!(registry.by_base_asset("BTC").is_empty());
+    assert!(registry.by_quote_asset("USDT").is_empty());
+    assert_eq!(registry.by_base_asset("WBTC").len(), 1);
+    assert_eq!(
+      registry
+        .get("BTCUSDT")
+        .map(|info| info.quote_asset.as_str()),
+      Some("FDUSD")
+    );
+  }
+}
@@ -1,6 +1,9 @@
 use crate::rest::core::inner_client::InnerClient;
+use order_validator::OrderValidator;
+use std::sync::Arc;
 
 pub mod enums;
+pub mod order_validator;
 pub mod responses;
 
 pub mod requests;
@@ -19,4 +22,6 @@ pub mod trade_stp_orders_info;
 pub struct SpotTradeV3Manager {
   pub(crate) client: InnerClient,
   pub(crate) recv_window: u64,
+  /// Rejects invalid orders locally, see `set_order_validator`
+  pub(crate) order_validator: Option<Arc<OrderValidator>>,
 }
@@ -0,0 +1,1090 @@
+use super::enums::{OrderSide, OrderType};
+use super::requests::PlaceOrderRequest;
+use super::SpotTradeV3Manager;
+use crate::rest::endpoints::{SpotV3, API};
+use crate::rest::spot::v3::market::enums::ExchangeSymbolStatus;
+use crate::rest::spot::v3::market::responses::AveragePrice;
+use crate::rest::spot::v3::market::symbol_registry::{LotSizeFilter, SymbolInfo, SymbolRegistry};
+use crate::util::build_query;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Relative tolerance of the tick and step size checks, prices and quantities are `f64`
+const STEP_TOLERANCE: f64 = 1e-8;
+
+/// How long `OrderValidator` uses an average price before fetching it again
+const AVG_PRICE_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// A filter rule an order breaks. `filter` is the Binance filter type, e.g. "LOT_SIZE",
+/// `field` the order parameter, e.g. "quantity"
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderViolation {
+  UnknownSymbol,
+  SymbolNotTrading {
+    status: ExchangeSymbolStatus,
+  },
+  OrderTypeNotAllowed {
+    order_type: OrderType,
+  },
+  /// A parameter the order type requires is missing
+  MissingField {
+    field: &'static str,
+  },
+  BelowMin {
+    filter: &'static str,
+    field: &'static str,
+    value: f64,
+    min: f64,
+  },
+  AboveMax {
+    filter: &'static str,
+    field: &'static str,
+    value: f64,
+    max: f64,
+  },
+  /// Not a multiple of the tick or step size
+  NotMultiple {
+    filter: &'static str,
+    field: &'static str,
+    value: f64,
+    step: f64,
+  },
+  TooManyIcebergParts {
+    parts: u64,
+    limit: u16,
+  },
+  TooManyOpenOrders {
+    open_orders: u16,
+    limit: u16,
+  },
+}
+
+impl OrderViolation {
+  /// Binance filter type of the violation, `None` for symbol and order type checks
+  pub fn filter(&self) -> Option<&'static str> {
+    match self {
+      OrderViolation::BelowMin { filter, .. }
+      | OrderViolation::AboveMax { filter, .. }
+      | OrderViolation::NotMultiple { filter, .. } => Some(filter),
+      OrderViolation::TooManyIcebergParts { .. } => Some("ICEBERG_PARTS"),
+      OrderViolation::TooManyOpenOrders { .. } => Some("MAX_NUM_ORDERS"),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for OrderViolation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      OrderViolation::UnknownSymbol => write!(f, "unknown symbol"),
+      OrderViolation::SymbolNotTrading { status } => write!(f, "symbol status is {}", status),
+      OrderViolation::OrderTypeNotAllowed { order_type } => {
+        write!(f, "order type {} is not allowed", order_type)
+      }
+      OrderViolation::MissingField { field } => write!(f, "{} is required", field),
+      OrderViolation::BelowMin {
+        filter,
+        field,
+        value,
+        min,
+      } => write!(f, "{}: {} {} is below {}", filter, field, value, min),
+      OrderViolation::AboveMax {
+        filter,
+        field,
+        value,
+        max,
+      } => write!(f, "{}: {} {} is above {}", filter, field, value, max),
+      OrderViolation::NotMultiple {
+        filter,
+        field,
+        value,
+        step,
+      } => write!(
+        f,
+        "{}: {} {} is not a multiple of {}",
+        filter, field, value, step
+      ),
+      OrderViolation::TooManyIcebergParts { parts, limit } => {
+        write!(f, "ICEBERG_PARTS: {} parts, at most {}", parts, limit)
+      }
+      OrderViolation::TooManyOpenOrders { open_orders, limit } => write!(
+        f,
+        "MAX_NUM_ORDERS: {} open orders, at most {}",
+        open_orders, limit
+      ),
+    }
+  }
+}
+
+/// Error of an order `SpotTradeV3Manager` rejected locally, get it with `downcast_ref`
+#[derive(Debug, Clone)]
+pub struct OrderRejected {
+  pub symbol: String,
+  pub violations: Vec<OrderViolation>,
+}
+
+impl fmt::Display for OrderRejected {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let violations: Vec<String> = self.violations.iter().map(|v| v.to_string()).collect();
+    write!(
+      f,
+      "Order for {} rejected locally: {}",
+      self.symbol,
+      violations.join(", ")
+    )
+  }
+}
+
+impl std::error::Error for OrderRejected {}
+
+/// Market and account state some filters depend on, their checks are skipped without it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrderContext {
+  /// Average price of the symbol, for PERCENT_PRICE(_BY_SIDE) and the notional of market orders.
+  /// `OrderValidator` fetches it when it isn't set
+  pub avg_price: Option<f64>,
+  /// Open orders of the account on the symbol, for MAX_NUM_ORDERS
+  pub open_orders: Option<u16>,
+}
+
+/// Check an order against the trading rules of its symbol.
+///
+/// Covers the symbol status, order type, PRICE_FILTER, PERCENT_PRICE, PERCENT_PRICE_BY_SIDE,
+/// LOT_SIZE, MARKET_LOT_SIZE, MIN_NOTIONAL, NOTIONAL, ICEBERG_PARTS, MAX_NUM_ORDERS
+/// and TRAILING_DELTA. Returns no violations for a valid order, see `needs_avg_price`
+/// for the checks skipped without `OrderContext::avg_price`
+pub fn validate_order(
+  info: &SymbolInfo,
+  request: &PlaceOrderRequest,
+  context: &OrderContext,
+) -> Vec<OrderViolation> {
+  let mut violations = Vec::new();
+  let filters = &info.filters;
+  let is_market = request.order_type == OrderType::Market;
+
+  if info.status != ExchangeSymbolStatus::Trading {
+    violations.push(OrderViolation::SymbolNotTrading {
+      status: info.status,
+    });
+  }
+  if !info.is_order_type_allowed(request.order_type) {
+    violations.push(OrderViolation::OrderTypeNotAllowed {
+      order_type: request.order_type,
+    });
+  }
+
+  let needs_price = matches!(
+    request.order_type,
+    OrderType::Limit
+      | OrderType::LimitMaker
+      | OrderType::StopLossLimit
+      | OrderType::TakeProfitLimit
+  );
+  if needs_price && request.price.is_none() {
+    violations.push(OrderViolation::MissingField { field: "price" });
+  }
+  // Stop orders are triggered by the stop price, the trailing delta or both
+  let needs_trigger = matches!(
+    request.order_type,
+    OrderType::StopLoss
+      | OrderType::StopLossLimit
+      | OrderType::TakeProfit
+      | OrderType::TakeProfitLimit
+  );
+  if needs_trigger && request.stop_price.is_none() && request.trailing_delta.is_none() {
+    violations.push(OrderViolation::MissingField { field: "stopPrice" });
+  }
+  if !is_market && request.qty.is_none() {
+    violations.push(OrderViolation::MissingField { field: "quantity" });
+  }
+  if is_market && request.qty.is_none() && request.quote_order_qty.is_none() {
+    violations.push(OrderViolation::MissingField { field: "quantity" });
+  }
+
+  // PRICE_FILTER, a 0 disables the rule
+  if let Some(price_filter) = &filters.price {
+    let prices = [("price", request.price), ("stopPrice", request.stop_price)];
+    for (field, value) in prices {
+      let Some(value) = value else { continue };
+      if price_filter.min_price > 0.0 && value < price_filter.min_price {
+        violations.push(OrderViolation::BelowMin {
+          filter: "PRICE_FILTER",
+          field,
+          value,
+          min: price_filter.min_price,
+        });
+      }
+      if price_filter.max_price > 0.0 && value > price_filter.max_price {
+        violations.push(OrderViolation::AboveMax {
+          filter: "PRICE_FILTER",
+          field,
+          value,
+          max: price_filter.max_price,
+        });
+      }
+      if price_filter.tick_size > 0.0
+        && !is_multiple(value - price_filter.min_price, price_filter.tick_size)
+      {
+        violations.push(OrderViolation::NotMultiple {
+          filter: "PRICE_FILTER",
+          field,
+          value,
+          step: price_filter.tick_size,
+        });
+      }
+    }
+  }
+
+  // PERCENT_PRICE and PERCENT_PRICE_BY_SIDE, relative to the average price
+  if let (Some(price), Some(avg_price)) = (request.price, context.avg_price) {
+    if let Some(percent) = &filters.percent_price {
+      check_range(
+        &mut violations,
+        "PERCENT_PRICE",
+        "price",
+        price,
+        avg_price * percent.multiplier_down,
+        Some(avg_price * percent.multiplier_up),
+      );
+    }
+    if let Some(by_side) = &filters.percent_price_by_side {
+      let (down, up) = match request.order_side {
+        OrderSide::Buy => (by_side.bid_multiplier_down, by_side.bid_multiplier_up),
+        OrderSide::Sell => (by_side.ask_multiplier_down, by_side.ask_multiplier_up),
+      };
+      check_range(
+        &mut violations,
+        "PERCENT_PRICE_BY_SIDE",
+        "price",
+        price,
+        avg_price * down,
+        Some(avg_price * up),
+      );
+    }
+  }
+
+  // LOT_SIZE applies to every order, MARKET_LOT_SIZE to market orders as well
+  if let Some(qty) = request.qty {
+    if let Some(lot_size) = &filters.lot_size {
+      check_lot_size(&mut violations, "LOT_SIZE", "quantity", qty, lot_size);
+    }
+    if is_market && let Some(market_lot_size) = &filters.market_lot_size {
+      check_lot_size(
+        &mut violations,
+        "MARKET_LOT_SIZE",
+        "quantity",
+        qty,
+        market_lot_size,
+      );
+    }
+  }
+  if let (Some(iceberg_qty), Some(lot_size)) = (request.iceberg_qty, &filters.lot_size) {
+    check_lot_size(
+      &mut violations,
+      "LOT_SIZE",
+      "icebergQty",
+      iceberg_qty,
+      lot_size,
+    );
+  }
+
+  // MIN_NOTIONAL and NOTIONAL, market orders use the quote quantity or the average price
+  let notional = match (request.qty, request.price, request.quote_order_qty) {
+    (_, _, Some(quote_order_qty)) if is_market => Some(quote_order_qty),
+    (Some(qty), _, _) if is_market => context.avg_price.map(|avg_price| qty * avg_price),
+    (Some(qty), Some(price), _) => Some(qty * price),
+    _ => None,
+  };
+  if let Some(notional) = notional {
+    if let Some(min_notional) = &filters.min_notional
+      && (!is_market || min_notional.apply_to_market)
+    {
+      check_range(
+        &mut violations,
+        "MIN_NOTIONAL",
+        "notional",
+        notional,
+        min_notional.min_notional,
+        None,
+      );
+    }
+    if let Some(notional_filter) = &filters.notional {
+      let min = if !is_market || notional_filter.apply_min_to_market {
+        notional_filter.min_notional
+      } else {
+        0.0
+      };
+      let max = notional_filter
+        .max_notional
+        .filter(|_| !is_market || notional_filter.apply_max_to_market);
+      check_range(&mut violations, "NOTIONAL", "notional", notional, min, max);
+    }
+  }
+
+  // ICEBERG_PARTS
+  if let (Some(qty), Some(iceberg_qty), Some(limit)) =
+    (request.qty, request.iceberg_qty, filters.iceberg_parts)
+    && iceberg_qty > 0.0
+  {
+    let parts = (qty / iceberg_qty - STEP_TOLERANCE).ceil() as u64;
+    if parts > limit as u64 {
+      violations.push(OrderViolation::TooManyIcebergParts { parts, limit });
+    }
+  }
+
+  // MAX_NUM_ORDERS, the order would be one more open order
+  if let (Some(open_orders), Some(limit)) = (context.open_orders, filters.max_num_orders)
+    && open_orders >= limit
+  {
+    violations.push(OrderViolation::TooManyOpenOrders { open_orders, limit });
+  }
+
+  // TRAILING_DELTA, "above" for orders triggered by a rising price
+  if let (Some(delta), Some(trailing)) = (request.trailing_delta, &filters.trailing_delta) {
+    let is_above = matches!(
+      (request.order_type, request.order_side),
+      (
+        OrderType::StopLoss | OrderType::StopLossLimit,
+        OrderSide::Buy
+      ) | (
+        OrderType::TakeProfit | OrderType::TakeProfitLimit,
+        OrderSide::Sell
+      )
+    );
+    let (min, max) = if is_above {
+      (
+        trailing.min_trailing_above_delta,
+        trailing.max_trailing_above_delta,
+      )
+    } else {
+      (
+        trailing.min_trailing_below_delta,
+        trailing.max_trailing_below_delta,
+      )
+    };
+    check_range(
+      &mut violations,
+      "TRAILING_DELTA",
+      "trailingDelta",
+      delta,
+      min as f64,
+      Some(max as f64).filter(|max| *max > 0.0),
+    );
+  }
+
+  violations
+}
+
+/// Whether a filter of the symbol checks the order against the average price:
+/// PERCENT_PRICE(_BY_SIDE) for orders with a price,
+/// MIN_NOTIONAL and NOTIONAL for market orders with a base quantity
+pub fn needs_avg_price(info: &SymbolInfo, request: &PlaceOrderRequest) -> bool {
+  let filters = &info.filters;
+  let checks_price = request.price.is_some()
+    && (filters.percent_price.is_some() || filters.percent_price_by_side.is_some());
+  let checks_market_notional = request.order_type == OrderType::Market
+    && request.qty.is_some()
+    && request.quote_order_qty.is_none()
+    && (filters
+      .min_notional
+      .as_ref()
+      .is_some_and(|f| f.apply_to_market)
+      || filters
+        .notional
+        .as_ref()
+        .is_some_and(|f| f.apply_min_to_market || f.apply_max_to_market));
+
+  checks_price || checks_market_notional
+}
+
+/// Validates orders with a `SymbolRegistry` and the latest `OrderContext` of each symbol.
+/// Shared with `SpotTradeV3Manager::set_order_validator` to reject invalid orders locally,
+/// which fetches the average price of orders that need it unless it's set or cached
+#[derive(Debug, Default)]
+pub struct OrderValidator {
+  registry: RwLock<SymbolRegistry>,
+  contexts: RwLock<HashMap<String, OrderContext>>,
+  /// Fetched or streamed average prices and when they were set
+  avg_prices: RwLock<HashMap<String, (f64, Instant)>>,
+}
+
+impl OrderValidator {
+  pub fn new(registry: SymbolRegistry) -> Self {
+    Self {
+      registry: RwLock::new(registry),
+      contexts: RwLock::default(),
+      avg_prices: RwLock::default(),
+    }
+  }
+
+  /// Replace the trading rules, e.g. after reloading exchange information
+  pub fn set_registry(&self, registry: SymbolRegistry) {
+    *self.registry.write().unwrap() = registry;
+  }
+
+  /// Average price and open orders of a symbol for the checks depending on them
+  pub fn set_context<S: Into<String>>(&self, symbol: S, context: OrderContext) {
+    let symbol = symbol.into().to_uppercase();
+    self.contexts.write().unwrap().insert(symbol, context);
+  }
+
+  /// Cache the average price of a symbol, e.g. from the `<symbol>@avgPrice` stream.
+  /// It's used for `AVG_PRICE_MAX_AGE` unless the context of the symbol has one
+  pub fn set_avg_price<S: Into<String>>(&self, symbol: S, avg_price: f64) {
+    let symbol = symbol.into().to_uppercase();
+    self
+      .avg_prices
+      .write()
+      .unwrap()
+      .insert(symbol, (avg_price, Instant::now()));
+  }
+
+  /// Whether the order is checked against an average price that is neither set nor cached
+  pub fn is_avg_price_missing(&self, request: &PlaceOrderRequest) -> bool {
+    let registry = self.registry.read().unwrap();
+    let Some(info) = registry.get(&request.symbol) else {
+      return false;
+    };
+    needs_avg_price(info, request) && self.context(&info.symbol).avg_price.is_none()
+  }
+
+  /// Violations of the order, an unknown symbol is a violation
+  pub fn validate(&self, request: &PlaceOrderRequest) -> Vec<OrderViolation> {
+    let registry = self.registry.read().unwrap();
+    let Some(info) = registry.get(&request.symbol) else {
+      return vec![OrderViolation::UnknownSymbol];
+    };
+
+    validate_order(info, request, &self.context(&info.symbol))
+  }
+
+  /// Context set for the symbol, with the cached average price if it has none
+  fn context(&self, symbol: &str) -> OrderContext {
+    let mut context = self
+      .contexts
+      .read()
+      .unwrap()
+      .get(symbol)
+      .copied()
+      .unwrap_or_default();
+    if context.avg_price.is_none() {
+      context.avg_price = self
+        .avg_prices
+        .read()
+        .unwrap()
+        .get(symbol)
+        .filter(|(_, set_at)| set_at.elapsed() < AVG_PRICE_MAX_AGE)
+        .map(|(avg_price, _)| *avg_price);
+    }
+    context
+  }
+}
+
+impl SpotTradeV3Manager {
+  /// Check orders with the validator before sending them,
+  /// invalid orders fail with `OrderRejected` without a request.
+  /// Orders checked against the average price fail if it can't be fetched
+  pub fn set_order_validator(mut self, validator: Arc<OrderValidator>) -> Self {
+    self.order_validator = Some(validator);
+    self
+  }
+
+  pub(crate) async fn check_order(&self, request: &PlaceOrderRequest) -> Result<()> {
+    let Some(validator) = &self.order_validator else {
+      return Ok(());
+    };
+
+    if validator.is_avg_price_missing(request) {
+      let avg_price = self.fetch_avg_price(&request.symbol).await.map_err(|e| {
+        anyhow!(
+          "Failed to fetch the average price of {} to validate the order: {}",
+          request.symbol,
+          e
+        )
+      })?;
+      validator.set_avg_price(request.symbol.as_str(), avg_price);
+    }
+
+    let violations = validator.validate(request);
+    if violations.is_empty() {
+      return Ok(());
+    }
+    Err(
+      OrderRejected {
+        symbol: request.symbol.to_uppercase(),
+        violations,
+      }
+      .into(),
+    )
+  }
+
+  async fn fetch_avg_price(&self, symbol: &str) -> Result<f64> {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.to_uppercase());
+    let request = build_query(parameters);
+    self
+      .client
+      .get::<AveragePrice>(API::SpotV3(SpotV3::AvgPrice), Some(request))
+      .await
+      .map(|r| r.price)
+  }
+}
+
+/// Whether `value` is a multiple of `step`, within the float tolerance
+fn is_multiple(value: f64, step: f64) -> bool {
+  let ratio = value / step;
+  (ratio - ratio.round()).abs() <= STEP_TOLERANCE * ratio.abs().max(1.0)
+}
+
+fn check_lot_size(
+  violations: &mut Vec<OrderViolation>,
+  filter: &'static str,
+  field: &'static str,
+  qty: f64,
+  lot_size: &LotSizeFilter,
+) {
+  check_range(
+    violations,
+    filter,
+    field,
+    qty,
+    lot_size.min_qty,
+    Some(lot_size.max_qty).filter(|max| *max > 0.0),
+  );
+  if lot_size.step_size > 0.0 && !is_multiple(qty - lot_size.min_qty, lot_size.step_size) {
+    violations.push(OrderViolation::NotMultiple {
+      filter,
+      field,
+      value: qty,
+      step: lot_size.step_size,
+    });
+  }
+}
+
+fn check_range(
+  violations: &mut Vec<OrderViolation>,
+  filter: &'static str,
+  field: &'static str,
+  value: f64,
+  min: f64,
+  max: Option<f64>,
+) {
+  if value < min {
+    violations.push(OrderViolation::BelowMin {
+      filter,
+      field,
+      value,
+      min,
+    });
+  }
+  if let Some(max) = max
+    && value > max
+  {
+    violations.push(OrderViolation::AboveMax {
+      filter,
+      field,
+      value,
+      max,
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::Binance;
+  use crate::config::Config;
+  use crate::rest::spot::v3::market::symbol_registry::{
+    MinNotionalFilter, NotionalFilter, PercentPriceBySideFilter, PriceFilter, SymbolFilters,
+    TrailingDeltaFilter,
+  };
+  use crate::rest::spot::v3::trade::enums::TimeInForce;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+
+  const AVG_PRICE: f64 = 50_000.0;
+
+  fn symbol_info() -> SymbolInfo {
+    SymbolInfo {
+      symbol: "BTCUSDT".into(),
+      status: ExchangeSymbolStatus::Trading,
+      base_asset: "BTC".into(),
+      base_asset_precision: 8,
+      quote_asset: "USDT".into(),
+      quote_precision: 8,
+      order_types: vec![
+        OrderType::Limit,
+        OrderType::LimitMaker,
+        OrderType::Market,
+        OrderType::StopLoss,
+        OrderType::StopLossLimit,
+        OrderType::TakeProfit,
+        OrderType::TakeProfitLimit,
+      ],
+      iceberg_allowed: true,
+      oco_allowed: true,
+      oto_allowed: true,
+      quote_order_qty_market_allowed: true,
+      allow_trailing_stop: true,
+      cancel_replace_allowed: true,
+      is_spot_trading_allowed: true,
+      is_margin_trading_allowed: true,
+      permission_sets: vec![],
+      filters: SymbolFilters {
+        price: Some(PriceFilter {
+          min_price: 0.01,
+          max_price: 1_000_000.0,
+          tick_size: 0.01,
+        }),
+        percent_price_by_side: Some(PercentPriceBySideFilter {
+          bid_multiplier_up: 1.2,
+          bid_multiplier_down: 0.2,
+          ask_multiplier_up: 5.0,
+          ask_multiplier_down: 0.8,
+          avg_price_mins: Some(5),
+        }),
+        lot_size: Some(LotSizeFilter {
+          min_qty: 0.00001,
+          max_qty: 9_000.0,
+          step_size: 0.00001,
+        }),
+        market_lot_size: Some(LotSizeFilter {
+          min_qty: 0.0,
+          max_qty: 100.0,
+          step_size: 0.0,
+        }),
+        notional: Some(NotionalFilter {
+          min_notional: 5.0,
+          apply_min_to_market: true,
+          max_notional: Some(9_000_000.0),
+          apply_max_to_market: false,
+          avg_price_mins: Some(5),
+        }),
+        iceberg_parts: Some(10),
+        max_num_orders: Some(200),
+        trailing_delta: Some(TrailingDeltaFilter {
+          min_trailing_above_delta: 10,
+          max_trailing_above_delta: 2_000,
+          min_trailing_below_delta: 10,
+          max_trailing_below_delta: 2_000,
+        }),
+        ..Default::default()
+      },
+    }
+  }
+
+  fn context() -> OrderContext {
+    OrderContext {
+      avg_price: Some(AVG_PRICE),
+      open_orders: Some(0),
+    }
+  }
+
+  fn limit_order(order_side: OrderSide, qty: f64, price: f64) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+      symbol: "BTCUSDT".into(),
+      order_side,
+      order_type: OrderType::Limit,
+      time_in_force: Some(TimeInForce::GTC),
+      qty: Some(qty),
+      price: Some(price),
+      ..Default::default()
+    }
+  }
+
+  fn market_order(qty: f64) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+      symbol: "BTCUSDT".into(),
+      order_side: OrderSide::Buy,
+      order_type: OrderType::Market,
+      qty: Some(qty),
+      ..Default::default()
+    }
+  }
+
+  fn stop_loss_order(order_side: OrderSide, trailing_delta: f64) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+      order_type: OrderType::StopLoss,
+      price: None,
+      trailing_delta: Some(trailing_delta),
+      ..limit_order(order_side, 0.1, 0.0)
+    }
+  }
+
+  fn validate(info: &SymbolInfo, request: &PlaceOrderRequest) -> Vec<OrderViolation> {
+    validate_order(info, request, &context())
+  }
+
+  #[test]
+  fn accepts_valid_orders() {
+    let info = symbol_info();
+
+    assert!(validate(&info, &limit_order(OrderSide::Buy, 0.1, AVG_PRICE)).is_empty());
+    assert!(validate(&info, &limit_order(OrderSide::Sell, 0.1, AVG_PRICE)).is_empty());
+    assert!(validate(&info, &market_order(1.0)).is_empty());
+    assert!(validate(&info, &stop_loss_order(OrderSide::Sell, 100.0)).is_empty());
+  }
+
+  #[test]
+  fn checks_price_filter() {
+    let info = symbol_info();
+
+    assert!(validate(&info, &limit_order(OrderSide::Buy, 0.1, 50_000.01)).is_empty());
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Buy, 0.1, 50_000.005)),
+      vec![OrderViolation::NotMultiple {
+        filter: "PRICE_FILTER",
+        field: "price",
+        value: 50_000.005,
+        step: 0.01,
+      }]
+    );
+
+    let mut info = symbol_info();
+    info.filters.percent_price_by_side = None;
+    info.filters.notional = None;
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Buy, 0.1, 2_000_000.0)),
+      vec![OrderViolation::AboveMax {
+        filter: "PRICE_FILTER",
+        field: "price",
+        value: 2_000_000.0,
+        max: 1_000_000.0,
+      }]
+    );
+  }
+
+  #[test]
+  fn checks_lot_size() {
+    let info = symbol_info();
+
+    assert!(validate(&info, &limit_order(OrderSide::Buy, 0.10001, AVG_PRICE)).is_empty());
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Buy, 0.100005, AVG_PRICE)),
+      vec![OrderViolation::NotMultiple {
+        filter: "LOT_SIZE",
+        field: "quantity",
+        value: 0.100005,
+        step: 0.00001,
+      }]
+    );
+    assert!(
+      validate(&info, &limit_order(OrderSide::Buy, 0.000001, AVG_PRICE)).contains(
+        &OrderViolation::BelowMin {
+          filter: "LOT_SIZE",
+          field: "quantity",
+          value: 0.000001,
+          min: 0.00001,
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn checks_market_lot_size_of_market_orders_only() {
+    let info = symbol_info();
+
+    assert!(validate(&info, &market_order(100.0)).is_empty());
+    assert_eq!(
+      validate(&info, &market_order(150.0)),
+      vec![OrderViolation::AboveMax {
+        filter: "MARKET_LOT_SIZE",
+        field: "quantity",
+        value: 150.0,
+        max: 100.0,
+      }]
+    );
+    assert!(validate(&info, &limit_order(OrderSide::Sell, 150.0, AVG_PRICE)).is_empty());
+  }
+
+  #[test]
+  fn checks_min_notional() {
+    let mut info = symbol_info();
+    info.filters.notional = None;
+    info.filters.min_notional = Some(MinNotionalFilter {
+      min_notional: 10.0,
+      apply_to_market: true,
+      avg_price_mins: Some(5),
+    });
+
+    assert!(validate(&info, &limit_order(OrderSide::Buy, 0.001, 10_000.0)).is_empty());
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Sell, 0.0001, 50_000.0)),
+      vec![OrderViolation::BelowMin {
+        filter: "MIN_NOTIONAL",
+        field: "notional",
+        value: 5.0,
+        min: 10.0,
+      }]
+    );
+  }
+
+  #[test]
+  fn checks_notional() {
+    let info = symbol_info();
+
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Sell, 0.00005, 50_000.0)),
+      vec![OrderViolation::BelowMin {
+        filter: "NOTIONAL",
+        field: "notional",
+        value: 2.5,
+        min: 5.0,
+      }]
+    );
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Sell, 200.0, 50_000.0)),
+      vec![OrderViolation::AboveMax {
+        filter: "NOTIONAL",
+        field: "notional",
+        value: 10_000_000.0,
+        max: 9_000_000.0,
+      }]
+    );
+    // The maximum doesn't apply to market orders here, the minimum does
+    let mut market = market_order(0.0);
+    market.qty = None;
+    market.quote_order_qty = Some(10_000_000.0);
+    assert!(validate(&info, &market).is_empty());
+    market.quote_order_qty = Some(1.0);
+    assert_eq!(
+      validate(&info, &market)
+        .iter()
+        .filter_map(|violation| violation.filter())
+        .collect::<Vec<_>>(),
+      vec!["NOTIONAL"]
+    );
+  }
+
+  #[test]
+  fn checks_percent_price_by_side() {
+    let info = symbol_info();
+
+    assert!(validate(&info, &limit_order(OrderSide::Buy, 0.1, 59_000.0)).is_empty());
+    assert!(validate(&info, &limit_order(OrderSide::Sell, 0.1, 41_000.0)).is_empty());
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Buy, 0.1, 61_000.0)),
+      vec![OrderViolation::AboveMax {
+        filter: "PERCENT_PRICE_BY_SIDE",
+        field: "price",
+        value: 61_000.0,
+        max: 60_000.0,
+      }]
+    );
+    assert_eq!(
+      validate(&info, &limit_order(OrderSide::Sell, 0.1, 39_000.0)),
+      vec![OrderViolation::BelowMin {
+        filter: "PERCENT_PRICE_BY_SIDE",
+        field: "price",
+        value: 39_000.0,
+        min: 40_000.0,
+      }]
+    );
+  }
+
+  #[test]
+  fn checks_iceberg_parts() {
+    let info = symbol_info();
+    let iceberg = |iceberg_qty: f64| PlaceOrderRequest {
+      iceberg_qty: Some(iceberg_qty),
+      ..limit_order(OrderSide::Buy, 1.0, AVG_PRICE)
+    };
+
+    assert!(validate(&info, &iceberg(0.1)).is_empty());
+    assert_eq!(
+      validate(&info, &iceberg(0.05)),
+      vec![OrderViolation::TooManyIcebergParts {
+        parts: 20,
+        limit: 10,
+      }]
+    );
+  }
+
+  #[test]
+  fn checks_max_num_orders() {
+    let info = symbol_info();
+    let order = limit_order(OrderSide::Buy, 0.1, AVG_PRICE);
+    let with_open_orders = |open_orders: u16| OrderContext {
+      open_orders: Some(open_orders),
+      ..context()
+    };
+
+    assert!(validate_order(&info, &order, &with_open_orders(199)).is_empty());
+    assert_eq!(
+      validate_order(&info, &order, &with_open_orders(200)),
+      vec![OrderViolation::TooManyOpenOrders {
+        open_orders: 200,
+        limit: 200,
+      }]
+    );
+  }
+
+  #[test]
+  fn checks_trailing_delta() {
+    let info = symbol_info();
+
+    assert_eq!(
+      validate(&info, &stop_loss_order(OrderSide::Sell, 5.0)),
+      vec![OrderViolation::BelowMin {
+        filter: "TRAILING_DELTA",
+        field: "trailingDelta",
+        value: 5.0,
+        min: 10.0,
+      }]
+    );
+    assert_eq!(
+      validate(&info, &stop_loss_order(OrderSide::Buy, 3_000.0)),
+      vec![OrderViolation::AboveMax {
+        filter: "TRAILING_DELTA",
+        field: "trailingDelta",
+        value: 3_000.0,
+        max: 2_000.0,
+      }]
+    );
+  }
+
+  #[test]
+  fn requires_stop_price_of_stop_orders() {
+    let info = symbol_info();
+
+    for order_type in [
+      OrderType::StopLoss,
+      OrderType::StopLossLimit,
+      OrderType::TakeProfit,
+      OrderType::TakeProfitLimit,
+    ] {
+      let order = PlaceOrderRequest {
+        order_type,
+        ..limit_order(OrderSide::Sell, 0.1, AVG_PRICE)
+      };
+      assert_eq!(
+        validate(&info, &order),
+        vec![OrderViolation::MissingField { field: "stopPrice" }],
+        "{}",
+        order_type
+      );
+
+      let order = PlaceOrderRequest {
+        stop_price: Some(AVG_PRICE),
+        ..order
+      };
+      assert!(validate(&info, &order).is_empty(), "{}", order_type);
+    }
+  }
+
+  #[test]
+  fn needs_avg_price_of_percent_price_and_market_notional() {
+    let info = symbol_info();
+
+    assert!(needs_avg_price(
+      &info,
+      &limit_order(OrderSide::Buy, 0.1, AVG_PRICE)
+    ));
+    assert!(needs_avg_price(&info, &market_order(0.1)));
+    let quote_order = PlaceOrderRequest {
+      qty: None,
+      quote_order_qty: Some(100.0),
+      ..market_order(0.1)
+    };
+    assert!(!needs_avg_price(&info, &quote_order));
+
+    let mut info = symbol_info();
+    info.filters.percent_price_by_side = None;
+    info.filters.notional = None;
+    assert!(!needs_avg_price(
+      &info,
+      &limit_order(OrderSide::Buy, 0.1, AVG_PRICE)
+    ));
+    assert!(!needs_avg_price(&info, &market_order(0.1)));
+  }
+
+  #[test]
+  fn validates_with_cached_avg_prices() {
+    let mut registry = SymbolRegistry::default();
+    registry.insert(symbol_info());
+    let validator = OrderValidator::new(registry);
+    let order = market_order(0.00001);
+
+    assert!(validator.is_avg_price_missing(&order));
+    assert!(validator.validate(&order).is_empty());
+
+    validator.set_avg_price("btcusdt", AVG_PRICE);
+    assert!(!validator.is_avg_price_missing(&order));
+    assert_eq!(
+      validator.validate(&order),
+      vec![OrderViolation::BelowMin {
+        filter: "NOTIONAL",
+        field: "notional",
+        value: 0.5,
+        min: 5.0,
+      }]
+    );
+
+    // The average price of the context is used over the cached one
+    validator.set_context(
+      "BTCUSDT",
+      OrderContext {
+        avg_price: Some(1_000_000.0),
+        open_orders: None,
+      },
+    );
+    assert!(validator.validate(&order).is_empty());
+  }
+
+  /// REST server answering average price requests, counting them
+  async fn avg_price_server(requests: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+      while let Ok((mut socket, _)) = listener.accept().await {
+        let requests = requests.clone();
+        tokio::spawn(async move {
+          let mut request = vec![];
+          let mut buffer = [0u8; 1024];
+          while !request.ends_with(b"\r\n\r\n") {
+            match socket.read(&mut buffer).await {
+              Ok(0) | Err(_) => return,
+              Ok(read) => request.extend_from_slice(&buffer[..read]),
+            }
+          }
+          assert!(String::from_utf8_lossy(&request).contains("/api/v3/avgPrice?symbol=BTCUSDT"));
+          requests.fetch_add(1, Ordering::SeqCst);
+          let body = r#"{"mins":5,"price":"50000.0","closeTime":1694061154503}"#;
+          let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+          );
+          let _ = socket.write_all(response.as_bytes()).await;
+        });
+      }
+    });
+    host
+  }
+
+  #[tokio::test]
+  async fn fetches_missing_avg_prices_before_checking_orders() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let config = Config::default().set_rest_api_endpoint(avg_price_server(requests.clone()).await);
+    let mut registry = SymbolRegistry::default();
+    registry.insert(symbol_info());
+    let manager = SpotTradeV3Manager::new_with_config(None, None, &config)
+      .set_order_validator(Arc::new(OrderValidator::new(registry)));
+
+    for _ in 0..2 {
+      let error = manager
+        .check_order(&market_order(0.00001))
+        .await
+        .unwrap_err();
+      let rejected = error.downcast_ref::<OrderRejected>().unwrap();
+      assert_eq!(rejected.violations[0].filter(), Some("NOTIONAL"));
+    }
+    // The second order used the cached average price
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+  }
+}
@@ -159,11 +159,19 @@ pub struct TradeRecordResponse {
   pub price: f64,
   #[serde(with = "string_to_float")]
   pub qty: f64,
+  /// Account trades only, empty for market trades
+  #[serde(default)]
   pub commission: String,
+  #[serde(default)]
   pub commission_asset: String,
   pub time: u64,
+  #[serde(default)]
   pub is_buyer: bool,
+  #[serde(default)]
   pub is_maker: bool,
+  /// Market trades only
+  #[serde(default)]
+  pub is_buyer_maker: bool,
   pub is_best_match: bool,
 }
 
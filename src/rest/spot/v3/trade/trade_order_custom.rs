@@ -13,6 +13,7 @@ impl SpotTradeV3Manager {
     &self,
     order_request: PlaceOrderRequest,
   ) -> Result<OrderCreatedResponse> {
+    self.check_order(&order_request).await?;
     let order = order_request.build_params_tree();
     let request = build_signed_query(order, self.recv_window)?;
 
@@ -29,6 +30,7 @@ impl SpotTradeV3Manager {
     &self,
     order_request: PlaceOrderRequest,
   ) -> Result<EmptyResponse> {
+    self.check_order(&order_request).await?;
     let order = order_request.build_params_tree();
     let request = build_signed_query(order, self.recv_window)?;
 
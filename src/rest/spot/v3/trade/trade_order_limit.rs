@@ -104,6 +104,7 @@ impl SpotTradeV3Manager {
     request.time_in_force = Some(TimeInForce::GTC);
     request.new_client_order_id = None;
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
@@ -135,6 +136,7 @@ impl SpotTradeV3Manager {
     request.order_type = OrderType::Limit;
     request.time_in_force = Some(TimeInForce::GTC);
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
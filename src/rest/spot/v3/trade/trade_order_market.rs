@@ -78,6 +78,7 @@ impl SpotTradeV3Manager {
     request.order_side = order_side;
     request.order_type = OrderType::Market;
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
@@ -105,6 +106,7 @@ impl SpotTradeV3Manager {
     request.order_type = OrderType::Market;
     request.time_in_force = Some(TimeInForce::GTC);
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
@@ -90,6 +90,7 @@ impl SpotTradeV3Manager {
     request.order_side = order_side;
     request.order_type = OrderType::Market;
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
 
     let query = build_signed_query(params_tree, self.recv_window)?;
@@ -117,6 +118,7 @@ impl SpotTradeV3Manager {
     request.order_side = order_side;
     request.order_type = OrderType::Market;
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
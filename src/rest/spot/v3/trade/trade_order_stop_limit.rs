@@ -153,6 +153,7 @@ impl SpotTradeV3Manager {
     request.order_type = OrderType::StopLossLimit;
     request.time_in_force = Some(time_in_force);
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
@@ -191,6 +192,7 @@ impl SpotTradeV3Manager {
     request.order_type = OrderType::StopLossLimit;
     request.time_in_force = Some(time_in_force);
 
+    self.check_order(&request).await?;
     let params_tree = request.build_params_tree();
     let query = build_signed_query(params_tree, self.recv_window)?;
 
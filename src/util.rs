@@ -1,5 +1,10 @@
 use anyhow::bail;
 use anyhow::Result;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use ed25519_dalek::{Signer, SigningKey};
+use hex::encode as hex_encode;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -35,6 +40,18 @@ pub fn build_signed_query_custom(
   bail!("Failed to get timestamp")
 }
 
+/// HMAC SHA256 signature of a query, hex encoded
+pub fn sign_query(secret_key: &str, query: &str) -> Result<String> {
+  let mut signed_key = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())?;
+  signed_key.update(query.as_bytes());
+  Ok(hex_encode(signed_key.finalize().into_bytes()))
+}
+
+/// Ed25519 signature of a query, base64 encoded
+pub fn sign_query_ed25519(signing_key: &SigningKey, query: &str) -> String {
+  BASE64_STANDARD.encode(signing_key.sign(query.as_bytes()).to_bytes())
+}
+
 fn get_timestamp(time: SystemTime) -> Result<u64> {
   Ok(
     time
@@ -44,11 +61,12 @@ fn get_timestamp(time: SystemTime) -> Result<u64> {
   )
 }
 
+/// Start time in seconds or milliseconds is not in the future
 pub fn is_start_time_valid(start_time: &u64) -> bool {
   let current_time = SystemTime::now()
     .duration_since(UNIX_EPOCH)
     .unwrap()
-    .as_secs();
+    .as_millis() as u64;
 
   start_time <= &current_time
 }
@@ -74,7 +92,7 @@ macro_rules! create_enum_with_fmt {
       $(, $variant:ident => $display:expr)* $(,)?
     }
   ) => {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub enum $name {
       $first_variant,
       $($variant),*
@@ -117,5 +135,22 @@ macro_rules! create_enum_with_fmt {
         }
       }
     }
+
+    /// Parses the same value the variant is displayed as
+    impl std::str::FromStr for $name {
+      type Err = anyhow::Error;
+
+      fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == $first_display {
+          return Ok($name::$first_variant);
+        }
+        $(
+        if value == $display {
+          return Ok($name::$variant);
+        }
+        )*
+        Err(anyhow::anyhow!("Unknown {} value: {}", stringify!($name), value))
+      }
+    }
   }
 }
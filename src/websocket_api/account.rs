@@ -0,0 +1,11 @@
+use super::WebSocketApiClient;
+use crate::rest::spot::v3::account::responses::AccountInformationResponse;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+impl WebSocketApiClient {
+  /// Account information with balances, `account.status`
+  pub async fn fetch_info_summary(&self) -> Result<AccountInformationResponse> {
+    self.signed_request("account.status", BTreeMap::new()).await
+  }
+}
@@ -0,0 +1,465 @@
+use crate::client::Binance;
+use crate::config::Config;
+use crate::rest::core::rate_limiter::ip_rate_limit_manager::IpRateLimitManager;
+use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::UnfilledOrderRateLimitManager;
+use crate::util::{build_query, sign_query, sign_query_ed25519};
+use crate::websocket_stream::spot::events::{ConnectionState, WebsocketSpotEvent};
+use anyhow::{anyhow, bail, Result};
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::SigningKey;
+use futures_util::{SinkExt, StreamExt};
+use rate_limits::RateLimitFeed;
+use responses::ApiResponse;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+pub mod account;
+mod rate_limits;
+pub mod responses;
+pub mod session;
+pub mod trade;
+
+/// Time to wait for the response of a request
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Events kept for the slowest receiver of `event_receiver`
+const EVENTS_CAPACITY: usize = 1024;
+
+/// Parameters sent as JSON numbers, the rest is sent as strings
+const INTEGER_PARAMS: [&str; 7] = [
+  "timestamp",
+  "recvWindow",
+  "orderId",
+  "orderListId",
+  "strategyId",
+  "strategyType",
+  "subscriptionId",
+];
+
+type ApiSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type ApiResponder = oneshot::Sender<Result<Value>>;
+
+/// Command enum that the internal actor will handle
+enum Command {
+  Request(u64, String, Value, ApiResponder),
+  /// Forget the request, its caller stopped waiting for the response
+  Cancel(u64),
+  Shutdown,
+}
+
+struct WebSocketApiActor {
+  host: String,
+  /// Connection, opened by the first request
+  socket: Option<ApiSocket>,
+  /// Requests waiting for a response, by id
+  pending_requests: HashMap<u64, ApiResponder>,
+  rate_limits: RateLimitFeed,
+  /// User data events and connection state changes
+  events: broadcast::Sender<WebsocketSpotEvent>,
+}
+
+impl WebSocketApiActor {
+  /// Send a request, connecting first if needed
+  async fn send_request(
+    &mut self,
+    id: u64,
+    method: String,
+    params: Value,
+    responder: ApiResponder,
+  ) {
+    if self.socket.is_none() {
+      match connect_async(self.host.as_str()).await {
+        Ok((socket, _)) => {
+          self.socket = Some(socket);
+          self.emit(WebsocketSpotEvent::ConnectionState(
+            ConnectionState::Connected,
+          ));
+        }
+        Err(e) => {
+          let _ = responder.send(Err(anyhow!("Failed to connect: {}", e)));
+          return;
+        }
+      }
+    }
+
+    let mut request = json!({ "id": id, "method": method });
+    if !params.is_null() {
+      request["params"] = params;
+    }
+
+    let Some(socket) = &mut self.socket else {
+      return;
+    };
+    match socket.send(Message::Text(request.to_string().into())).await {
+      Ok(()) => {
+        self.pending_requests.insert(id, responder);
+      }
+      Err(e) => {
+        let _ = responder.send(Err(anyhow!("Failed to send request: {}", e)));
+        self.connection_lost(format!("Failed to send request: {}", e));
+      }
+    }
+  }
+
+  /// Route a message to the request it answers, or emit the user data event it holds
+  async fn handle_incoming_message(&mut self, msg: &str) {
+    let Ok(response) = serde_json::from_str::<ApiResponse>(msg) else {
+      self.emit(WebsocketSpotEvent::Unknown(msg.to_string()));
+      return;
+    };
+
+    if let Some(rate_limits) = &response.rate_limits {
+      self.rate_limits.update(rate_limits).await;
+    }
+
+    if let Some(event) = response.event {
+      let event = WebsocketSpotEvent::from_message(None, event.get());
+      if let WebsocketSpotEvent::OrderTrade(report) = &event {
+        self.rate_limits.execution_report(&report.into()).await;
+      }
+      self.emit(event);
+      return;
+    }
+
+    let Some(responder) = response.id.and_then(|id| self.pending_requests.remove(&id)) else {
+      return;
+    };
+    let result = match (response.error, response.result) {
+      (Some(error), _) => Err(anyhow!(
+        "Binance error: code={}, msg={}",
+        error.code,
+        error.msg
+      )),
+      (None, result) => Ok(result.unwrap_or(Value::Null)),
+    };
+    let _ = responder.send(result);
+  }
+
+  /// Drop the broken connection, the next request opens a new one
+  fn connection_lost(&mut self, reason: String) {
+    self.socket = None;
+    for (_, responder) in self.pending_requests.drain() {
+      let _ = responder.send(Err(anyhow!("Connection lost before the response")));
+    }
+    self.emit(WebsocketSpotEvent::ConnectionState(
+      ConnectionState::Disconnected { reason },
+    ));
+  }
+
+  /// Deliver an event to the receivers, if any
+  fn emit(&self, event: WebsocketSpotEvent) {
+    let _ = self.events.send(event);
+  }
+
+  async fn run(mut self, mut cmd_rx: mpsc::Receiver<Command>) -> Result<()> {
+    loop {
+      tokio::select! {
+        command = cmd_rx.recv() => {
+          match command {
+            Some(Command::Request(id, method, params, responder)) => {
+              self.send_request(id, method, params, responder).await;
+            }
+            Some(Command::Cancel(id)) => {
+              self.pending_requests.remove(&id);
+            }
+            // The client asked to stop or was dropped
+            Some(Command::Shutdown) | None => break,
+          }
+        },
+
+        next_message = async {
+          match &mut self.socket {
+            Some(socket) => socket.next().await,
+            None => None,
+          }
+        }, if self.socket.is_some() => {
+          match next_message {
+            Some(Ok(Message::Text(msg))) => {
+              self.handle_incoming_message(&msg).await;
+            }
+            Some(Ok(Message::Ping(payload))) => {
+              if let Some(socket) = &mut self.socket
+                && let Err(e) = socket.send(Message::Pong(payload)).await
+              {
+                self.connection_lost(format!("Failed to send pong: {}", e));
+              }
+            }
+            Some(Ok(Message::Close(frame))) => {
+              let reason = match frame {
+                Some(frame) => format!("Closed by server: {} {}", frame.code, frame.reason),
+                None => "Closed by server".to_string(),
+              };
+              self.connection_lost(reason);
+            }
+            Some(Err(e)) => {
+              self.connection_lost(format!("WebSocket error: {}", e));
+            }
+            None => {
+              self.connection_lost("WebSocket stream ended".to_string());
+            }
+            _ => {}
+          }
+        }
+      }
+    }
+
+    if let Some(mut socket) = self.socket.take() {
+      socket.close(None).await?;
+    }
+    Ok(())
+  }
+}
+
+/// Client of the Binance WebSocket API, requests and responses over one persistent connection.
+///
+/// The connection is opened by the first request and again by the next one after it is lost.
+/// Requests are signed the same way as REST calls, or with an Ed25519 key if one is set.
+/// Requests acquire their weight from the rate limit managers of the `Config`,
+/// rate limits of the responses update them
+pub struct WebSocketApiClient {
+  command_tx: mpsc::Sender<Command>,
+  join_handle: JoinHandle<Result<()>>,
+  events: broadcast::Sender<WebsocketSpotEvent>,
+  /// Id of the last request
+  last_request_id: AtomicU64,
+  api_key: Option<String>,
+  secret_key: Option<String>,
+  signing_key: Option<SigningKey>,
+  recv_window: u64,
+  request_timeout: Duration,
+  ip_rate_limit_manager: Option<Arc<IpRateLimitManager>>,
+  unfilled_order_rate_limit_manager: Option<Arc<UnfilledOrderRateLimitManager>>,
+}
+
+impl Binance for WebSocketApiClient {
+  fn new(api_key: Option<String>, secret_key: Option<String>) -> Self {
+    Self::new_with_config(api_key, secret_key, &Config::default())
+  }
+
+  fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
+    let (tx, rx) = mpsc::channel(32);
+    let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+
+    let actor = WebSocketApiActor {
+      host: config.ws_api_host.clone(),
+      socket: None,
+      pending_requests: HashMap::new(),
+      rate_limits: RateLimitFeed::new(
+        config.ip_rate_limit_manager.clone(),
+        config.unfilled_order_rate_limit_manager.clone(),
+      ),
+      events: events.clone(),
+    };
+
+    let join_handle = tokio::spawn(async move {
+      let result = actor.run(rx).await;
+      if let Err(ref e) = result {
+        eprintln!("WebSocket API actor error: {:?}", e);
+      }
+      result
+    });
+
+    Self {
+      command_tx: tx,
+      join_handle,
+      events,
+      last_request_id: AtomicU64::new(0),
+      api_key,
+      secret_key,
+      signing_key: None,
+      recv_window: config.recv_window,
+      request_timeout: DEFAULT_REQUEST_TIMEOUT,
+      ip_rate_limit_manager: config.ip_rate_limit_manager.clone(),
+      unfilled_order_rate_limit_manager: config.unfilled_order_rate_limit_manager.clone(),
+    }
+  }
+}
+
+impl WebSocketApiClient {
+  /// Time to wait for the response of a request, default 10 seconds
+  pub fn set_request_timeout(mut self, request_timeout: Duration) -> Self {
+    self.request_timeout = request_timeout;
+    self
+  }
+
+  /// Sign requests with an Ed25519 private key in PKCS#8 PEM format instead of the secret key.
+  /// Required by `session_logon`, the api key has to be the one of this Ed25519 key
+  pub fn set_ed25519_private_key(mut self, private_key_pem: &str) -> Result<Self> {
+    let signing_key = SigningKey::from_pkcs8_pem(private_key_pem)
+      .map_err(|e| anyhow!("Invalid Ed25519 private key: {}", e))?;
+    self.signing_key = Some(signing_key);
+    Ok(self)
+  }
+
+  /// New receiver of user data events and connection state changes from now on
+  pub fn event_receiver(&self) -> broadcast::Receiver<WebsocketSpotEvent> {
+    self.events.subscribe()
+  }
+
+  /// Send a request and wait for its `result`, its weight is acquired first
+  pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+    if let Some(ip_rate_limiter) = &self.ip_rate_limit_manager {
+      ip_rate_limiter
+        .acquire_weight(request_weight(method, &params))
+        .await?;
+    }
+
+    let id = self.last_request_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let (tx, rx) = oneshot::channel();
+    self
+      .command_tx
+      .send(Command::Request(id, method.to_string(), params, tx))
+      .await
+      .map_err(|_| anyhow!("Actor task ended"))?;
+
+    let result = match tokio::time::timeout(self.request_timeout, rx).await {
+      Ok(Ok(response)) => response?,
+      Ok(Err(_)) => bail!("Actor task ended or response channel closed"),
+      Err(_) => {
+        let _ = self.command_tx.send(Command::Cancel(id)).await;
+        bail!(
+          "No response from the server within {:?}",
+          self.request_timeout
+        )
+      }
+    };
+    Ok(serde_json::from_value(result)?)
+  }
+
+  /// Send a request with `apiKey`, `timestamp`, `recvWindow` and the signature of the parameters
+  pub async fn signed_request<T: DeserializeOwned>(
+    &self,
+    method: &str,
+    params: BTreeMap<String, String>,
+  ) -> Result<T> {
+    let params = self.sign_params(params)?;
+    self.request(method, params).await
+  }
+
+  /// Send a signed request placing `orders` orders, the unfilled order limit is checked first
+  pub(crate) async fn order_request<T: DeserializeOwned>(
+    &self,
+    method: &str,
+    params: BTreeMap<String, String>,
+    orders: u64,
+  ) -> Result<T> {
+    if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager {
+      order_rate_limiter.acquire_orders(orders).await?;
+    }
+    self.signed_request(method, params).await
+  }
+
+  /// Parameters with the api key, timestamp and signature
+  fn sign_params(&self, mut params: BTreeMap<String, String>) -> Result<Value> {
+    let Some(api_key) = &self.api_key else {
+      bail!("API key is required for signed requests");
+    };
+    params.insert("apiKey".into(), api_key.clone());
+    if self.recv_window > 0 {
+      params.insert("recvWindow".into(), self.recv_window.to_string());
+    }
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    params.insert("timestamp".into(), timestamp.to_string());
+
+    signed_params(params, |payload| {
+      match (&self.signing_key, &self.secret_key) {
+        (Some(signing_key), _) => Ok(sign_query_ed25519(signing_key, payload)),
+        (None, Some(secret_key)) => sign_query(secret_key, payload),
+        (None, None) => bail!("Secret key or Ed25519 private key is required for signed requests"),
+      }
+    })
+  }
+
+  /// Ask the actor to close the connection
+  pub async fn shutdown(&self) -> Result<()> {
+    self
+      .command_tx
+      .send(Command::Shutdown)
+      .await
+      .map_err(|_| anyhow!("Actor task ended"))
+  }
+
+  /// Wait for the actor to finish after `shutdown`
+  pub async fn wait_for_end(self) -> Result<()> {
+    match self.join_handle.await {
+      Ok(result) => result,
+      Err(e) => Err(anyhow!("Join error: {:?}", e)),
+    }
+  }
+}
+
+/// Request weight of a WebSocket API method, 1 for the ones not listed
+fn request_weight(method: &str, params: &Value) -> u64 {
+  match method {
+    "account.status" => 20,
+    "order.status" => 4,
+    "openOrders.status" if params.get("symbol").is_some() => 6,
+    "openOrders.status" => 80,
+    "session.logon" | "session.status" | "session.logout" => 2,
+    "userDataStream.subscribe" | "userDataStream.unsubscribe" => 2,
+    _ => 1,
+  }
+}
+
+/// Parameters with the signature of their payload, the `key=value` pairs in alphabetical
+/// order joined with `&` like the query of a signed REST call. Values are signed and sent
+/// as they are, so they may contain `=` and `&`
+fn signed_params<F>(params: BTreeMap<String, String>, sign: F) -> Result<Value>
+where
+  F: FnOnce(&str) -> Result<String>,
+{
+  let signature = sign(&build_query(params.clone()))?;
+
+  let mut signed = Map::new();
+  for (key, value) in params {
+    let value = match value.parse::<i64>() {
+      Ok(number) if INTEGER_PARAMS.contains(&key.as_str()) => Value::from(number),
+      _ => Value::from(value),
+    };
+    signed.insert(key, value);
+  }
+  signed.insert("signature".into(), Value::from(signature));
+  Ok(Value::Object(signed))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn signs_values_with_separators_as_they_are() {
+    let mut params = BTreeMap::new();
+    params.insert("symbol".to_string(), "BTCUSDT".to_string());
+    params.insert("newClientOrderId".to_string(), "a=b&c=d".to_string());
+    params.insert("timestamp".to_string(), "1700000000000".to_string());
+
+    let mut payload = String::new();
+    let signed = signed_params(params, |query| {
+      payload = query.to_string();
+      sign_query("secret", query)
+    })
+    .unwrap();
+
+    assert_eq!(
+      payload,
+      "newClientOrderId=a=b&c=d&symbol=BTCUSDT&timestamp=1700000000000"
+    );
+    assert_eq!(
+      signed,
+      json!({
+        "newClientOrderId": "a=b&c=d",
+        "symbol": "BTCUSDT",
+        "timestamp": 1700000000000i64,
+        "signature": sign_query("secret", &payload).unwrap(),
+      })
+    );
+  }
+}
@@ -0,0 +1,87 @@
+use crate::rest::core::rate_limiter::ip_rate_limit_manager::{
+  IpIntervalAndNum, IpRateLimitManager,
+};
+use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::{
+  OrderExecution, OrderIntervalAndNum, UnfilledOrderRateLimitManager,
+};
+use crate::rest::spot::v3::account::responses::{
+  AccountRateLimitIntervalResponse, AccountRateLimitResponse,
+};
+use crate::rest::spot::v3::market::responses::RateLimitIntervalResponse;
+use std::sync::Arc;
+
+/// Passes the `rateLimits` of responses on to the rate limit managers,
+/// the same counts REST calls report in headers, and fills of execution reports
+pub(crate) struct RateLimitFeed {
+  ip_rate_limit_manager: Option<Arc<IpRateLimitManager>>,
+  unfilled_order_rate_limit_manager: Option<Arc<UnfilledOrderRateLimitManager>>,
+}
+
+impl RateLimitFeed {
+  pub(crate) fn new(
+    ip_rate_limit_manager: Option<Arc<IpRateLimitManager>>,
+    unfilled_order_rate_limit_manager: Option<Arc<UnfilledOrderRateLimitManager>>,
+  ) -> Self {
+    Self {
+      ip_rate_limit_manager,
+      unfilled_order_rate_limit_manager,
+    }
+  }
+
+  pub(crate) async fn update(&self, rate_limits: &[AccountRateLimitResponse]) {
+    for rate_limit in rate_limits {
+      match rate_limit.rate_limit_type.as_str() {
+        "REQUEST_WEIGHT" => {
+          if let Some(ip_rate_limiter) = &self.ip_rate_limit_manager {
+            let interval = IpIntervalAndNum {
+              interval: ip_interval(&rate_limit.interval),
+              interval_num: rate_limit.interval_num,
+            };
+            let _ = ip_rate_limiter
+              .set_weight_limit(&interval, rate_limit.limit)
+              .await;
+            if let Err(e) = ip_rate_limiter
+              .set_weight_count(&interval, rate_limit.count)
+              .await
+            {
+              eprintln!("Failed to update used weight from response: {}", e);
+            }
+          }
+        }
+        "ORDERS" => {
+          if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager {
+            let interval = OrderIntervalAndNum {
+              interval: rate_limit.interval.clone(),
+              interval_num: rate_limit.interval_num,
+            };
+            let _ = order_rate_limiter
+              .set_order_limit(&interval, rate_limit.limit)
+              .await;
+            if let Err(e) = order_rate_limiter
+              .set_order_count(&interval, rate_limit.count)
+              .await
+            {
+              eprintln!("Failed to update order count from response: {}", e);
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// Release filled orders of an `executionReport` from the unfilled order count
+  pub(crate) async fn execution_report(&self, execution: &OrderExecution) {
+    if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager {
+      order_rate_limiter.handle_execution_report(execution).await;
+    }
+  }
+}
+
+fn ip_interval(interval: &AccountRateLimitIntervalResponse) -> RateLimitIntervalResponse {
+  match interval {
+    AccountRateLimitIntervalResponse::Second => RateLimitIntervalResponse::Second,
+    AccountRateLimitIntervalResponse::Minute => RateLimitIntervalResponse::Minute,
+    AccountRateLimitIntervalResponse::Day => RateLimitIntervalResponse::Day,
+  }
+}
@@ -0,0 +1,36 @@
+use crate::errors::BinanceContentError;
+use crate::rest::spot::v3::account::responses::AccountRateLimitResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+/// Message of the WebSocket API, a response to a request or a user data event
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiResponse {
+  pub id: Option<u64>,
+  pub result: Option<Value>,
+  pub error: Option<BinanceContentError>,
+  pub rate_limits: Option<Vec<AccountRateLimitResponse>>,
+  /// Event of a user data subscription
+  pub event: Option<Box<RawValue>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatusResponse {
+  /// Key the session is authenticated with, `None` before `session.logon`
+  pub api_key: Option<String>,
+  pub authorized_since: Option<u64>,
+  pub connected_since: u64,
+  pub return_rate_limits: bool,
+  pub server_time: u64,
+  /// Whether the session is subscribed to the user data stream
+  pub user_data_stream: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataSubscriptionResponse {
+  pub subscription_id: Option<u64>,
+}
@@ -0,0 +1,47 @@
+use super::responses::{SessionStatusResponse, UserDataSubscriptionResponse};
+use super::WebSocketApiClient;
+use crate::model::EmptyResponse;
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+impl WebSocketApiClient {
+  /// Authenticate the connection, `session.logon`.
+  ///
+  /// Binance accepts it for Ed25519 API keys only, see `set_ed25519_private_key`.
+  /// Without a session every signed request carries its own signature,
+  /// which works with HMAC keys too
+  pub async fn session_logon(&self) -> Result<SessionStatusResponse> {
+    if self.signing_key.is_none() {
+      bail!("session.logon requires an Ed25519 private key");
+    }
+    self.signed_request("session.logon", BTreeMap::new()).await
+  }
+
+  /// Authentication state of the connection, `session.status`
+  pub async fn session_status(&self) -> Result<SessionStatusResponse> {
+    self.request("session.status", Value::Null).await
+  }
+
+  /// Forget the authenticated key of the connection, `session.logout`
+  pub async fn session_logout(&self) -> Result<SessionStatusResponse> {
+    self.request("session.logout", Value::Null).await
+  }
+
+  /// Receive user data events over this connection, `userDataStream.subscribe`.
+  ///
+  /// Requires an authenticated session. Events arrive at `event_receiver`,
+  /// the subscription ends with the connection
+  pub async fn subscribe_user_data(&self) -> Result<UserDataSubscriptionResponse> {
+    self.request("userDataStream.subscribe", Value::Null).await
+  }
+
+  /// Stop user data events of the subscription, or of all if `None`, `userDataStream.unsubscribe`
+  pub async fn unsubscribe_user_data(&self, subscription_id: Option<u64>) -> Result<EmptyResponse> {
+    let params = match subscription_id {
+      Some(subscription_id) => json!({ "subscriptionId": subscription_id }),
+      None => Value::Null,
+    };
+    self.request("userDataStream.unsubscribe", params).await
+  }
+}
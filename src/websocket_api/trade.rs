@@ -0,0 +1,131 @@
+use super::WebSocketApiClient;
+use crate::model::EmptyResponse;
+use crate::rest::spot::v3::trade::enums::{OrderSide, OrderType, TimeInForce};
+use crate::rest::spot::v3::trade::requests::PlaceOrderRequest;
+use crate::rest::spot::v3::trade::responses::{
+  OrderCanceledResponse, OrderCreatedResponse, OrderInfoResponse,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+impl WebSocketApiClient {
+  /// Place an order of any type, `order.place`
+  pub async fn place_order(&self, request: PlaceOrderRequest) -> Result<OrderCreatedResponse> {
+    self
+      .order_request("order.place", request.build_params_tree(), 1)
+      .await
+  }
+
+  /// Validate an order without sending it to the matching engine, `order.test`
+  pub async fn test_place_order(&self, request: PlaceOrderRequest) -> Result<EmptyResponse> {
+    self
+      .signed_request("order.test", request.build_params_tree())
+      .await
+  }
+
+  /// Place a LIMIT GTC order
+  pub async fn place_limit_order<S, Q, PR>(
+    &self,
+    symbol: S,
+    order_side: OrderSide,
+    qty: Q,
+    price: PR,
+  ) -> Result<OrderCreatedResponse>
+  where
+    S: Into<String>,
+    Q: Into<f64>,
+    PR: Into<f64>,
+  {
+    let request = PlaceOrderRequest {
+      symbol: symbol.into(),
+      qty: Some(qty.into()),
+      price: Some(price.into()),
+      order_side,
+      order_type: OrderType::Limit,
+      time_in_force: Some(TimeInForce::GTC),
+      ..Default::default()
+    };
+
+    self.place_order(request).await
+  }
+
+  /// Place a MARKET order
+  pub async fn place_market_order<S, Q>(
+    &self,
+    symbol: S,
+    order_side: OrderSide,
+    qty: Q,
+  ) -> Result<OrderCreatedResponse>
+  where
+    S: Into<String>,
+    Q: Into<f64>,
+  {
+    let request = PlaceOrderRequest {
+      symbol: symbol.into(),
+      qty: Some(qty.into()),
+      order_side,
+      order_type: OrderType::Market,
+      ..Default::default()
+    };
+
+    self.place_order(request).await
+  }
+
+  /// Cancel order with order id, `order.cancel`
+  pub async fn cancel_order_by_id<S, O>(
+    &self,
+    symbol: S,
+    order_id: O,
+  ) -> Result<OrderCanceledResponse>
+  where
+    S: Into<String>,
+    O: Into<u64>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.into());
+    parameters.insert("orderId".into(), order_id.into().to_string());
+
+    self.signed_request("order.cancel", parameters).await
+  }
+
+  /// Cancel order with client id, `order.cancel`
+  pub async fn cancel_order_by_client_id<S, C>(
+    &self,
+    symbol: S,
+    client_order_id: C,
+  ) -> Result<OrderCanceledResponse>
+  where
+    S: Into<String>,
+    C: Into<String>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.into());
+    parameters.insert("origClientOrderId".into(), client_order_id.into());
+
+    self.signed_request("order.cancel", parameters).await
+  }
+
+  /// Get an order's Info, `order.status`
+  pub async fn fetch_order_by_id<S, O>(&self, symbol: S, order_id: O) -> Result<OrderInfoResponse>
+  where
+    S: Into<String>,
+    O: Into<u64>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.into());
+    parameters.insert("orderId".into(), order_id.into().to_string());
+
+    self.signed_request("order.status", parameters).await
+  }
+
+  /// Current open orders for ONE symbol, `openOrders.status`
+  pub async fn list_open_orders_by_symbol<S>(&self, symbol: S) -> Result<Vec<OrderInfoResponse>>
+  where
+    S: Into<String>,
+  {
+    let mut parameters: BTreeMap<String, String> = BTreeMap::new();
+    parameters.insert("symbol".into(), symbol.into());
+
+    self.signed_request("openOrders.status", parameters).await
+  }
+}
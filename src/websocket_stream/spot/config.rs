@@ -0,0 +1,307 @@
+use crate::config::Config;
+use crate::websocket_stream::spot::enums::TimeUnit;
+use crate::websocket_stream::spot::recording::WebsocketRecorder;
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::WebsocketUrl;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const WS_HOST: &str = "wss://stream.binance.com:443";
+/// Market data only streams, no user data
+const MARKET_DATA_WS_HOST: &str = "wss://data-stream.binance.vision";
+/// SBE encoded market data streams
+const SBE_WS_HOST: &str = "wss://stream-sbe.binance.com:9443";
+
+/// Exponential backoff used by the actor to reconnect after the connection is lost
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+  /// Delay before the first reconnection attempt
+  pub initial_delay: Duration,
+  /// Upper bound of the delay between attempts
+  pub max_delay: Duration,
+  /// Factor the delay grows by after each failed attempt
+  pub multiplier: u32,
+  /// Attempts before giving up, `None` to retry forever
+  pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+  fn default() -> Self {
+    Self {
+      initial_delay: Duration::from_secs(1),
+      max_delay: Duration::from_secs(60),
+      multiplier: 2,
+      max_attempts: None,
+    }
+  }
+}
+
+impl ReconnectPolicy {
+  /// Never reconnect, give up as soon as the connection is lost
+  pub fn disabled() -> Self {
+    Self::default().set_max_attempts(0)
+  }
+
+  pub fn set_initial_delay(mut self, initial_delay: Duration) -> Self {
+    self.initial_delay = initial_delay;
+    self
+  }
+
+  pub fn set_max_delay(mut self, max_delay: Duration) -> Self {
+    self.max_delay = max_delay;
+    self
+  }
+
+  pub fn set_multiplier(mut self, multiplier: u32) -> Self {
+    self.multiplier = multiplier;
+    self
+  }
+
+  pub fn set_max_attempts<A: Into<Option<u32>>>(mut self, max_attempts: A) -> Self {
+    self.max_attempts = max_attempts.into();
+    self
+  }
+
+  /// Delay before the given attempt, starting from 1
+  pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+    let factor = self
+      .multiplier
+      .max(1)
+      .saturating_pow(attempt.saturating_sub(1));
+
+    self
+      .initial_delay
+      .saturating_mul(factor)
+      .min(self.max_delay)
+  }
+
+  /// Whether the given attempt, starting from 1, is allowed
+  pub fn allows_attempt(&self, attempt: u32) -> bool {
+    match self.max_attempts {
+      Some(max_attempts) => attempt <= max_attempts,
+      None => true,
+    }
+  }
+}
+
+/// What happens to incoming events while the event queue is full
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+  /// Pause reading from the socket until the handler catches up
+  Block,
+  /// Discard the oldest queued event to make room
+  DropOldest,
+  /// Discard the incoming event
+  DropNewest,
+  /// Replace the queued event of the same ticker stream with the newer one,
+  /// other events wait for room like `Block`
+  ConflatePerStream,
+}
+
+/// Encoding of the stream payloads
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamEncoding {
+  Json,
+  /// Simple Binary Encoding, needs the API key of an Ed25519 key pair
+  Sbe { api_key: String },
+}
+
+/// What the actor does when a stream stays silent longer than its heartbeat
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StaleStreamAction {
+  /// Only report `ConnectionState::StaleStream`
+  Notify,
+  /// Unsubscribe and subscribe the stream again on the same connection
+  Resubscribe,
+  /// Open the connection again with all its streams
+  Reconnect,
+}
+
+/// Settings of a `WebSocketSpotStream` connection
+#[derive(Clone, Debug)]
+pub struct WebSocketSpotConfig {
+  /// Scheme and host, optionally with a port, e.g. `wss://stream.binance.com:9443`
+  pub host: String,
+  /// Replaces the port of the host if set, Binance accepts 9443 and 443
+  pub port: Option<u16>,
+  pub url: WebsocketUrl,
+  /// Unit of the timestamps in events, milliseconds by default
+  pub time_unit: Option<TimeUnit>,
+  pub reconnect_policy: ReconnectPolicy,
+  /// How long to wait for the server to answer a request, e.g. SUBSCRIBE
+  pub request_timeout: Duration,
+  /// Streams per connection before a new one is opened. Binance allows up to 1024,
+  /// fewer keep the connection URL short and spread the incoming messages
+  pub max_streams_per_connection: usize,
+  /// Connections the subscriptions may be spread across, `None` for no limit
+  pub max_connections: Option<usize>,
+  /// Connection age at which a replacement is opened, Binance closes connections
+  /// after 24 hours. `None` to keep the connection until it is closed
+  pub rotation_interval: Option<Duration>,
+  /// Longest time both the old and the replacement connections are open
+  pub rotation_overlap: Duration,
+  /// Events read from the socket and waiting for the handler, per connection.
+  /// `None` to call the handler while reading, a slow handler then delays the pongs
+  pub event_queue_capacity: Option<usize>,
+  /// What to do with incoming events while the queue is full
+  pub backpressure_policy: BackpressurePolicy,
+  /// Writes the raw frames of the connections to a file if set
+  pub recorder: Option<WebsocketRecorder>,
+  /// Longest silence expected of a stream while connected, e.g. a few seconds for
+  /// a liquid bookTicker. Pings keep a connection open even if its streams stopped
+  pub heartbeats: HashMap<StreamName, Duration>,
+  /// What to do once a stream is silent past its heartbeat
+  pub stale_stream_action: StaleStreamAction,
+  /// Payload encoding the connections expect, JSON by default
+  pub encoding: StreamEncoding,
+}
+
+impl Default for WebSocketSpotConfig {
+  fn default() -> Self {
+    Self {
+      host: WS_HOST.into(),
+      port: None,
+      url: WebsocketUrl::MultiStream,
+      time_unit: None,
+      reconnect_policy: ReconnectPolicy::default(),
+      request_timeout: Duration::from_secs(10),
+      max_streams_per_connection: 200,
+      max_connections: None,
+      rotation_interval: Some(Duration::from_secs(23 * 60 * 60)),
+      rotation_overlap: Duration::from_secs(10),
+      event_queue_capacity: Some(1024),
+      backpressure_policy: BackpressurePolicy::Block,
+      recorder: None,
+      heartbeats: HashMap::new(),
+      stale_stream_action: StaleStreamAction::Resubscribe,
+      encoding: StreamEncoding::Json,
+    }
+  }
+}
+
+impl From<&Config> for WebSocketSpotConfig {
+  /// Host of `Config::ws_host`, e.g. testnet from `Config::testnet()`
+  fn from(config: &Config) -> Self {
+    let host = config.ws_host.trim_end_matches('/');
+    let host = host.strip_suffix("/ws").unwrap_or(host);
+
+    Self::default().set_host(host)
+  }
+}
+
+impl WebSocketSpotConfig {
+  /// Streams of `data-stream.binance.vision`, market data only
+  pub fn market_data_only() -> Self {
+    Self::default().set_host(MARKET_DATA_WS_HOST)
+  }
+
+  /// Streams of `stream-sbe.binance.com` in Simple Binary Encoding: trade, bestBidAsk,
+  /// depth and depth20. `api_key` is the API key of an Ed25519 key pair
+  pub fn sbe<K: Into<String>>(api_key: K) -> Self {
+    Self::default()
+      .set_host(SBE_WS_HOST)
+      .set_encoding(StreamEncoding::Sbe {
+        api_key: api_key.into(),
+      })
+  }
+
+  pub fn set_host<T: Into<String>>(mut self, host: T) -> Self {
+    self.host = host.into();
+    self
+  }
+
+  pub fn set_port<P: Into<Option<u16>>>(mut self, port: P) -> Self {
+    self.port = port.into();
+    self
+  }
+
+  pub fn set_url(mut self, url: WebsocketUrl) -> Self {
+    self.url = url;
+    self
+  }
+
+  pub fn set_time_unit<T: Into<Option<TimeUnit>>>(mut self, time_unit: T) -> Self {
+    self.time_unit = time_unit.into();
+    self
+  }
+
+  /// Host with the configured port, without a trailing slash
+  pub fn base_url(&self) -> String {
+    let host = self.host.trim_end_matches('/');
+    let Some(port) = self.port else {
+      return host.to_string();
+    };
+
+    let (scheme, rest) = host.split_once("://").unwrap_or(("wss", host));
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let hostname = authority.split(':').next().unwrap_or(authority);
+
+    format!("{}://{}:{}{}", scheme, hostname, port, path)
+  }
+
+  pub fn set_reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+    self.reconnect_policy = reconnect_policy;
+    self
+  }
+
+  pub fn set_request_timeout(mut self, request_timeout: Duration) -> Self {
+    self.request_timeout = request_timeout;
+    self
+  }
+
+  pub fn set_max_streams_per_connection(mut self, max_streams_per_connection: usize) -> Self {
+    self.max_streams_per_connection = max_streams_per_connection;
+    self
+  }
+
+  pub fn set_max_connections<C: Into<Option<usize>>>(mut self, max_connections: C) -> Self {
+    self.max_connections = max_connections.into();
+    self
+  }
+
+  pub fn set_rotation_interval<D: Into<Option<Duration>>>(mut self, rotation_interval: D) -> Self {
+    self.rotation_interval = rotation_interval.into();
+    self
+  }
+
+  pub fn set_rotation_overlap(mut self, rotation_overlap: Duration) -> Self {
+    self.rotation_overlap = rotation_overlap;
+    self
+  }
+
+  pub fn set_event_queue_capacity<C: Into<Option<usize>>>(
+    mut self,
+    event_queue_capacity: C,
+  ) -> Self {
+    self.event_queue_capacity = event_queue_capacity.into();
+    self
+  }
+
+  pub fn set_backpressure_policy(mut self, backpressure_policy: BackpressurePolicy) -> Self {
+    self.backpressure_policy = backpressure_policy;
+    self
+  }
+
+  /// Record the raw frames for a later replay with `WebsocketReplayer`
+  pub fn set_recorder<R: Into<Option<WebsocketRecorder>>>(mut self, recorder: R) -> Self {
+    self.recorder = recorder.into();
+    self
+  }
+
+  /// Expect an event of the stream at least every `max_silence`
+  pub fn set_heartbeat(mut self, stream: StreamName, max_silence: Duration) -> Self {
+    self.heartbeats.insert(stream, max_silence);
+    self
+  }
+
+  pub fn set_encoding(mut self, encoding: StreamEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn set_stale_stream_action(mut self, stale_stream_action: StaleStreamAction) -> Self {
+    self.stale_stream_action = stale_stream_action;
+    self
+  }
+}
@@ -0,0 +1,178 @@
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{from_str, Value};
+use std::borrow::Cow;
+
+/// Top level fields used to route a message without deserializing all of it
+#[derive(Deserialize)]
+struct Envelope<'a> {
+  #[serde(borrow)]
+  stream: Option<Cow<'a, str>>,
+  #[serde(borrow)]
+  data: Option<&'a RawValue>,
+  id: Option<u64>,
+  #[serde(rename = "e", borrow)]
+  event_type: Option<Cow<'a, str>>,
+  #[serde(rename = "lastUpdateId")]
+  last_update_id: Option<u64>,
+  #[serde(rename = "u")]
+  update_id: Option<u64>,
+}
+
+/// Event type of an element of the all market arrays
+#[derive(Deserialize)]
+struct EventType<'a> {
+  #[serde(rename = "e", borrow)]
+  event_type: Option<Cow<'a, str>>,
+}
+
+/// Incoming message of a connection
+pub(crate) enum IncomingMessage<'a> {
+  /// Combined stream payload `{"stream":..,"data":..}`
+  Combined {
+    stream: Option<Cow<'a, str>>,
+    data: &'a RawValue,
+  },
+  /// Response to a request, e.g. SUBSCRIBE
+  Response {
+    id: u64,
+    json: Value,
+  },
+  Event(Box<WebsocketSpotEvent>),
+}
+
+impl WebsocketSpotEvent {
+  /// Parse a message of a stream by its event type `e` and the stream name if known.
+  ///
+  /// Combined payloads `{"stream":..,"data":..}` are unwrapped. Messages that match
+  /// no event or fail to deserialize, e.g. responses to requests, become `Unknown`
+  pub fn from_message(stream: Option<&str>, msg: &str) -> Self {
+    match parse_message(stream, msg) {
+      IncomingMessage::Combined { stream, data } => {
+        Self::from_message(stream.as_deref(), data.get())
+      }
+      IncomingMessage::Response { .. } => WebsocketSpotEvent::Unknown(msg.to_string()),
+      IncomingMessage::Event(event) => *event,
+    }
+  }
+}
+
+/// Route a message to the event it holds, reading only the top level fields first
+pub(crate) fn parse_message<'a>(stream: Option<&str>, msg: &'a str) -> IncomingMessage<'a> {
+  if msg.trim_start().starts_with('[') {
+    return IncomingMessage::Event(Box::new(parse_array(stream, msg)));
+  }
+
+  let Ok(envelope) = from_str::<Envelope>(msg) else {
+    return IncomingMessage::Event(Box::new(WebsocketSpotEvent::Unknown(msg.to_string())));
+  };
+
+  if let Some(data) = envelope.data {
+    return IncomingMessage::Combined {
+      stream: envelope.stream,
+      data,
+    };
+  }
+  if let Some(id) = envelope.id {
+    return IncomingMessage::Response {
+      id,
+      json: from_str(msg).unwrap_or(Value::Null),
+    };
+  }
+
+  IncomingMessage::Event(Box::new(parse_event(stream, &envelope, msg)))
+}
+
+/// Event of a single object payload
+fn parse_event(stream: Option<&str>, envelope: &Envelope, msg: &str) -> WebsocketSpotEvent {
+  let event = match envelope.event_type.as_deref() {
+    Some("trade") => from_str(msg).map(WebsocketSpotEvent::Trade),
+    Some("aggTrade") => from_str(msg).map(WebsocketSpotEvent::AggTrades),
+    Some("kline") => from_str(msg).map(WebsocketSpotEvent::Kline),
+    Some("continuous_kline") => from_str(msg).map(WebsocketSpotEvent::ContinuousKline),
+    Some("indexPriceKline") => from_str(msg).map(WebsocketSpotEvent::IndexKline),
+    Some("24hrMiniTicker") => from_str(msg).map(WebsocketSpotEvent::MiniTicker),
+    Some("24hrTicker") => from_str(msg).map(WebsocketSpotEvent::DayTicker),
+    Some("avgPrice") => from_str(msg).map(WebsocketSpotEvent::AvgPrice),
+    // 1hTicker, 4hTicker, 1dTicker
+    Some(event_type) if event_type.ends_with("Ticker") => {
+      from_str(msg).map(WebsocketSpotEvent::WindowTicker)
+    }
+    Some("depthUpdate") => from_str(msg).map(WebsocketSpotEvent::DepthOrderBook),
+    Some("ACCOUNT_UPDATE") => from_str(msg).map(WebsocketSpotEvent::AccountUpdate),
+    Some("outboundAccountPosition") => {
+      from_str(msg).map(WebsocketSpotEvent::OutboundAccountPosition)
+    }
+    Some("balanceUpdate") => from_str(msg).map(WebsocketSpotEvent::SpotBalanceUpdate),
+    Some("executionReport") => from_str(msg).map(WebsocketSpotEvent::OrderTrade),
+    Some("listStatus") => from_str(msg).map(WebsocketSpotEvent::ListStatus),
+    Some("listenKeyExpired") => from_str(msg).map(WebsocketSpotEvent::ListenKeyExpired),
+    Some(_) => return WebsocketSpotEvent::Unknown(msg.to_string()),
+    // Book ticker and partial depth payloads have no event type
+    None => match stream {
+      Some(stream) if stream.ends_with("@bookTicker") => {
+        from_str(msg).map(WebsocketSpotEvent::BookTicker)
+      }
+      Some(stream) if stream.contains("@depth") => from_str(msg).map(WebsocketSpotEvent::OrderBook),
+      _ if envelope.last_update_id.is_some() => from_str(msg).map(WebsocketSpotEvent::OrderBook),
+      _ if envelope.update_id.is_some() => from_str(msg).map(WebsocketSpotEvent::BookTicker),
+      _ => return WebsocketSpotEvent::Unknown(msg.to_string()),
+    },
+  };
+
+  event.unwrap_or_else(|_| WebsocketSpotEvent::Unknown(msg.to_string()))
+}
+
+/// Event of the all market streams, e.g. !ticker@arr
+fn parse_array(stream: Option<&str>, msg: &str) -> WebsocketSpotEvent {
+  let event = match stream {
+    Some("!miniTicker@arr") => from_str(msg).map(WebsocketSpotEvent::MiniTickerAll),
+    Some("!ticker@arr") => from_str(msg).map(WebsocketSpotEvent::DayTickerAll),
+    Some(stream) if stream.starts_with("!ticker_") => {
+      from_str(msg).map(WebsocketSpotEvent::WindowTickerAll)
+    }
+    _ => {
+      let event_type = from_str::<Vec<EventType>>(msg)
+        .ok()
+        .and_then(|events| events.into_iter().next())
+        .and_then(|event| event.event_type);
+
+      match event_type.as_deref() {
+        Some("24hrMiniTicker") => from_str(msg).map(WebsocketSpotEvent::MiniTickerAll),
+        Some("24hrTicker") => from_str(msg).map(WebsocketSpotEvent::DayTickerAll),
+        Some(event_type) if event_type.ends_with("Ticker") => {
+          from_str(msg).map(WebsocketSpotEvent::WindowTickerAll)
+        }
+        _ => return WebsocketSpotEvent::Unknown(msg.to_string()),
+      }
+    }
+  };
+
+  event.unwrap_or_else(|_| WebsocketSpotEvent::Unknown(msg.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const AVG_PRICE: &str = r#"{"e":"avgPrice","E":1693907033000,"s":"BTCUSDT","i":"5m","w":"25776.86000000","T":1693907032213}"#;
+
+  #[test]
+  fn parses_avg_price_events() {
+    let combined = format!(r#"{{"stream":"btcusdt@avgPrice","data":{}}}"#, AVG_PRICE);
+
+    for event in [
+      WebsocketSpotEvent::from_message(None, AVG_PRICE),
+      WebsocketSpotEvent::from_message(None, &combined),
+    ] {
+      let WebsocketSpotEvent::AvgPrice(event) = event else {
+        panic!("not an avgPrice event: {:?}", event);
+      };
+      assert_eq!(event.symbol, "BTCUSDT");
+      assert_eq!(event.interval, "5m");
+      assert_eq!(event.price, "25776.86000000");
+      assert_eq!(event.last_trade_time, 1693907032213);
+    }
+  }
+}
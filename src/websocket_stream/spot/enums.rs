@@ -0,0 +1,23 @@
+use crate::create_enum_with_fmt;
+
+create_enum_with_fmt!(DepthLevels, {
+  Levels5 => "5",
+  Levels10 => "10",
+  Levels20 => "20",
+});
+
+create_enum_with_fmt!(DepthUpdateSpeed, {
+  Ms1000 => "1000ms",
+  Ms100 => "100ms",
+});
+
+create_enum_with_fmt!(TickerWindowSize, {
+  Hour1 => "1h",
+  Hour4 => "4h",
+  Day1 => "1d",
+});
+
+create_enum_with_fmt!(TimeUnit, {
+  Millisecond => "MILLISECOND",
+  Microsecond => "MICROSECOND",
+});
@@ -0,0 +1,328 @@
+use crate::websocket_stream::spot::config::{BackpressurePolicy, WebSocketSpotConfig};
+use crate::websocket_stream::spot::event_stream::EventSink;
+use crate::websocket_stream::spot::events::{ConnectionState, WebsocketSpotEvent};
+use crate::websocket_stream::spot::stream_name::StreamName;
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Counters of the event queues of a `WebSocketSpotStream`, shared by all its connections
+#[derive(Debug, Default)]
+pub struct EventQueueMetrics {
+  depth: AtomicUsize,
+  max_depth: AtomicUsize,
+  delivered: AtomicU64,
+  dropped: AtomicU64,
+  conflated: AtomicU64,
+  slow_consumer: AtomicU64,
+}
+
+/// Snapshot of `EventQueueMetrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventQueueStats {
+  /// Events waiting for delivery
+  pub depth: usize,
+  /// Highest depth seen so far
+  pub max_depth: usize,
+  /// Events handed over to the handler or channel
+  pub delivered: u64,
+  /// Events discarded by `DropOldest` and `DropNewest`
+  pub dropped: u64,
+  /// Events replaced by a newer one of the same stream with `ConflatePerStream`
+  pub conflated: u64,
+  /// Times a queue filled up because the consumer could not keep up
+  pub slow_consumer: u64,
+}
+
+impl EventQueueMetrics {
+  pub fn stats(&self) -> EventQueueStats {
+    EventQueueStats {
+      depth: self.depth.load(Ordering::Relaxed),
+      max_depth: self.max_depth.load(Ordering::Relaxed),
+      delivered: self.delivered.load(Ordering::Relaxed),
+      dropped: self.dropped.load(Ordering::Relaxed),
+      conflated: self.conflated.load(Ordering::Relaxed),
+      slow_consumer: self.slow_consumer.load(Ordering::Relaxed),
+    }
+  }
+
+  fn queued(&self) {
+    let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+    self.max_depth.fetch_max(depth, Ordering::Relaxed);
+  }
+
+  fn dequeued(&self) {
+    self.depth.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+/// How the actor hands events over to the sink
+pub(crate) enum EventDelivery {
+  /// Called from the actor, reading pauses while the handler runs
+  Direct(EventSink),
+  /// Through a bounded queue drained by a separate task
+  Queued(EventQueue),
+}
+
+impl EventDelivery {
+  pub(crate) fn new(
+    sink: EventSink,
+    config: &WebSocketSpotConfig,
+    metrics: Arc<EventQueueMetrics>,
+  ) -> Self {
+    match config.event_queue_capacity {
+      Some(capacity) => EventDelivery::Queued(EventQueue::spawn(
+        sink,
+        capacity,
+        config.backpressure_policy.clone(),
+        metrics,
+      )),
+      None => EventDelivery::Direct(sink),
+    }
+  }
+
+  /// - `stream` is the subscription the event came from, if known
+  pub(crate) async fn send(
+    &self,
+    stream: Option<StreamName>,
+    event: WebsocketSpotEvent,
+  ) -> Result<()> {
+    match self {
+      EventDelivery::Direct(sink) => sink.send(stream.as_ref(), event).await,
+      EventDelivery::Queued(queue) => queue.push(stream, event).await,
+    }
+  }
+}
+
+struct QueuedEvent {
+  /// Stream the event replaces older events of, with `ConflatePerStream`
+  conflation_key: Option<String>,
+  stream: Option<StreamName>,
+  event: WebsocketSpotEvent,
+}
+
+#[derive(Default)]
+struct QueueState {
+  events: VecDeque<QueuedEvent>,
+  /// Set by the producer when it's dropped, the rest is still delivered
+  closed: bool,
+  /// Set by the consumer when the handler failed
+  failed: Option<String>,
+  /// Whether the queue is full since the last `SlowConsumer` notice
+  full: bool,
+}
+
+struct Shared {
+  state: Mutex<QueueState>,
+  /// Signals the consumer that events were added or the queue was closed
+  events_added: Notify,
+  /// Signals the producer that events were taken out
+  space_freed: Notify,
+  metrics: Arc<EventQueueMetrics>,
+}
+
+/// Producer side of the queue between the socket and the handler.
+/// Dropping it lets the consumer deliver what's left and stop
+pub(crate) struct EventQueue {
+  shared: Arc<Shared>,
+  capacity: usize,
+  policy: BackpressurePolicy,
+}
+
+impl EventQueue {
+  fn spawn(
+    sink: EventSink,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    metrics: Arc<EventQueueMetrics>,
+  ) -> Self {
+    let shared = Arc::new(Shared {
+      state: Mutex::new(QueueState::default()),
+      events_added: Notify::new(),
+      space_freed: Notify::new(),
+      metrics,
+    });
+
+    tokio::spawn(deliver(shared.clone(), sink));
+
+    Self {
+      shared,
+      capacity: capacity.max(1),
+      policy,
+    }
+  }
+
+  /// Add an event according to the policy. Connection state changes always go in
+  pub(crate) async fn push(
+    &self,
+    stream: Option<StreamName>,
+    event: WebsocketSpotEvent,
+  ) -> Result<()> {
+    let conflation_key = match self.policy {
+      BackpressurePolicy::ConflatePerStream => conflation_key(&event),
+      _ => None,
+    };
+    let control = is_control(&event);
+    let mut queued = Some(QueuedEvent {
+      conflation_key,
+      stream,
+      event,
+    });
+
+    loop {
+      let space_freed = self.shared.space_freed.notified();
+      tokio::pin!(space_freed);
+      space_freed.as_mut().enable();
+
+      {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(reason) = &state.failed {
+          return Err(anyhow!("Event handler failed: {}", reason));
+        }
+
+        let event = queued.take().unwrap();
+        if let Some(key) = &event.conflation_key
+          && let Some(older) = state
+            .events
+            .iter_mut()
+            .find(|queued| queued.conflation_key.as_ref() == Some(key))
+        {
+          *older = event;
+          self
+            .shared
+            .metrics
+            .conflated
+            .fetch_add(1, Ordering::Relaxed);
+          return Ok(());
+        }
+
+        if state.events.len() < self.capacity {
+          state.full = false;
+        }
+        if control || state.events.len() < self.capacity {
+          self.enqueue(&mut state, event);
+          return Ok(());
+        }
+
+        if !state.full {
+          state.full = true;
+          self
+            .shared
+            .metrics
+            .slow_consumer
+            .fetch_add(1, Ordering::Relaxed);
+          let depth = state.events.len();
+          self.enqueue(
+            &mut state,
+            QueuedEvent {
+              conflation_key: None,
+              stream: None,
+              event: WebsocketSpotEvent::ConnectionState(ConnectionState::SlowConsumer {
+                queue_depth: depth,
+              }),
+            },
+          );
+        }
+
+        match self.policy {
+          BackpressurePolicy::DropNewest => {
+            self.shared.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+          }
+          BackpressurePolicy::DropOldest => {
+            let oldest = state
+              .events
+              .iter()
+              .position(|queued| !is_control(&queued.event));
+            if let Some(oldest) = oldest {
+              state.events.remove(oldest);
+              self.shared.metrics.dequeued();
+              self.shared.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            self.enqueue(&mut state, event);
+            return Ok(());
+          }
+          // Events of streams that can't be conflated, e.g. trades, are never lost
+          BackpressurePolicy::Block | BackpressurePolicy::ConflatePerStream => {
+            queued = Some(event);
+          }
+        }
+      }
+
+      space_freed.await;
+    }
+  }
+
+  fn enqueue(&self, state: &mut QueueState, event: QueuedEvent) {
+    state.events.push_back(event);
+    self.shared.metrics.queued();
+    self.shared.events_added.notify_one();
+  }
+}
+
+impl Drop for EventQueue {
+  fn drop(&mut self) {
+    self.shared.state.lock().unwrap().closed = true;
+    self.shared.events_added.notify_one();
+  }
+}
+
+/// Consumer task, hands the events over to the sink one by one
+async fn deliver(shared: Arc<Shared>, sink: EventSink) {
+  loop {
+    let events_added = shared.events_added.notified();
+    tokio::pin!(events_added);
+    events_added.as_mut().enable();
+
+    let next = {
+      let mut state = shared.state.lock().unwrap();
+      match state.events.pop_front() {
+        Some(queued) => Some(queued),
+        None if state.closed => return,
+        None => None,
+      }
+    };
+
+    let Some(queued) = next else {
+      events_added.await;
+      continue;
+    };
+
+    shared.metrics.dequeued();
+    shared.space_freed.notify_one();
+
+    if let Err(e) = sink.send(queued.stream.as_ref(), queued.event).await {
+      let mut state = shared.state.lock().unwrap();
+      for _ in state.events.drain(..) {
+        shared.metrics.dequeued();
+      }
+      state.failed = Some(e.to_string());
+      shared.space_freed.notify_one();
+      return;
+    }
+    shared.metrics.delivered.fetch_add(1, Ordering::Relaxed);
+  }
+}
+
+fn is_control(event: &WebsocketSpotEvent) -> bool {
+  matches!(event, WebsocketSpotEvent::ConnectionState(_))
+}
+
+/// Stream of events where only the latest one matters, e.g. tickers
+fn conflation_key(event: &WebsocketSpotEvent) -> Option<String> {
+  match event {
+    WebsocketSpotEvent::DayTicker(v) => Some(format!("24hrTicker|{}", v.symbol)),
+    WebsocketSpotEvent::WindowTicker(v) => Some(format!("{}|{}", v.event_type, v.symbol)),
+    WebsocketSpotEvent::MiniTicker(v) => Some(format!("24hrMiniTicker|{}", v.symbol)),
+    WebsocketSpotEvent::BookTicker(v) => Some(format!("bookTicker|{}", v.symbol)),
+    WebsocketSpotEvent::AvgPrice(v) => Some(format!("avgPrice|{}", v.symbol)),
+    WebsocketSpotEvent::DayTickerAll(_) => Some("24hrTicker@arr".to_string()),
+    WebsocketSpotEvent::WindowTickerAll(v) => {
+      v.first().map(|ticker| format!("{}@arr", ticker.event_type))
+    }
+    WebsocketSpotEvent::MiniTickerAll(_) => Some("24hrMiniTicker@arr".to_string()),
+    _ => None,
+  }
+}
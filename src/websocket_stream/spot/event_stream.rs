@@ -0,0 +1,87 @@
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use crate::websocket_stream::spot::handler::SharedEventHandler;
+use crate::websocket_stream::spot::router::EventRouter;
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::SharedWebSocketCallback;
+use anyhow::{anyhow, Result};
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast, mpsc};
+
+/// Where the actor delivers incoming events
+#[derive(Clone)]
+pub(crate) enum EventSink {
+  /// Synchronous callback, called for every event
+  Callback(SharedWebSocketCallback),
+  /// `WebsocketSpotEventHandler`, the method of the event is awaited
+  Handler(SharedEventHandler),
+  /// Bounded channel read by a single `WebsocketSpotEventStream`.
+  /// Reading from the socket pauses while the buffer is full
+  Channel(mpsc::Sender<WebsocketSpotEvent>),
+  /// Fan-out to every receiver, slow receivers skip the oldest events
+  Broadcast(broadcast::Sender<WebsocketSpotEvent>),
+  /// Handlers registered per stream or event kind
+  Router(Arc<EventRouter>),
+}
+
+impl EventSink {
+  /// - `stream` is the subscription the event came from, if known
+  pub(crate) async fn send(
+    &self,
+    stream: Option<&StreamName>,
+    event: WebsocketSpotEvent,
+  ) -> Result<()> {
+    match self {
+      EventSink::Callback(handler) => {
+        let mut handler = handler.lock().await;
+        (handler)(event)
+      }
+      EventSink::Handler(handler) => {
+        let mut handler = handler.lock().await;
+        handler.handle(event).await
+      }
+      EventSink::Channel(tx) => tx
+        .send(event)
+        .await
+        .map_err(|_| anyhow!("Event stream was dropped")),
+      EventSink::Broadcast(tx) => {
+        // No receivers at the moment is fine, new ones can be added at any time
+        let _ = tx.send(event);
+        Ok(())
+      }
+      EventSink::Router(router) => {
+        router.dispatch(stream, event).await;
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Events of a `WebSocketSpotStream` created with a channel
+///
+/// Ends once the `WebSocketSpotStream` is dropped and its connections are closed.
+/// Dropping it stops the connections.
+pub struct WebsocketSpotEventStream {
+  rx: mpsc::Receiver<WebsocketSpotEvent>,
+}
+
+impl WebsocketSpotEventStream {
+  pub(crate) fn new(rx: mpsc::Receiver<WebsocketSpotEvent>) -> Self {
+    Self { rx }
+  }
+
+  /// Receive the next event, `None` once the actor has stopped
+  pub async fn recv(&mut self) -> Option<WebsocketSpotEvent> {
+    self.rx.recv().await
+  }
+}
+
+impl Stream for WebsocketSpotEventStream {
+  type Item = WebsocketSpotEvent;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.rx.poll_recv(cx)
+  }
+}
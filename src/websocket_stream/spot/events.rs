@@ -1,11 +1,17 @@
+use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::OrderExecution;
 use crate::serde_helpers::string_to_float;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum WebsocketSpotEvent {
   AccountUpdate(AccountUpdateEvent),
+  #[deprecated(note = "balanceUpdate events are delivered as `SpotBalanceUpdate`")]
   BalanceUpdate(BalanceUpdateEvent),
   OrderTrade(OrderTradeEvent),
+  OutboundAccountPosition(OutboundAccountPositionEvent),
+  SpotBalanceUpdate(SpotBalanceUpdateEvent),
+  ListStatus(ListStatusEvent),
+  ListenKeyExpired(ListenKeyExpiredEvent),
   AggTrades(AggTradesEvent),
   Trade(TradeEvent),
   OrderBook(OrderBook),
@@ -16,6 +22,57 @@ pub enum WebsocketSpotEvent {
   Kline(KlineEvent),
   DepthOrderBook(DepthOrderBookEvent),
   BookTicker(BookTickerEvent),
+  MiniTicker(MiniTickerEvent),
+  MiniTickerAll(Vec<MiniTickerEvent>),
+  AvgPrice(AvgPriceEvent),
+  ContinuousKline(ContinuousKlineEvent),
+  IndexKline(IndexKlineEvent),
+  ConnectionState(ConnectionState),
+  /// Raw message that matches no event, or failed to deserialize into the event of its type
+  Unknown(String),
+}
+
+/// State changes of the underlying connection, produced by the actor itself,
+/// and of the listen key, produced by `UserDataStream`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ConnectionState {
+  Connected,
+  Disconnected {
+    reason: String,
+  },
+  /// Reconnection attempt is scheduled after `delay_ms`
+  Reconnecting {
+    attempt: u32,
+    delay_ms: u64,
+  },
+  /// Reconnection attempts are exhausted, the next subscription connects again
+  GaveUp {
+    attempts: u32,
+  },
+  /// Connection was replaced by a new one ahead of the 24h limit
+  Rotated,
+  /// The event queue filled up, the handler is slower than the incoming events
+  SlowConsumer {
+    queue_depth: usize,
+  },
+  /// No event of the stream for `silent_ms`, longer than its heartbeat
+  StaleStream {
+    stream: String,
+    silent_ms: u64,
+  },
+  /// The listen key of a user data stream couldn't be kept alive, a new one is created
+  ListenKeyKeepAliveFailed {
+    reason: String,
+  },
+  /// A new listen key of a user data stream couldn't be created. It's tried again
+  /// after `retry_ms`, `None` once the attempts of the retry policy are exhausted
+  ListenKeyFailed {
+    attempt: u32,
+    reason: String,
+    retry_ms: Option<u64>,
+  },
+  /// The user data stream is subscribed to a new listen key after it expired or failed
+  ListenKeyRecreated,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,6 +81,9 @@ pub struct OrderBook {
   pub last_update_id: u64,
   pub bids: Vec<Bids>,
   pub asks: Vec<Asks>,
+  /// Only SBE depth snapshots carry the symbol, JSON partial depth payloads don't
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub symbol: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -287,6 +347,110 @@ pub struct OrderTradeEvent {
   pub m_ignore: bool,
 }
 
+impl From<&OrderTradeEvent> for OrderExecution {
+  fn from(event: &OrderTradeEvent) -> Self {
+    Self {
+      execution_type: event.execution_type.clone(),
+      last_filled_qty: event.qty_last_filled_trade.parse().unwrap_or_default(),
+      accumulated_filled_qty: event
+        .accumulated_qty_filled_trades
+        .parse()
+        .unwrap_or_default(),
+    }
+  }
+}
+
+/// Balances of the assets changed by an account update
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboundAccountPositionEvent {
+  #[serde(rename = "e")]
+  pub event_type: String,
+  #[serde(rename = "E")]
+  pub event_time: u64,
+  #[serde(rename = "u")]
+  pub last_account_update_time: u64,
+  #[serde(rename = "B")]
+  pub balances: Vec<AccountPositionBalance>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountPositionBalance {
+  #[serde(rename = "a")]
+  pub asset: String,
+  #[serde(rename = "f")]
+  pub free: String,
+  #[serde(rename = "l")]
+  pub locked: String,
+}
+
+/// Deposit, withdrawal or transfer of an asset of the spot account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotBalanceUpdateEvent {
+  #[serde(rename = "e")]
+  pub event_type: String,
+  #[serde(rename = "E")]
+  pub event_time: u64,
+  #[serde(rename = "a")]
+  pub asset: String,
+  #[serde(rename = "d")]
+  pub balance_delta: String,
+  #[serde(rename = "T")]
+  pub clear_time: u64,
+}
+
+/// Status change of an order list, e.g. OCO
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListStatusEvent {
+  #[serde(rename = "e")]
+  pub event_type: String,
+  #[serde(rename = "E")]
+  pub event_time: u64,
+  #[serde(rename = "s")]
+  pub symbol: String,
+  #[serde(rename = "g")]
+  pub order_list_id: i64,
+  #[serde(rename = "c")]
+  pub contingency_type: String,
+  #[serde(rename = "l")]
+  pub list_status_type: String,
+  #[serde(rename = "L")]
+  pub list_order_status: String,
+  #[serde(rename = "r")]
+  pub list_reject_reason: String,
+  #[serde(rename = "C")]
+  pub list_client_order_id: String,
+  #[serde(rename = "T")]
+  pub transaction_time: u64,
+  #[serde(rename = "O")]
+  pub orders: Vec<ListStatusOrder>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListStatusOrder {
+  #[serde(rename = "s")]
+  pub symbol: String,
+  #[serde(rename = "i")]
+  pub order_id: u64,
+  #[serde(rename = "c")]
+  pub client_order_id: String,
+}
+
+/// The listen key of the user data stream is no longer valid
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenKeyExpiredEvent {
+  #[serde(rename = "e")]
+  pub event_type: String,
+  #[serde(rename = "E")]
+  pub event_time: u64,
+  pub listen_key: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DayTickerEvent {
@@ -417,6 +581,25 @@ pub struct MiniTickerEvent {
   pub quote_volume: String,
 }
 
+/// Average price of `<symbol>@avgPrice`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AvgPriceEvent {
+  #[serde(rename = "e")]
+  pub event_type: String,
+  #[serde(rename = "E")]
+  pub event_time: u64,
+  #[serde(rename = "s")]
+  pub symbol: String,
+  /// Interval of the average, e.g. "5m"
+  #[serde(rename = "i")]
+  pub interval: String,
+  #[serde(rename = "w")]
+  pub price: String,
+  #[serde(rename = "T")]
+  pub last_trade_time: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct KlineEvent {
@@ -0,0 +1,320 @@
+use crate::websocket_stream::spot::enums::TimeUnit;
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use crate::websocket_stream::spot::stream_name::StreamName;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/// Latest samples per stream the latency statistics are computed over
+const LATENCY_WINDOW: usize = 1000;
+
+/// Delay between the exchange event time and the local receive time, per stream.
+/// Shared by all connections of a `WebSocketSpotStream`
+#[derive(Debug, Default)]
+pub struct FeedLatencyMetrics {
+  streams: Mutex<HashMap<String, LatencyWindow>>,
+}
+
+#[derive(Debug, Default)]
+struct LatencyWindow {
+  /// Latest delays in milliseconds, negative if the local clock is behind
+  samples: VecDeque<i64>,
+  total: u64,
+}
+
+/// Latency of a stream over its latest samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedLatencyStats {
+  /// Samples recorded since the stream started
+  pub samples: u64,
+  pub last_ms: i64,
+  pub min_ms: i64,
+  pub max_ms: i64,
+  pub mean_ms: f64,
+  pub p50_ms: i64,
+  pub p99_ms: i64,
+}
+
+impl FeedLatencyMetrics {
+  /// Statistics of every stream that delivered events with an event time
+  pub fn stats(&self) -> HashMap<String, FeedLatencyStats> {
+    let streams = self.streams.lock().unwrap();
+    streams
+      .iter()
+      .filter_map(|(stream, window)| Some((stream.clone(), window.stats()?)))
+      .collect()
+  }
+
+  /// Statistics of one stream, `None` before its first event
+  pub fn stream_stats(&self, stream: &StreamName) -> Option<FeedLatencyStats> {
+    let streams = self.streams.lock().unwrap();
+    streams.get(&stream.to_string())?.stats()
+  }
+
+  /// Forget all samples
+  pub fn reset(&self) {
+    self.streams.lock().unwrap().clear();
+  }
+
+  pub(crate) fn record(&self, stream: &str, latency_ms: i64) {
+    let mut streams = self.streams.lock().unwrap();
+    let window = match streams.get_mut(stream) {
+      Some(window) => window,
+      None => streams.entry(stream.to_string()).or_default(),
+    };
+
+    if window.samples.len() >= LATENCY_WINDOW {
+      window.samples.pop_front();
+    }
+    window.samples.push_back(latency_ms);
+    window.total += 1;
+  }
+}
+
+impl LatencyWindow {
+  fn stats(&self) -> Option<FeedLatencyStats> {
+    let last_ms = *self.samples.back()?;
+    let mut sorted: Vec<i64> = self.samples.iter().copied().collect();
+    sorted.sort_unstable();
+    let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+
+    Some(FeedLatencyStats {
+      samples: self.total,
+      last_ms,
+      min_ms: sorted[0],
+      max_ms: sorted[sorted.len() - 1],
+      mean_ms: sorted.iter().sum::<i64>() as f64 / sorted.len() as f64,
+      p50_ms: percentile(50),
+      p99_ms: percentile(99),
+    })
+  }
+}
+
+/// Silence of the streams with a heartbeat expectation, per connection
+pub(crate) struct StreamWatchdog {
+  heartbeats: HashMap<StreamName, Duration>,
+  /// Last event of each watched stream, or when watching started
+  last_event_at: HashMap<StreamName, Instant>,
+}
+
+impl StreamWatchdog {
+  pub(crate) fn new(heartbeats: HashMap<StreamName, Duration>) -> Self {
+    Self {
+      heartbeats,
+      last_event_at: HashMap::new(),
+    }
+  }
+
+  pub(crate) fn is_watched(&self, stream: &StreamName) -> bool {
+    self.heartbeats.contains_key(stream)
+  }
+
+  /// Start the silence of the streams over, e.g. after (re)subscribing
+  pub(crate) fn restart<'a, I>(&mut self, streams: I)
+  where
+    I: IntoIterator<Item = &'a StreamName>,
+  {
+    let now = Instant::now();
+    for stream in streams {
+      if self.is_watched(stream) {
+        self.last_event_at.insert(stream.clone(), now);
+      }
+    }
+  }
+
+  pub(crate) fn event_received(&mut self, stream: &StreamName) {
+    if let Some(last_event_at) = self.last_event_at.get_mut(stream) {
+      *last_event_at = Instant::now();
+    }
+  }
+
+  /// When the next subscribed stream turns stale if nothing arrives
+  pub(crate) fn next_deadline(&self, subscriptions: &HashSet<StreamName>) -> Option<Instant> {
+    self
+      .last_event_at
+      .iter()
+      .filter(|(stream, _)| subscriptions.contains(*stream))
+      .filter_map(|(stream, at)| Some(*at + *self.heartbeats.get(stream)?))
+      .min()
+  }
+
+  /// Subscribed streams silent past their heartbeat, and for how long
+  pub(crate) fn stale_streams(
+    &self,
+    subscriptions: &HashSet<StreamName>,
+  ) -> Vec<(StreamName, Duration)> {
+    self
+      .last_event_at
+      .iter()
+      .filter(|(stream, _)| subscriptions.contains(*stream))
+      .filter_map(|(stream, at)| {
+        let silence = at.elapsed();
+        (silence >= *self.heartbeats.get(stream)?).then(|| (stream.clone(), silence))
+      })
+      .collect()
+  }
+}
+
+/// Subscriptions by stream name and by the key of the events they deliver,
+/// to attribute events to the stream they came from
+#[derive(Debug, Default)]
+pub(crate) struct StreamIndex {
+  by_name: HashMap<String, StreamName>,
+  /// Every subscription delivering events of the key
+  by_event_key: HashMap<EventKey, Vec<StreamName>>,
+}
+
+/// Event type, lowercased symbol and e.g. the kline interval of the events of a stream
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EventKey {
+  kind: &'static str,
+  symbol: String,
+  detail: String,
+}
+
+impl StreamIndex {
+  pub(crate) fn insert(&mut self, stream: &StreamName) {
+    self.by_name.insert(stream.to_string(), stream.clone());
+    for key in stream_event_keys(stream) {
+      self
+        .by_event_key
+        .entry(key)
+        .or_default()
+        .push(stream.clone());
+    }
+  }
+
+  pub(crate) fn remove(&mut self, stream: &StreamName) {
+    self.by_name.remove(&stream.to_string());
+    for key in stream_event_keys(stream) {
+      if let Some(streams) = self.by_event_key.get_mut(&key) {
+        streams.retain(|subscription| subscription != stream);
+        if streams.is_empty() {
+          self.by_event_key.remove(&key);
+        }
+      }
+    }
+  }
+
+  /// Subscribed stream the event came from.
+  /// Without the name of a combined payload, only the single subscription delivering
+  /// such events is used, e.g. not when both `@depth` and `@depth@100ms` of a symbol are
+  pub(crate) fn event_stream(
+    &self,
+    stream: Option<&str>,
+    event: &WebsocketSpotEvent,
+  ) -> Option<StreamName> {
+    if let Some(stream) = stream {
+      return self.by_name.get(stream).cloned();
+    }
+    match self.by_event_key.get(&event_key(event)?)?.as_slice() {
+      [stream] => Some(stream.clone()),
+      _ => None,
+    }
+  }
+}
+
+impl EventKey {
+  fn new<S: Into<String>>(kind: &'static str, symbol: &str, detail: S) -> Self {
+    Self {
+      kind,
+      symbol: symbol.to_ascii_lowercase(),
+      detail: detail.into(),
+    }
+  }
+}
+
+/// Keys of the events the stream delivers
+fn stream_event_keys(stream: &StreamName) -> Vec<EventKey> {
+  let symbol = stream.symbol().unwrap_or_default();
+  let key = match stream {
+    StreamName::Trade { .. } => EventKey::new("trade", symbol, ""),
+    StreamName::AggTrade { .. } => EventKey::new("aggTrade", symbol, ""),
+    StreamName::Kline { interval, .. } => EventKey::new("kline", symbol, interval.to_string()),
+    StreamName::Depth { levels: None, .. } => EventKey::new("depthUpdate", symbol, ""),
+    // JSON partial depth payloads have no symbol, SBE depth snapshots do
+    StreamName::Depth {
+      levels: Some(_), ..
+    } => {
+      return vec![
+        EventKey::new("partialDepth", symbol, ""),
+        EventKey::new("partialDepth", "", ""),
+      ];
+    }
+    StreamName::BookTicker { .. } | StreamName::BestBidAsk { .. } => {
+      EventKey::new("bookTicker", symbol, "")
+    }
+    StreamName::MiniTicker { .. } => EventKey::new("miniTicker", symbol, ""),
+    StreamName::Ticker { .. } => EventKey::new("ticker", symbol, ""),
+    StreamName::WindowTicker { window_size, .. } => {
+      EventKey::new("windowTicker", symbol, format!("{}Ticker", window_size))
+    }
+    StreamName::AllMiniTickers => EventKey::new("allMiniTickers", "", ""),
+    StreamName::AllTickers => EventKey::new("allTickers", "", ""),
+    StreamName::AllWindowTickers { window_size } => {
+      EventKey::new("allWindowTickers", "", format!("{}Ticker", window_size))
+    }
+    StreamName::AvgPrice { .. } => EventKey::new("avgPrice", symbol, ""),
+    StreamName::UserData { .. } => return vec![],
+  };
+  vec![key]
+}
+
+/// Key of the stream that delivers the event, `None` for events not from market streams
+fn event_key(event: &WebsocketSpotEvent) -> Option<EventKey> {
+  let key = match event {
+    WebsocketSpotEvent::Trade(v) => EventKey::new("trade", &v.symbol, ""),
+    WebsocketSpotEvent::AggTrades(v) => EventKey::new("aggTrade", &v.symbol, ""),
+    WebsocketSpotEvent::Kline(v) => EventKey::new("kline", &v.symbol, v.kline.interval.as_str()),
+    WebsocketSpotEvent::DepthOrderBook(v) => EventKey::new("depthUpdate", &v.symbol, ""),
+    WebsocketSpotEvent::OrderBook(v) => {
+      EventKey::new("partialDepth", v.symbol.as_deref().unwrap_or_default(), "")
+    }
+    WebsocketSpotEvent::BookTicker(v) => EventKey::new("bookTicker", &v.symbol, ""),
+    WebsocketSpotEvent::MiniTicker(v) => EventKey::new("miniTicker", &v.symbol, ""),
+    WebsocketSpotEvent::DayTicker(v) => EventKey::new("ticker", &v.symbol, ""),
+    WebsocketSpotEvent::WindowTicker(v) => {
+      EventKey::new("windowTicker", &v.symbol, v.event_type.as_str())
+    }
+    WebsocketSpotEvent::MiniTickerAll(_) => EventKey::new("allMiniTickers", "", ""),
+    WebsocketSpotEvent::AvgPrice(v) => EventKey::new("avgPrice", &v.symbol, ""),
+    WebsocketSpotEvent::DayTickerAll(_) => EventKey::new("allTickers", "", ""),
+    WebsocketSpotEvent::WindowTickerAll(v) => {
+      EventKey::new("allWindowTickers", "", v.first()?.event_type.as_str())
+    }
+    _ => return None,
+  };
+  Some(key)
+}
+
+/// Delay of a market event in milliseconds, `None` for events without an event time
+pub(crate) fn event_latency_ms(
+  event: &WebsocketSpotEvent,
+  time_unit: Option<&TimeUnit>,
+) -> Option<i64> {
+  let event_time = match event {
+    WebsocketSpotEvent::Trade(v) => v.event_time,
+    WebsocketSpotEvent::AggTrades(v) => v.event_time,
+    WebsocketSpotEvent::DepthOrderBook(v) => v.event_time,
+    WebsocketSpotEvent::DayTicker(v) => v.event_time,
+    WebsocketSpotEvent::WindowTicker(v) => v.event_time,
+    WebsocketSpotEvent::MiniTicker(v) => v.event_time,
+    WebsocketSpotEvent::AvgPrice(v) => v.event_time,
+    WebsocketSpotEvent::Kline(v) => v.event_time,
+    WebsocketSpotEvent::DayTickerAll(v) => v.iter().map(|ticker| ticker.event_time).max()?,
+    WebsocketSpotEvent::WindowTickerAll(v) => v.iter().map(|ticker| ticker.event_time).max()?,
+    WebsocketSpotEvent::MiniTickerAll(v) => v.iter().map(|ticker| ticker.event_time).max()?,
+    _ => return None,
+  };
+  let event_time_ms = match time_unit {
+    Some(TimeUnit::Microsecond) => event_time / 1000,
+    _ => event_time,
+  };
+
+  let received_at_ms = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_millis() as i64;
+  Some(received_at_ms - event_time_ms as i64)
+}
@@ -0,0 +1,211 @@
+use crate::websocket_stream::spot::events::{
+  AccountUpdateEvent, AggTradesEvent, AvgPriceEvent, BookTickerEvent, ConnectionState,
+  ContinuousKlineEvent, DayTickerEvent, DepthOrderBookEvent, IndexKlineEvent, KlineEvent,
+  ListStatusEvent, ListenKeyExpiredEvent, MiniTickerEvent, OrderBook, OrderTradeEvent,
+  OutboundAccountPositionEvent, SpotBalanceUpdateEvent, TradeEvent, WebsocketSpotEvent,
+  WindowTickerEvent,
+};
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Typed alternative to the closure handler of `WebSocketSpotStream`, one method per event.
+///
+/// Every method does nothing by default, implement the ones of the subscribed streams.
+/// Methods may be written as `async fn`, the next event waits until the previous one
+/// was handled. An error is handled like an error of the closure handler
+pub trait WebsocketSpotEventHandler: Send + 'static {
+  /// `<symbol>@trade`
+  fn on_trade(&mut self, _event: TradeEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@aggTrade`
+  fn on_agg_trade(&mut self, _event: AggTradesEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@kline_<interval>`
+  fn on_kline(&mut self, _event: KlineEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  fn on_continuous_kline(
+    &mut self,
+    _event: ContinuousKlineEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  fn on_index_kline(&mut self, _event: IndexKlineEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// Diff depth `<symbol>@depth`
+  fn on_depth(&mut self, _event: DepthOrderBookEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// Partial book depth `<symbol>@depth<levels>`
+  fn on_partial_depth(&mut self, _event: OrderBook) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@bookTicker`
+  fn on_book_ticker(&mut self, _event: BookTickerEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@ticker`
+  fn on_day_ticker(&mut self, _event: DayTickerEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `!ticker@arr`
+  fn on_all_day_tickers(
+    &mut self,
+    _events: Vec<DayTickerEvent>,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@ticker_<window_size>`
+  fn on_window_ticker(
+    &mut self,
+    _event: WindowTickerEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `!ticker_<window_size>@arr`
+  fn on_all_window_tickers(
+    &mut self,
+    _events: Vec<WindowTickerEvent>,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@miniTicker`
+  fn on_mini_ticker(&mut self, _event: MiniTickerEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `!miniTicker@arr`
+  fn on_all_mini_tickers(
+    &mut self,
+    _events: Vec<MiniTickerEvent>,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// `<symbol>@avgPrice`
+  fn on_avg_price(&mut self, _event: AvgPriceEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// executionReport of the user data stream
+  fn on_execution_report(
+    &mut self,
+    _event: OrderTradeEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// outboundAccountPosition of the user data stream
+  fn on_account_position(
+    &mut self,
+    _event: OutboundAccountPositionEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// balanceUpdate of the user data stream
+  fn on_balance_update(
+    &mut self,
+    _event: SpotBalanceUpdateEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// listStatus of the user data stream
+  fn on_list_status(&mut self, _event: ListStatusEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  fn on_listen_key_expired(
+    &mut self,
+    _event: ListenKeyExpiredEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  fn on_account_update(
+    &mut self,
+    _event: AccountUpdateEvent,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  fn on_connection_state(
+    &mut self,
+    _state: ConnectionState,
+  ) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+
+  /// Events without a method of their own, e.g. `Unknown`
+  fn on_other(&mut self, _event: WebsocketSpotEvent) -> impl Future<Output = Result<()>> + Send {
+    async { Ok(()) }
+  }
+}
+
+/// Call the method of the handler for the event, e.g. to test a handler without a connection
+pub async fn handle_event<H: WebsocketSpotEventHandler>(
+  handler: &mut H,
+  event: WebsocketSpotEvent,
+) -> Result<()> {
+  match event {
+    WebsocketSpotEvent::Trade(v) => handler.on_trade(v).await,
+    WebsocketSpotEvent::AggTrades(v) => handler.on_agg_trade(v).await,
+    WebsocketSpotEvent::Kline(v) => handler.on_kline(v).await,
+    WebsocketSpotEvent::ContinuousKline(v) => handler.on_continuous_kline(v).await,
+    WebsocketSpotEvent::IndexKline(v) => handler.on_index_kline(v).await,
+    WebsocketSpotEvent::DepthOrderBook(v) => handler.on_depth(v).await,
+    WebsocketSpotEvent::OrderBook(v) => handler.on_partial_depth(v).await,
+    WebsocketSpotEvent::BookTicker(v) => handler.on_book_ticker(v).await,
+    WebsocketSpotEvent::DayTicker(v) => handler.on_day_ticker(v).await,
+    WebsocketSpotEvent::DayTickerAll(v) => handler.on_all_day_tickers(v).await,
+    WebsocketSpotEvent::WindowTicker(v) => handler.on_window_ticker(v).await,
+    WebsocketSpotEvent::WindowTickerAll(v) => handler.on_all_window_tickers(v).await,
+    WebsocketSpotEvent::MiniTicker(v) => handler.on_mini_ticker(v).await,
+    WebsocketSpotEvent::MiniTickerAll(v) => handler.on_all_mini_tickers(v).await,
+    WebsocketSpotEvent::AvgPrice(v) => handler.on_avg_price(v).await,
+    WebsocketSpotEvent::OrderTrade(v) => handler.on_execution_report(v).await,
+    WebsocketSpotEvent::OutboundAccountPosition(v) => handler.on_account_position(v).await,
+    WebsocketSpotEvent::SpotBalanceUpdate(v) => handler.on_balance_update(v).await,
+    WebsocketSpotEvent::ListStatus(v) => handler.on_list_status(v).await,
+    WebsocketSpotEvent::ListenKeyExpired(v) => handler.on_listen_key_expired(v).await,
+    WebsocketSpotEvent::AccountUpdate(v) => handler.on_account_update(v).await,
+    WebsocketSpotEvent::ConnectionState(v) => handler.on_connection_state(v).await,
+    WebsocketSpotEvent::Unknown(_) => handler.on_other(event).await,
+    #[allow(deprecated)]
+    WebsocketSpotEvent::BalanceUpdate(_) => handler.on_other(event).await,
+  }
+}
+
+type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Object safe form of `WebsocketSpotEventHandler`, so the sink can hold any handler
+pub(crate) trait DynEventHandler: Send {
+  fn handle(&mut self, event: WebsocketSpotEvent) -> HandlerFuture<'_>;
+}
+
+impl<H: WebsocketSpotEventHandler> DynEventHandler for H {
+  fn handle(&mut self, event: WebsocketSpotEvent) -> HandlerFuture<'_> {
+    Box::pin(handle_event(self, event))
+  }
+}
+
+pub(crate) type SharedEventHandler = Arc<Mutex<Box<dyn DynEventHandler>>>;
@@ -1,155 +1,355 @@
-use anyhow::Result;
-use events::{
-  AccountUpdateEvent, AggTradesEvent, BalanceUpdateEvent, BookTickerEvent, DayTickerEvent,
-  DepthOrderBookEvent, KlineEvent, OrderBook, OrderTradeEvent, TradeEvent, WebsocketSpotEvent,
-  WindowTickerEvent,
-};
+use anyhow::{anyhow, bail, Result};
+use config::{StaleStreamAction, StreamEncoding, WebSocketSpotConfig};
+use event_queue::{EventDelivery, EventQueueMetrics, EventQueueStats};
+use event_stream::{EventSink, WebsocketSpotEventStream};
+use feed_health::{FeedLatencyMetrics, FeedLatencyStats, StreamIndex, StreamWatchdog};
+use dispatch::IncomingMessage;
+use events::{ConnectionState, WebsocketSpotEvent};
 use futures_util::SinkExt;
 use futures_util::StreamExt;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::collections::HashSet;
+use futures_util::future::join_all;
+use handler::WebsocketSpotEventHandler;
+use recording::FrameSource;
+use rotation::Rotation;
+use router::{EventKind, EventRouter, RouteHandle};
+use send_limiter::SendLimiter;
+use serde_json::{json, Value};
+use shard::Shard;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::SystemTime;
+use stream_name::StreamName;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc::Receiver;
-use tokio::sync::{mpsc, oneshot, Mutex};
-use tokio::task::JoinHandle;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::{Message, Utf8Bytes};
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
+pub mod config;
+mod dispatch;
+pub mod enums;
+pub mod event_queue;
+pub mod event_stream;
 pub mod events;
+pub mod feed_health;
+pub mod handler;
+pub mod order_book;
+pub mod recording;
+mod rotation;
+pub mod sbe;
+pub mod router;
+mod send_limiter;
+pub mod sequence;
+mod shard;
+pub mod stream_name;
+pub mod user_data;
 
-enum WebsocketUrl {
+/// Path of the websocket endpoint the streams are requested on
+#[derive(Clone, Debug)]
+pub enum WebsocketUrl {
+  /// /ws/\<stream1\>/\<stream2\>, raw event payloads
   Default,
+  /// /stream?streams=\<stream1\>/\<stream2\>, payloads wrapped into `{"stream":..,"data":..}`
   MultiStream,
+  /// Custom path on the host, streams are appended as /\<stream1\>/\<stream2\>
   Custom(String),
 }
 
 impl WebsocketUrl {
-  fn params(self, subscription: &str) -> String {
+  fn params(&self, host: &str, subscription: &str) -> String {
     match self {
-      WebsocketUrl::Default => format!("wss://stream.binance.com:443/ws/{}", subscription),
+      WebsocketUrl::Default => format!("{}/ws/{}", host, subscription),
       WebsocketUrl::MultiStream => {
-        format!(
-          "wss://stream.binance.com:443/stream?streams={}",
-          subscription
-        )
+        format!("{}/stream?streams={}", host, subscription)
       }
-      WebsocketUrl::Custom(url) => format!("{}/{}", url, subscription),
+      WebsocketUrl::Custom(path) => format!("{}{}/{}", host, path, subscription),
     }
   }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-enum InternalEvents {
-  DayTickerEventAll(Vec<DayTickerEvent>),
-  WindowTickerEventAll(Vec<WindowTickerEvent>),
-  BalanceUpdateEvent(BalanceUpdateEvent),
-  DayTickerEvent(DayTickerEvent),
-  WindowTickerEvent(WindowTickerEvent),
-  BookTickerEvent(BookTickerEvent),
-  AccountUpdateEvent(AccountUpdateEvent),
-  OrderTradeEvent(OrderTradeEvent),
-  AggTradesEvent(AggTradesEvent),
-  TradeEvent(TradeEvent),
-  KlineEvent(KlineEvent),
-  OrderBook(OrderBook),
-  DepthOrderBookEvent(DepthOrderBookEvent),
-}
+type RequestResponder = oneshot::Sender<Result<Value>>;
 
 /// Command enum that the internal actor will handle
 enum Command {
-  Subscribe(Vec<String>),
-  Unsubscribe(Vec<String>),
-  ListSubscriptions(oneshot::Sender<Vec<String>>),
+  Subscribe(Vec<StreamName>, RequestResponder),
+  Unsubscribe(Vec<StreamName>, RequestResponder),
+  ListSubscriptions(oneshot::Sender<Vec<StreamName>>),
+  /// Any other method of the stream API, e.g. LIST_SUBSCRIPTIONS
+  Request(String, Value, RequestResponder),
   Shutdown,
 }
 
+/// Request sent to the server and waiting for its response
+struct PendingRequest {
+  responder: RequestResponder,
+  /// Streams to drop from subscriptions if the request is rejected
+  streams_on_reject: Vec<StreamName>,
+}
+
+/// Delay before the next try when a replacement connection could not be opened
+const ROTATION_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+type SpotSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
 type WebSocketCallback = dyn FnMut(WebsocketSpotEvent) -> Result<()> + Send + Sync + 'static;
 type SharedWebSocketCallback = Arc<Mutex<Box<WebSocketCallback>>>;
 
 struct WebSocketActor {
   /// Active subscriptions
-  subscriptions: HashSet<String>,
-  /// Event handler (can modify Arcs inside)
-  handler: SharedWebSocketCallback,
+  subscriptions: HashSet<StreamName>,
+  /// Active subscriptions by name and by the events they deliver
+  stream_index: StreamIndex,
+  /// Event handler (can modify Arcs inside) or channel to deliver events to,
+  /// directly or through the event queue
+  delivery: EventDelivery,
   /// WebSocket connection
-  socket: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+  socket: Option<SpotSocket>,
+  /// Replacement connection, open while rotating
+  standby: Option<SpotSocket>,
+  /// Events deduplication while the connection is replaced
+  rotation: Option<Rotation>,
+  /// When the connection gets replaced
+  rotate_at: Option<Instant>,
+  config: WebSocketSpotConfig,
+  /// Failed reconnection attempts in a row
+  reconnect_attempt: u32,
+  /// When the next reconnection attempt is due
+  reconnect_at: Option<Instant>,
+  /// Id of the last request sent to the server
+  last_request_id: u64,
+  /// Requests waiting for a response, by id
+  pending_requests: HashMap<u64, PendingRequest>,
+  /// Requests waiting for the send limiter, serialized
+  queued_requests: VecDeque<String>,
+  send_limiter: SendLimiter,
+  /// Silence of the streams with a heartbeat
+  watchdog: StreamWatchdog,
+  latency_metrics: Arc<FeedLatencyMetrics>,
 }
 
 impl WebSocketActor {
   /// Construct a new WebSockets struct with callback
-  pub fn new(handler: SharedWebSocketCallback) -> Self {
+  pub fn new(
+    sink: EventSink,
+    config: WebSocketSpotConfig,
+    queue_metrics: Arc<EventQueueMetrics>,
+    latency_metrics: Arc<FeedLatencyMetrics>,
+  ) -> Self {
     Self {
       subscriptions: HashSet::new(),
-      handler,
+      stream_index: StreamIndex::default(),
+      delivery: EventDelivery::new(sink, &config, queue_metrics),
+      watchdog: StreamWatchdog::new(config.heartbeats.clone()),
       socket: None,
+      standby: None,
+      rotation: None,
+      rotate_at: None,
+      config,
+      reconnect_attempt: 0,
+      reconnect_at: None,
+      last_request_id: 0,
+      pending_requests: HashMap::new(),
+      queued_requests: VecDeque::new(),
+      send_limiter: SendLimiter::default(),
+      latency_metrics,
     }
   }
 
   /// Subscribe to a stream (adds it dynamically)
-  pub async fn subscribe(&mut self, streams: Vec<String>) -> Result<()> {
-    let mut new_streams: Vec<String> = vec![];
+  ///
+  /// Responds once the server acknowledges the request.
+  /// If there is no connection yet, the streams are part of the connection URL instead
+  pub async fn subscribe(
+    &mut self,
+    streams: Vec<StreamName>,
+    responder: RequestResponder,
+  ) -> Result<()> {
+    let mut new_streams: Vec<StreamName> = vec![];
 
-    streams.iter().for_each(|x| {
-      let inserted = self.subscriptions.insert(x.clone().to_string());
-      if inserted {
-        return new_streams.push(x.to_string());
+    streams.into_iter().for_each(|x| {
+      if self.subscriptions.insert(x.clone()) {
+        self.stream_index.insert(&x);
+        new_streams.push(x);
       }
     });
 
     if new_streams.is_empty() {
+      let _ = responder.send(Ok(Value::Null));
       return Ok(());
     }
 
-    if let Some(socket) = &mut self.socket {
-      let msg = json!({
-          "method": "SUBSCRIBE",
-          "params": new_streams,
-          "id": 1
-      })
-      .to_string();
-      socket.send(Message::Text(Utf8Bytes::from(msg))).await?;
+    if self.socket.is_some() {
+      self.watchdog.restart(&new_streams);
+      let params = json!(
+        new_streams
+          .iter()
+          .map(|s| s.to_string())
+          .collect::<Vec<_>>()
+      );
+      self
+        .send_request("SUBSCRIBE", params, responder, new_streams)
+        .await?;
+    } else if self.reconnect_at.is_some() {
+      // Streams are subscribed with the scheduled reconnection
+      let _ = responder.send(Ok(Value::Null));
     } else {
-      self.connect().await?;
+      self.open_connection().await?;
+      let response = match self.socket {
+        Some(_) => Ok(Value::Null),
+        None => Err(anyhow!(
+          "Failed to connect, streams are kept for the reconnection"
+        )),
+      };
+      let _ = responder.send(response);
     }
     Ok(())
   }
 
   /// Unsubscribe from a stream (removes dynamically)
-  pub async fn unsubscribe(&mut self, streams: Vec<String>) -> Result<()> {
+  ///
+  /// Responds once the server acknowledges the request
+  pub async fn unsubscribe(
+    &mut self,
+    streams: Vec<StreamName>,
+    responder: RequestResponder,
+  ) -> Result<()> {
     let mut remove_streams: Vec<String> = vec![];
 
     streams.iter().for_each(|x| {
-      let removed = self.subscriptions.remove(&x.clone());
-      if removed {
-        return remove_streams.push(x.to_string());
+      if self.subscriptions.remove(x) {
+        self.stream_index.remove(x);
+        remove_streams.push(x.to_string());
       }
     });
 
     if remove_streams.is_empty() {
+      let _ = responder.send(Ok(Value::Null));
       return Ok(());
     }
 
-    if let Some(socket) = &mut self.socket {
-      let msg = json!({
-          "method": "UNSUBSCRIBE",
-          "params": remove_streams,
-          "id": 2
-      })
-      .to_string();
-      socket.send(Message::Text(Utf8Bytes::from(msg))).await?;
+    if self.subscriptions.is_empty() {
+      // Nothing left to listen to, closing the connection unsubscribes from everything
+      self.reconnect_at = None;
+      if self.socket.is_some() {
+        self.disconnect().await?;
+        self.fail_pending_requests("Connection closed");
+        self
+          .emit(WebsocketSpotEvent::ConnectionState(
+            ConnectionState::Disconnected {
+              reason: "No active subscriptions".into(),
+            },
+          ))
+          .await?;
+      }
+      let _ = responder.send(Ok(Value::Null));
+    } else if self.socket.is_some() {
+      self
+        .send_request("UNSUBSCRIBE", json!(remove_streams), responder, vec![])
+        .await?;
+    } else {
+      let _ = responder.send(Ok(Value::Null));
     }
+    Ok(())
+  }
 
-    if self.subscriptions.is_empty() {
-      self.disconnect().await?;
+  /// Send a request with a unique id, the response is delivered to the responder.
+  /// Requests over the message limit of the connection are queued
+  async fn send_request(
+    &mut self,
+    method: &str,
+    params: Value,
+    responder: RequestResponder,
+    streams_on_reject: Vec<StreamName>,
+  ) -> Result<()> {
+    if self.socket.is_none() {
+      let _ = responder.send(Err(anyhow!("WebSocket is not connected")));
+      return Ok(());
+    }
+
+    self.last_request_id += 1;
+    let id = self.last_request_id;
+    // Forget requests nobody waits for anymore, e.g. timed out ones
+    self
+      .pending_requests
+      .retain(|_, pending| !pending.responder.is_closed());
+
+    let msg = json!({
+        "method": method,
+        "params": params,
+        "id": id
+    })
+    .to_string();
+
+    self.pending_requests.insert(
+      id,
+      PendingRequest {
+        responder,
+        streams_on_reject,
+      },
+    );
+    self.queued_requests.push_back(msg);
+    self.send_queued_requests().await
+  }
+
+  /// Send the queued requests the message limit of the connection allows now
+  async fn send_queued_requests(&mut self) -> Result<()> {
+    while self.send_limiter.next_send_at().is_none()
+      && let Some(msg) = self.queued_requests.pop_front()
+    {
+      let Some(socket) = &mut self.socket else {
+        self.queued_requests.clear();
+        return Ok(());
+      };
+
+      self.send_limiter.sent();
+      if let Err(e) = socket
+        .send(Message::Text(Utf8Bytes::from(msg.clone())))
+        .await
+      {
+        return self
+          .connection_lost(format!("Failed to send request: {}", e))
+          .await;
+      }
+      // The replacement connection gets the same request, the first response wins
+      if let Some(standby) = &mut self.standby {
+        let _ = standby.send(Message::Text(Utf8Bytes::from(msg))).await;
+      }
     }
     Ok(())
   }
 
+  /// Deliver the response to the request it answers
+  fn handle_response(&mut self, id: u64, json: &Value) {
+    let Some(pending) = self.pending_requests.remove(&id) else {
+      return;
+    };
+
+    let error = json.get("error").unwrap_or(json);
+    let response = match (error.get("code"), error.get("msg")) {
+      (Some(code), Some(msg)) => Err(anyhow!("Binance error: code={}, msg={}", code, msg)),
+      _ => Ok(json.get("result").cloned().unwrap_or(Value::Null)),
+    };
+
+    if response.is_err() {
+      for stream in pending.streams_on_reject.iter() {
+        self.subscriptions.remove(stream);
+        self.stream_index.remove(stream);
+      }
+    }
+    let _ = pending.responder.send(response);
+  }
+
+  /// Answer every request waiting for a response with an error, queued ones are dropped
+  fn fail_pending_requests(&mut self, reason: &str) {
+    self.queued_requests.clear();
+    for (_, pending) in self.pending_requests.drain() {
+      let _ = pending.responder.send(Err(anyhow!("{}", reason)));
+    }
+  }
+
   /// Unsubscribe from a stream (removes dynamically)
-  pub async fn list_subscriptions(&mut self) -> Result<Vec<String>> {
+  pub async fn list_subscriptions(&mut self) -> Result<Vec<StreamName>> {
     Ok(self.subscriptions.iter().cloned().collect())
   }
 
@@ -159,34 +359,245 @@ impl WebSocketActor {
       return Ok(());
     }
 
-    let res: Vec<String> = self.subscriptions.iter().cloned().collect();
-    let url = WebsocketUrl::MultiStream.params(&res.join("/"));
-    let (socket, _) = connect_async(url).await?;
-    self.socket = Some(socket);
+    self.socket = Some(self.open_socket().await?);
+    self.rotate_at = self
+      .config
+      .rotation_interval
+      .map(|interval| Instant::now() + interval);
     Ok(())
   }
 
+  /// Open a socket subscribed to current subscriptions
+  async fn open_socket(&self) -> Result<SpotSocket> {
+    let res: Vec<String> = self.subscriptions.iter().map(|s| s.to_string()).collect();
+    let mut url = self
+      .config
+      .url
+      .params(&self.config.base_url(), &res.join("/"));
+    if let Some(time_unit) = &self.config.time_unit {
+      let separator = if url.contains('?') { '&' } else { '?' };
+      url = format!("{}{}timeUnit={}", url, separator, time_unit);
+    }
+    let mut request = url.into_client_request()?;
+    if let StreamEncoding::Sbe { api_key } = &self.config.encoding {
+      request
+        .headers_mut()
+        .insert("X-MBX-APIKEY", api_key.parse()?);
+    }
+    let (socket, _) = connect_async(request).await?;
+    Ok(socket)
+  }
+
   /// Disconnect WebSocket
   async fn disconnect(&mut self) -> Result<()> {
+    self.rotate_at = None;
+    self.rotation = None;
+    if let Some(mut standby) = self.standby.take() {
+      let _ = standby.close(None).await;
+    }
     if let Some(mut socket) = self.socket.take() {
       socket.close(None).await?;
     }
     Ok(())
   }
 
+  /// Open the replacement connection, both are read until it catches up
+  async fn start_rotation(&mut self) -> Result<()> {
+    self.rotate_at = None;
+
+    match self.open_socket().await {
+      Ok(standby) => {
+        self.standby = Some(standby);
+        self.rotation = Some(Rotation::new());
+      }
+      Err(_) => {
+        // The current connection still works, try again a bit later
+        self.rotate_at = Some(Instant::now() + ROTATION_RETRY_DELAY);
+      }
+    }
+    Ok(())
+  }
+
+  /// Close the old connection and continue with the replacement
+  async fn switch_to_standby(&mut self) -> Result<()> {
+    let Some(standby) = self.standby.take() else {
+      return Ok(());
+    };
+
+    if let Some(mut socket) = self.socket.replace(standby) {
+      let _ = socket.close(None).await;
+    }
+    if let Some(rotation) = &mut self.rotation {
+      rotation.switched_at = Some(Instant::now());
+    }
+    self.rotate_at = self
+      .config
+      .rotation_interval
+      .map(|interval| Instant::now() + interval);
+
+    self
+      .emit(WebsocketSpotEvent::ConnectionState(
+        ConnectionState::Rotated,
+      ))
+      .await
+  }
+
+  /// Drop the broken replacement connection and try again later
+  fn standby_lost(&mut self) {
+    self.standby = None;
+    self.rotation = None;
+    self.rotate_at = Some(Instant::now() + ROTATION_RETRY_DELAY);
+  }
+
+  /// Connect with current subscriptions, scheduling a reconnection if it fails
+  async fn open_connection(&mut self) -> Result<()> {
+    self.reconnect_at = None;
+
+    match self.connect().await {
+      Ok(()) => {
+        self.reconnect_attempt = 0;
+        if self.socket.is_some() {
+          self.watchdog.restart(&self.subscriptions);
+          self
+            .emit(WebsocketSpotEvent::ConnectionState(
+              ConnectionState::Connected,
+            ))
+            .await?;
+        }
+        Ok(())
+      }
+      Err(e) => {
+        self
+          .connection_lost(format!("Failed to connect: {}", e))
+          .await
+      }
+    }
+  }
+
+  /// Drop the broken connection and schedule the next reconnection attempt with backoff
+  async fn connection_lost(&mut self, reason: String) -> Result<()> {
+    // The socket is broken already, so there is nothing to close gracefully
+    self.socket = None;
+    if self.standby.is_some() {
+      // The replacement is already open, nothing is lost
+      return self.switch_to_standby().await;
+    }
+    self.rotation = None;
+    self.rotate_at = None;
+    self.fail_pending_requests("Connection lost before the response");
+    self
+      .emit(WebsocketSpotEvent::ConnectionState(
+        ConnectionState::Disconnected { reason },
+      ))
+      .await?;
+
+    if self.subscriptions.is_empty() {
+      return Ok(());
+    }
+
+    let policy = &self.config.reconnect_policy;
+    let attempt = self.reconnect_attempt + 1;
+    if !policy.allows_attempt(attempt) {
+      let attempts = self.reconnect_attempt;
+      self.reconnect_attempt = 0;
+      return self
+        .emit(WebsocketSpotEvent::ConnectionState(
+          ConnectionState::GaveUp { attempts },
+        ))
+        .await;
+    }
+
+    let delay = policy.delay_for_attempt(attempt);
+    self.reconnect_attempt = attempt;
+    self.reconnect_at = Some(Instant::now() + delay);
+
+    self
+      .emit(WebsocketSpotEvent::ConnectionState(
+        ConnectionState::Reconnecting {
+          attempt,
+          delay_ms: delay.as_millis() as u64,
+        },
+      ))
+      .await
+  }
+
+  /// Report streams silent past their heartbeat and get them delivering again
+  async fn check_stale_streams(&mut self) -> Result<()> {
+    let stale = self.watchdog.stale_streams(&self.subscriptions);
+    if stale.is_empty() {
+      return Ok(());
+    }
+
+    for (stream, silence) in stale.iter() {
+      self
+        .emit(WebsocketSpotEvent::ConnectionState(
+          ConnectionState::StaleStream {
+            stream: stream.to_string(),
+            silent_ms: silence.as_millis() as u64,
+          },
+        ))
+        .await?;
+    }
+    let streams: Vec<StreamName> = stale.into_iter().map(|(stream, _)| stream).collect();
+    self.watchdog.restart(&streams);
+
+    match self.config.stale_stream_action {
+      StaleStreamAction::Notify => Ok(()),
+      StaleStreamAction::Resubscribe => {
+        let params = json!(streams.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        // Nobody waits for the responses, the streams stay subscribed either way
+        let (responder, _) = oneshot::channel();
+        self
+          .send_request("UNSUBSCRIBE", params.clone(), responder, vec![])
+          .await?;
+        let (responder, _) = oneshot::channel();
+        self
+          .send_request("SUBSCRIBE", params, responder, vec![])
+          .await
+      }
+      StaleStreamAction::Reconnect => {
+        self.disconnect().await?;
+        self.fail_pending_requests("Connection closed");
+        let names: Vec<String> = streams.iter().map(|s| s.to_string()).collect();
+        self
+          .emit(WebsocketSpotEvent::ConnectionState(
+            ConnectionState::Disconnected {
+              reason: format!("Stale streams: {}", names.join(", ")),
+            },
+          ))
+          .await?;
+        self.open_connection().await
+      }
+    }
+  }
+
+  /// Deliver an event to the handler
+  async fn emit(&self, event: WebsocketSpotEvent) -> Result<()> {
+    self.delivery.send(None, event).await
+  }
+
   async fn run(mut self, mut cmd_rx: Receiver<Command>) -> Result<()> {
     let mut keep_running = true;
 
     while keep_running {
+      let stale_deadline = match self.socket {
+        Some(_) => self.watchdog.next_deadline(&self.subscriptions),
+        None => None,
+      };
+      let next_send_at = self.send_limiter.next_send_at();
+
       tokio::select! {
         command = cmd_rx.recv() => {
           match command {
-            Some(Command::Subscribe(stream)) => {
-              self.subscribe(stream).await?;
+            Some(Command::Subscribe(stream, responder)) => {
+              self.subscribe(stream, responder).await?;
             }
-            Some(Command::Unsubscribe(streams)) => {
-              self.unsubscribe(streams).await?;
+            Some(Command::Unsubscribe(streams, responder)) => {
+              self.unsubscribe(streams, responder).await?;
             },
+            Some(Command::Request(method, params, responder)) => {
+              self.send_request(&method, params, responder, vec![]).await?;
+            }
             Some(Command::ListSubscriptions(tx)) => {
               let subscriptions = self.list_subscriptions().await?;
               let _ = tx.send(subscriptions);
@@ -202,8 +613,17 @@ impl WebSocketActor {
               }
           },
 
+          // 2) Or reconnect once the backoff delay has passed
+          _ = async {
+            if let Some(reconnect_at) = self.reconnect_at {
+              tokio::time::sleep_until(reconnect_at).await;
+            }
+          }, if self.reconnect_at.is_some() => {
+            self.open_connection().await?;
+          },
 
-          // 2) Or read next WebSocket message (if connected)
+
+          // 3) Or read next WebSocket message (if connected)
           next_message = async {
           if let Some(socket) = &mut self.socket {
             socket.next().await
@@ -213,28 +633,106 @@ impl WebSocketActor {
             None // no message
           }
           }, if self.socket.is_some() => {
+          if let Some(Ok(message)) = &next_message {
+            self.record_frame(message, false);
+          }
           match next_message {
             Some(Ok(Message::Text(msg))) => {
-              self.handle_incoming_message(&msg).await?;
+              self.handle_incoming_message(&msg, None, false).await?;
+            }
+            Some(Ok(Message::Binary(frame))) => {
+              self.handle_binary_message(&frame, false).await?;
             }
             Some(Ok(Message::Ping(payload))) => {
-              if let Some(socket) = &mut self.socket {
-                socket.send(Message::Pong(payload)).await?;
+              if let Some(socket) = &mut self.socket
+                && let Err(e) = socket.send(Message::Pong(payload)).await
+              {
+                self.connection_lost(format!("Failed to send pong: {}", e)).await?;
               }
             }
-            Some(Ok(Message::Close(_))) => {
-              eprintln!("WebSocket disconnected. Attempting to reconnect or break?");
-              self.disconnect().await?;
-              // optionally attempt reconnection here, or just keep running
+            Some(Ok(Message::Close(frame))) => {
+              let reason = match frame {
+                Some(frame) => format!("Closed by server: {} {}", frame.code, frame.reason),
+                None => "Closed by server".to_string(),
+              };
+              self.connection_lost(reason).await?;
             },
             Some(Err(e)) => {
-                            eprintln!("WebSocket error: {:?}", e);
-                            self.disconnect().await?;
-                            // optionally attempt to reconnect
-                        }
+              self.connection_lost(format!("WebSocket error: {}", e)).await?;
+            }
+            None => {
+              self.connection_lost("WebSocket stream ended".to_string()).await?;
+            }
             _ => {}
           }
           }
+
+          // 4) Or open a replacement connection before Binance closes the current one
+          _ = async {
+            if let Some(rotate_at) = self.rotate_at {
+              tokio::time::sleep_until(rotate_at).await;
+            }
+          }, if self.rotate_at.is_some() && self.socket.is_some() && self.standby.is_none() => {
+            self.start_rotation().await?;
+          },
+
+          // 5) Or read the replacement connection while rotating
+          next_message = async {
+            match &mut self.standby {
+              Some(standby) => standby.next().await,
+              None => None,
+            }
+          }, if self.standby.is_some() => {
+            if let Some(Ok(message)) = &next_message {
+              self.record_frame(message, true);
+            }
+            match next_message {
+              Some(Ok(Message::Text(msg))) => {
+                self.handle_incoming_message(&msg, None, true).await?;
+              }
+              Some(Ok(Message::Binary(frame))) => {
+                self.handle_binary_message(&frame, true).await?;
+              }
+              Some(Ok(Message::Ping(payload))) => {
+                if let Some(standby) = &mut self.standby
+                  && standby.send(Message::Pong(payload)).await.is_err()
+                {
+                  self.standby_lost();
+                }
+              }
+              Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                self.standby_lost();
+              }
+              _ => {}
+            }
+          },
+
+          // 6) Or switch to the replacement if no overlap was seen in time, e.g. quiet streams
+          _ = async {
+            if let Some(rotation) = &self.rotation {
+              tokio::time::sleep_until(rotation.started_at + self.config.rotation_overlap).await;
+            }
+          }, if self.standby.is_some() && self.rotation.is_some() => {
+            self.switch_to_standby().await?;
+          }
+
+          // 7) Or act on streams that stopped delivering while pings keep the connection open
+          _ = async {
+            if let Some(stale_deadline) = stale_deadline {
+              tokio::time::sleep_until(stale_deadline).await;
+            }
+          }, if stale_deadline.is_some() => {
+            self.check_stale_streams().await?;
+          }
+
+          // 8) Or send the requests queued over the message limit
+          _ = async {
+            if let Some(next_send_at) = next_send_at {
+              tokio::time::sleep_until(next_send_at).await;
+            }
+          }, if !self.queued_requests.is_empty() => {
+            self.send_queued_requests().await?;
+          }
       }
     }
 
@@ -243,77 +741,248 @@ impl WebSocketActor {
     Ok(())
   }
 
+  /// Write a text or binary frame to the recorder, if set
+  fn record_frame(&self, message: &Message, from_standby: bool) {
+    let Some(recorder) = &self.config.recorder else {
+      return;
+    };
+
+    let source = match (from_standby, self.rotation.is_some()) {
+      (true, _) => FrameSource::Standby,
+      (false, true) => FrameSource::PrimaryRotating,
+      (false, false) => FrameSource::Primary,
+    };
+    let recorded = match message {
+      Message::Text(msg) => recorder.record_text(SystemTime::now(), source, msg),
+      Message::Binary(frame) => recorder.record_binary(SystemTime::now(), source, frame),
+      _ => return,
+    };
+    if let Err(e) = recorded {
+      eprintln!("Failed to record frame: {:?}", e);
+    }
+  }
+
   /// Processes incoming messages
-  async fn handle_incoming_message(&mut self, msg: &str) -> Result<()> {
-    let json: serde_json::Value = serde_json::from_str(msg)?;
+  ///
+  /// - `stream` is the stream name of the combined payload
+  /// - `from_standby` whether the message came from the replacement connection
+  async fn handle_incoming_message(
+    &mut self,
+    msg: &str,
+    stream: Option<&str>,
+    from_standby: bool,
+  ) -> Result<()> {
+    let action = match dispatch::parse_message(stream, msg) {
+      IncomingMessage::Combined { stream, data } => {
+        return Box::pin(self.handle_incoming_message(
+          data.get(),
+          stream.as_deref(),
+          from_standby,
+        ))
+        .await;
+      }
+      // Responses to requests, e.g. {"result":null,"id":1}
+      IncomingMessage::Response { id, json } => {
+        self.handle_response(id, &json);
+        return Ok(());
+      }
+      IncomingMessage::Event(event) => *event,
+    };
 
-    if let Some(data) = json.get("data") {
-      Box::pin(self.handle_incoming_message(&data.to_string())).await?;
-      return Ok(());
+    self.handle_event(stream, action, from_standby).await
+  }
+
+  /// Processes SBE encoded frames, they hold no stream name
+  async fn handle_binary_message(&mut self, frame: &[u8], from_standby: bool) -> Result<()> {
+    let events = match sbe::decode(frame, self.config.time_unit.as_ref()) {
+      Ok(events) => events,
+      Err(e) => {
+        eprintln!("Failed to decode SBE frame: {:?}", e);
+        return Ok(());
+      }
+    };
+
+    for event in events {
+      self.handle_event(None, event, from_standby).await?;
     }
+    Ok(())
+  }
 
-    let res_json = serde_json::from_value::<InternalEvents>(json);
-    if let Ok(events) = res_json {
-      let action = match events {
-        InternalEvents::DayTickerEventAll(v) => WebsocketSpotEvent::DayTickerAll(v),
-        InternalEvents::WindowTickerEventAll(v) => WebsocketSpotEvent::WindowTickerAll(v),
-        InternalEvents::BookTickerEvent(v) => WebsocketSpotEvent::BookTicker(v),
-        InternalEvents::BalanceUpdateEvent(v) => WebsocketSpotEvent::BalanceUpdate(v),
-        InternalEvents::AccountUpdateEvent(v) => WebsocketSpotEvent::AccountUpdate(v),
-        InternalEvents::OrderTradeEvent(v) => WebsocketSpotEvent::OrderTrade(v),
-        InternalEvents::AggTradesEvent(v) => WebsocketSpotEvent::AggTrades(v),
-        InternalEvents::TradeEvent(v) => WebsocketSpotEvent::Trade(v),
-        InternalEvents::DayTickerEvent(v) => WebsocketSpotEvent::DayTicker(v),
-        InternalEvents::WindowTickerEvent(v) => WebsocketSpotEvent::WindowTicker(v),
-        InternalEvents::KlineEvent(v) => WebsocketSpotEvent::Kline(v),
-        InternalEvents::OrderBook(v) => WebsocketSpotEvent::OrderBook(v),
-        InternalEvents::DepthOrderBookEvent(v) => WebsocketSpotEvent::DepthOrderBook(v),
-      };
+  /// Deliver an event unless the other connection delivered it already while rotating
+  async fn handle_event(
+    &mut self,
+    stream: Option<&str>,
+    action: WebsocketSpotEvent,
+    from_standby: bool,
+  ) -> Result<()> {
+    if let Some(rotation) = &mut self.rotation
+      && let Some(key) = rotation::event_key(stream, &action)
+      && rotation.is_duplicate(key, from_standby)
+    {
+      return self.finish_rotation_step().await;
+    }
+
+    let event_stream = self.stream_index.event_stream(stream, &action);
+    if let Some(event_stream) = &event_stream {
+      self.watchdog.event_received(event_stream);
+      if let Some(latency_ms) =
+        feed_health::event_latency_ms(&action, self.config.time_unit.as_ref())
       {
-        let mut handler = self.handler.lock().await;
-        (handler)(action)?;
+        self
+          .latency_metrics
+          .record(&event_stream.to_string(), latency_ms);
       }
     }
+    self.delivery.send(event_stream, action).await?;
+    self.finish_rotation_step().await
+  }
+
+  /// Switch to the replacement once it caught up, stop deduplicating after the overlap
+  async fn finish_rotation_step(&mut self) -> Result<()> {
+    let Some(rotation) = &self.rotation else {
+      return Ok(());
+    };
+
+    if rotation.overlapped && self.standby.is_some() {
+      self.switch_to_standby().await?;
+    } else if rotation.is_finished(self.config.rotation_overlap) {
+      self.rotation = None;
+    }
     Ok(())
   }
 }
 
+/// Spot market streams over one or more connections.
+///
+/// Subscriptions are spread across connections of at most
+/// `max_streams_per_connection` streams each, new connections are opened as needed.
+/// Events of all connections go to the same handler.
 pub struct WebSocketSpotStream {
-  command_tx: mpsc::Sender<Command>,
-  join_handle: JoinHandle<Result<()>>,
-  callback: SharedWebSocketCallback,
+  /// Connections the subscriptions are spread across
+  shards: Mutex<Vec<Shard>>,
+  sink: EventSink,
+  config: WebSocketSpotConfig,
+  /// Event queue counters of all connections
+  queue_metrics: Arc<EventQueueMetrics>,
+  /// Event time delays of all connections
+  latency_metrics: Arc<FeedLatencyMetrics>,
 }
 
 impl WebSocketSpotStream {
-  /// Construct and start background task immediately.
+  /// Construct the stream, connections are opened on subscription.
   ///
   /// - `handler` is the callback for incoming events.
-  /// Should be wrapped into Arc + Mutex + Box
+  ///   Should be wrapped into Arc + Mutex + Box
   pub fn new_with_shared_handler(handler: SharedWebSocketCallback) -> Self {
-    // Create the command channel
-    let (tx, rx) = mpsc::channel(32);
+    Self::new_with_shared_handler_and_config(handler, WebSocketSpotConfig::default())
+  }
 
-    // Create and start the actor with a boxed version of our handler
-    let actor = WebSocketActor::new(handler.clone());
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// - `handler` is the callback for incoming events.
+  ///   Should be wrapped into Arc + Mutex + Box
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_shared_handler_and_config(
+    handler: SharedWebSocketCallback,
+    config: WebSocketSpotConfig,
+  ) -> Self {
+    Self::new_with_sink(EventSink::Callback(handler), config)
+  }
 
-    // Spawn the actor in a background task
-    let join_handle = tokio::spawn(async move {
-      let result = actor.run(rx).await;
-      if let Err(ref e) = result {
-        eprintln!("WebSocket actor error: {:?}", e);
-      }
-      result
-    });
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// - `handler` gets every event through the method of its type
+  pub fn new_with_event_handler<H: WebsocketSpotEventHandler>(handler: H) -> Self {
+    Self::new_with_event_handler_and_config(handler, WebSocketSpotConfig::default())
+  }
 
-    // Return a handle that can be used to send commands to that actor
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// - `handler` gets every event through the method of its type
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_event_handler_and_config<H: WebsocketSpotEventHandler>(
+    handler: H,
+    config: WebSocketSpotConfig,
+  ) -> Self {
+    Self::new_with_sink(
+      EventSink::Handler(Arc::new(Mutex::new(Box::new(handler)))),
+      config,
+    )
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Events are read from the returned stream instead of a callback.
+  /// - `buffer` is the number of events kept while the stream is not polled,
+  ///   reading from the socket pauses once it is full
+  pub fn new_with_channel(buffer: usize) -> (Self, WebsocketSpotEventStream) {
+    Self::new_with_channel_and_config(buffer, WebSocketSpotConfig::default())
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Events are read from the returned stream instead of a callback.
+  /// - `buffer` is the number of events kept while the stream is not polled,
+  ///   reading from the socket pauses once it is full
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_channel_and_config(
+    buffer: usize,
+    config: WebSocketSpotConfig,
+  ) -> (Self, WebsocketSpotEventStream) {
+    let (events_tx, events_rx) = mpsc::channel(buffer);
+    let stream = Self::new_with_sink(EventSink::Channel(events_tx), config);
+    (stream, WebsocketSpotEventStream::new(events_rx))
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Every receiver from `event_receiver` gets all events of the stream.
+  /// - `capacity` is the number of events kept for the slowest receiver,
+  ///   older events are skipped for it once exceeded
+  pub fn new_with_broadcast(capacity: usize) -> Self {
+    Self::new_with_broadcast_and_config(capacity, WebSocketSpotConfig::default())
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Every receiver from `event_receiver` gets all events of the stream.
+  /// - `capacity` is the number of events kept for the slowest receiver,
+  ///   older events are skipped for it once exceeded
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_broadcast_and_config(capacity: usize, config: WebSocketSpotConfig) -> Self {
+    let (events_tx, _) = broadcast::channel(capacity);
+    Self::new_with_sink(EventSink::Broadcast(events_tx), config)
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Events go to handlers registered with `route_stream` and `route_events`,
+  /// streams are subscribed and unsubscribed with their handlers
+  pub fn new_with_router() -> Arc<Self> {
+    Self::new_with_router_and_config(WebSocketSpotConfig::default())
+  }
+
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// Events go to handlers registered with `route_stream` and `route_events`,
+  /// streams are subscribed and unsubscribed with their handlers
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_router_and_config(config: WebSocketSpotConfig) -> Arc<Self> {
+    let router = Arc::new(EventRouter::default());
+    Arc::new(Self::new_with_sink(EventSink::Router(router), config))
+  }
+
+  fn new_with_sink(sink: EventSink, config: WebSocketSpotConfig) -> Self {
     Self {
-      command_tx: tx,
-      join_handle,
-      callback: handler,
+      shards: Mutex::new(Vec::new()),
+      sink,
+      config,
+      queue_metrics: Arc::new(EventQueueMetrics::default()),
+      latency_metrics: Arc::new(FeedLatencyMetrics::default()),
     }
   }
 
-  /// Construct and start background task immediately.
+  /// Construct the stream, connections are opened on subscription.
   ///
   /// - `handler` is the callback for incoming events.
   pub fn new<Callback>(handler: Callback) -> Self
@@ -323,55 +992,384 @@ impl WebSocketSpotStream {
     Self::new_with_shared_handler(Arc::new(Mutex::new(Box::new(handler))))
   }
 
+  /// Construct the stream, connections are opened on subscription.
+  ///
+  /// - `handler` is the callback for incoming events.
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_config<Callback>(handler: Callback, config: WebSocketSpotConfig) -> Self
+  where
+    Callback: FnMut(WebsocketSpotEvent) -> Result<()> + Send + Sync + 'static,
+  {
+    Self::new_with_shared_handler_and_config(Arc::new(Mutex::new(Box::new(handler))), config)
+  }
+
+  /// Deliver an event produced outside of the connections, e.g. by the user data stream
+  pub(crate) async fn emit(&self, event: WebsocketSpotEvent) -> Result<()> {
+    self.sink.send(None, event).await
+  }
+
+  /// New receiver of all events from now on.
+  /// Only available for streams created with `new_with_broadcast`
+  pub fn event_receiver(&self) -> Result<broadcast::Receiver<WebsocketSpotEvent>> {
+    match &self.sink {
+      EventSink::Broadcast(tx) => Ok(tx.subscribe()),
+      _ => Err(anyhow!("Stream was not created with a broadcast channel")),
+    }
+  }
+
+  /// Call the handler with the events of the stream and connection state changes,
+  /// subscribing to the stream if it's the first handler of it.
+  /// Only available for streams created with `new_with_router`
+  pub async fn route_stream<Callback>(
+    self: &Arc<Self>,
+    stream: StreamName,
+    handler: Callback,
+  ) -> Result<RouteHandle>
+  where
+    Callback: FnMut(WebsocketSpotEvent) -> Result<()> + Send + Sync + 'static,
+  {
+    let router = self.router()?;
+    let _stream_changes = router.lock_stream_changes().await;
+    let (id, first) =
+      router.add_stream_route(stream.clone(), Arc::new(Mutex::new(Box::new(handler))));
+    let route = RouteHandle::new(id, router.clone(), Arc::downgrade(self));
+
+    if first {
+      // Dropping the route on failure unsubscribes again
+      self.subscribe(vec![stream]).await?;
+    }
+    Ok(route)
+  }
+
+  /// Call the handler with the events of the kind and symbol from any subscribed stream,
+  /// and connection state changes. The streams are not subscribed by this route.
+  /// Only available for streams created with `new_with_router`
+  pub fn route_events<Callback>(
+    self: &Arc<Self>,
+    kind: EventKind,
+    symbol: &str,
+    handler: Callback,
+  ) -> Result<RouteHandle>
+  where
+    Callback: FnMut(WebsocketSpotEvent) -> Result<()> + Send + Sync + 'static,
+  {
+    let router = self.router()?;
+    let id = router.add_event_route(
+      kind,
+      symbol.to_string(),
+      Arc::new(Mutex::new(Box::new(handler))),
+    );
+    Ok(RouteHandle::new(id, router, Arc::downgrade(self)))
+  }
+
+  fn router(&self) -> Result<Arc<EventRouter>> {
+    match &self.sink {
+      EventSink::Router(router) => Ok(router.clone()),
+      _ => Err(anyhow!("Stream was not created with a router")),
+    }
+  }
+
   /// Subscribe to a stream
-  pub async fn subscribe(&self, streams: Vec<String>) -> Result<()> {
-    self
-      .command_tx
-      .send(Command::Subscribe(streams))
-      .await
-      .map_err(|_| anyhow::anyhow!("Actor task ended"))?;
-    Ok(())
+  ///
+  /// Streams go to connections with free slots first, a new connection is opened
+  /// for the rest. Waits for the server to acknowledge the subscription.
+  /// Streams rejected by the server are dropped from the subscriptions, and the least
+  /// loaded connection is merged into the others if they have room for its streams.
+  /// Each connection sends at most 5 requests per second, the rest waits
+  pub async fn subscribe(&self, streams: Vec<StreamName>) -> Result<()> {
+    let mut shards = self.shards.lock().await;
+
+    let mut new_streams: Vec<StreamName> = vec![];
+    for stream in streams {
+      if !new_streams.contains(&stream) && !shards.iter().any(|s| s.streams.contains(&stream)) {
+        new_streams.push(stream);
+      }
+    }
+    if new_streams.is_empty() {
+      return Ok(());
+    }
+
+    let max_streams = self.config.max_streams_per_connection.max(1);
+    let free_slots: usize = shards.iter().map(|s| s.free_slots(max_streams)).sum();
+    let connections_needed = new_streams
+      .len()
+      .saturating_sub(free_slots)
+      .div_ceil(max_streams);
+    if let Some(max_connections) = self.config.max_connections
+      && shards.len() + connections_needed > max_connections
+    {
+      bail!(
+        "Subscriptions need {} connections, at most {} allowed",
+        shards.len() + connections_needed,
+        max_connections
+      );
+    }
+
+    for _ in 0..connections_needed {
+      shards.push(Shard::spawn(
+        self.sink.clone(),
+        self.config.clone(),
+        self.queue_metrics.clone(),
+        self.latency_metrics.clone(),
+      ));
+    }
+    let batches = Self::assign_streams(&mut shards, new_streams, max_streams);
+
+    let result = self.send_subscribe(&mut shards, batches).await;
+    self.close_empty_shards(&mut shards).await;
+    self.merge_least_loaded_shard(&mut shards).await?;
+    result
   }
 
   /// Unsubscribe from a stream
-  pub async fn unsubscribe(&self, streams: Vec<String>) -> Result<()> {
-    self
-      .command_tx
-      .send(Command::Unsubscribe(streams))
-      .await
-      .map_err(|_| anyhow::anyhow!("Actor task ended"))?;
+  ///
+  /// Waits for the server to acknowledge the request. Connections left without streams
+  /// are closed, and the least loaded connection is merged into the others if they
+  /// have room for its streams
+  pub async fn unsubscribe(&self, streams: Vec<StreamName>) -> Result<()> {
+    let mut shards = self.shards.lock().await;
+
+    let mut requests = vec![];
+    for shard in shards.iter_mut() {
+      let remove_streams: Vec<StreamName> = streams
+        .iter()
+        .filter(|stream| shard.streams.remove(stream))
+        .cloned()
+        .collect();
+
+      if !remove_streams.is_empty() {
+        requests.push(shard.request(
+          |responder| Command::Unsubscribe(remove_streams, responder),
+          self.config.request_timeout,
+        ));
+      }
+    }
+    let responses = join_all(requests).await;
+
+    self.close_empty_shards(&mut shards).await;
+    self.merge_least_loaded_shard(&mut shards).await?;
+
+    for response in responses {
+      response?;
+    }
     Ok(())
   }
 
-  /// Subscribe to a stream
-  pub async fn list_subscriptions(&self) -> Result<Vec<String>> {
-    let (tx, rx) = oneshot::channel();
-    self
-      .command_tx
-      .send(Command::ListSubscriptions(tx))
-      .await
-      .map_err(|_| anyhow::anyhow!("Actor task ended"))?;
+  /// Put the streams into connections with free slots, in order.
+  /// Returns the streams assigned to every connection by its index
+  fn assign_streams(
+    shards: &mut [Shard],
+    mut streams: Vec<StreamName>,
+    max_streams: usize,
+  ) -> Vec<(usize, Vec<StreamName>)> {
+    let mut batches = vec![];
 
-    rx.await
-      .map_err(|_| anyhow::anyhow!("Actor task ended or response channel closed"))
+    for (index, shard) in shards.iter_mut().enumerate() {
+      let take = shard.free_slots(max_streams).min(streams.len());
+      if take == 0 {
+        continue;
+      }
+      let batch: Vec<StreamName> = streams.drain(..take).collect();
+      shard.streams.extend(batch.iter().cloned());
+      batches.push((index, batch));
+    }
+
+    batches
   }
 
-  /// Ask the actor to shut down. Optionally you can `await` the join handle after this.
+  /// Subscribe every connection to its streams.
+  /// Connections with a failed subscription take their streams from the actor
+  async fn send_subscribe(
+    &self,
+    shards: &mut [Shard],
+    batches: Vec<(usize, Vec<StreamName>)>,
+  ) -> Result<()> {
+    let requests = batches.into_iter().map(|(index, batch)| {
+      let shard = &shards[index];
+      async move {
+        let response = shard
+          .request(
+            |responder| Command::Subscribe(batch, responder),
+            self.config.request_timeout,
+          )
+          .await;
+        (index, response)
+      }
+    });
+    let responses = join_all(requests).await;
+
+    let mut result = Ok(());
+    for (index, response) in responses {
+      if let Err(e) = response {
+        shards[index].sync_streams().await?;
+        if result.is_ok() {
+          result = Err(e);
+        }
+      }
+    }
+    result
+  }
+
+  /// Close connections that have no streams left
+  async fn close_empty_shards(&self, shards: &mut Vec<Shard>) {
+    let (empty, active): (Vec<Shard>, Vec<Shard>) =
+      shards.drain(..).partition(|s| s.streams.is_empty());
+    *shards = active;
+
+    for shard in empty {
+      let _ = shard.shutdown().await;
+    }
+  }
+
+  /// Move the streams of the least loaded connection to the others and close it,
+  /// if they have room. Streams are subscribed on the new connection first,
+  /// so events of the moved streams can be delivered twice for a moment
+  async fn merge_least_loaded_shard(&self, shards: &mut Vec<Shard>) -> Result<()> {
+    let max_streams = self.config.max_streams_per_connection.max(1);
+
+    let Some((index, _)) = shards
+      .iter()
+      .enumerate()
+      .min_by_key(|(_, shard)| shard.streams.len())
+    else {
+      return Ok(());
+    };
+    let free_slots: usize = shards
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| *i != index)
+      .map(|(_, shard)| shard.free_slots(max_streams))
+      .sum();
+    if shards.len() < 2 || shards[index].streams.len() > free_slots {
+      return Ok(());
+    }
+
+    let merged = shards.remove(index);
+    let streams = merged.streams.iter().cloned().collect();
+    let batches = Self::assign_streams(shards, streams, max_streams);
+    let result = self.send_subscribe(shards, batches).await;
+
+    let _ = merged.shutdown().await;
+    result
+  }
+
+  /// Streams the server reports as subscribed on the connections
+  pub async fn list_server_subscriptions(&self) -> Result<Vec<StreamName>> {
+    let shards = self.shards.lock().await;
+    let mut subscriptions = vec![];
+
+    for shard in shards.iter() {
+      let result = shard
+        .request(
+          |responder| Command::Request("LIST_SUBSCRIPTIONS".into(), json!([]), responder),
+          self.config.request_timeout,
+        )
+        .await?;
+
+      let names: Vec<String> = serde_json::from_value(result)?;
+      for name in names {
+        subscriptions.push(name.parse()?);
+      }
+    }
+    Ok(subscriptions)
+  }
+
+  /// Enable or disable the combined stream payload `{"stream":..,"data":..}`
+  /// on every connection
+  pub async fn set_combined(&self, combined: bool) -> Result<()> {
+    let shards = self.shards.lock().await;
+
+    for shard in shards.iter() {
+      shard
+        .request(
+          |responder| {
+            Command::Request(
+              "SET_PROPERTY".into(),
+              json!(["combined", combined]),
+              responder,
+            )
+          },
+          self.config.request_timeout,
+        )
+        .await?;
+    }
+    Ok(())
+  }
+
+  /// Whether the server sends the combined stream payload, asks the first connection
+  pub async fn is_combined(&self) -> Result<bool> {
+    let shards = self.shards.lock().await;
+    let shard = shards
+      .first()
+      .ok_or_else(|| anyhow!("WebSocket is not connected"))?;
+
+    let result = shard
+      .request(
+        |responder| Command::Request("GET_PROPERTY".into(), json!(["combined"]), responder),
+        self.config.request_timeout,
+      )
+      .await?;
+
+    result
+      .as_bool()
+      .ok_or_else(|| anyhow!("Unexpected GET_PROPERTY result: {}", result))
+  }
+
+  /// Subscriptions of all connections
+  pub async fn list_subscriptions(&self) -> Result<Vec<StreamName>> {
+    let shards = self.shards.lock().await;
+    let mut subscriptions = vec![];
+
+    for shard in shards.iter() {
+      subscriptions.extend(shard.list_subscriptions().await?);
+    }
+    Ok(subscriptions)
+  }
+
+  /// Depth and dropped events of the event queues of all connections
+  pub fn event_queue_stats(&self) -> EventQueueStats {
+    self.queue_metrics.stats()
+  }
+
+  /// Delay between the event time and the receive time of each stream, over its latest events
+  pub fn feed_latency_stats(&self) -> HashMap<String, FeedLatencyStats> {
+    self.latency_metrics.stats()
+  }
+
+  /// Delay statistics of one stream, `None` before its first event with an event time
+  pub fn stream_latency_stats(&self, stream: &StreamName) -> Option<FeedLatencyStats> {
+    self.latency_metrics.stream_stats(stream)
+  }
+
+  /// Number of open connections
+  pub async fn connections_count(&self) -> usize {
+    self.shards.lock().await.len()
+  }
+
+  /// Ask the actors to shut down. Optionally you can `await` the join handle after this.
   pub async fn shutdown(&self) -> Result<()> {
-    self
-      .command_tx
-      .send(Command::Shutdown)
-      .await
-      .map_err(|_| anyhow::anyhow!("Actor task ended"))?;
+    let shards = self.shards.lock().await;
+
+    for shard in shards.iter() {
+      shard.shutdown().await?;
+    }
     Ok(())
   }
 
-  /// If you want, you can provide a method to wait for the actor to finish.
+  /// If you want, you can provide a method to wait for the actors to finish.
   pub async fn wait_for_end(self) -> Result<()> {
-    match self.join_handle.await {
-      Ok(r) => r,
-      Err(e) => Err(anyhow::anyhow!("Join error: {:?}", e)),
+    let mut result = Ok(());
+
+    for shard in self.shards.into_inner() {
+      let shard_result = match shard.join_handle.await {
+        Ok(r) => r,
+        Err(e) => Err(anyhow::anyhow!("Join error: {:?}", e)),
+      };
+      if result.is_ok() {
+        result = shard_result;
+      }
     }
+    result
   }
 
   /// Implementation that creates an entirely new WebSocketSpotStream instance
@@ -390,7 +1388,7 @@ impl WebSocketSpotStream {
     }
 
     // Create a new stream with our handler
-    let new_stream = WebSocketSpotStream::new_with_shared_handler(self.callback.clone());
+    let new_stream = WebSocketSpotStream::new_with_sink(self.sink.clone(), self.config.clone());
 
     // Step 3: Replace self with the new stream
     *self = new_stream;
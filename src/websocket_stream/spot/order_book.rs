@@ -0,0 +1,606 @@
+use crate::rest::spot::v3::market::responses::OrderBookResponse;
+use crate::rest::spot::v3::market::SpotMarketV3Manager;
+use crate::websocket_stream::spot::enums::DepthUpdateSpeed;
+use crate::websocket_stream::spot::events::{
+  ConnectionState, DepthOrderBookEvent, WebsocketSpotEvent,
+};
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::WebSocketSpotStream;
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+/// Snapshot attempts before a sync is given up until the next event
+const SYNC_ATTEMPTS: u32 = 5;
+
+/// Price level of the book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderBookLevel {
+  pub price: f64,
+  pub qty: f64,
+}
+
+/// Top levels of the book, best prices first
+#[derive(Debug, Clone)]
+pub struct OrderBookDepth {
+  pub symbol: String,
+  pub last_update_id: u64,
+  pub bids: Vec<OrderBookLevel>,
+  pub asks: Vec<OrderBookLevel>,
+}
+
+/// Change notifications of `LocalOrderBook`
+#[derive(Debug, Clone)]
+pub enum OrderBookUpdate {
+  /// The book was loaded from a snapshot and can be read
+  Synced { symbol: String, last_update_id: u64 },
+  /// Levels were changed by a diff depth event
+  Updated {
+    symbol: String,
+    last_update_id: u64,
+    best_bid: Option<OrderBookLevel>,
+    best_ask: Option<OrderBookLevel>,
+  },
+  /// Update ids are not continuous, the book is loaded from a new snapshot
+  GapDetected {
+    symbol: String,
+    expected_first_update_id: u64,
+    first_update_id: u64,
+  },
+  /// The snapshot could not be loaded, the next event of the symbol tries again
+  SyncFailed { symbol: String, reason: String },
+}
+
+/// Price as an ordered map key
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Price {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.total_cmp(&other.0)
+  }
+}
+
+/// Book of one symbol
+#[derive(Default)]
+struct SymbolBook {
+  bids: BTreeMap<Price, f64>,
+  asks: BTreeMap<Price, f64>,
+  /// Update id the book is at, `None` until it is loaded from a snapshot
+  last_update_id: Option<u64>,
+  /// Events received while the snapshot is loading
+  buffer: Vec<DepthOrderBookEvent>,
+  /// Id of the sync loading the snapshot, if any
+  sync_id: Option<u64>,
+}
+
+/// Result of applying a diff depth event
+enum Applied {
+  Updated,
+  /// Event is older than the book
+  Skipped,
+  Gap {
+    expected_first_update_id: u64,
+  },
+}
+
+impl SymbolBook {
+  fn reset(&mut self) {
+    self.bids.clear();
+    self.asks.clear();
+    self.last_update_id = None;
+  }
+
+  fn load_snapshot(&mut self, snapshot: &OrderBookResponse) {
+    self.bids = snapshot
+      .bids
+      .iter()
+      .map(|level| (Price(level.price), level.qty))
+      .collect();
+    self.asks = snapshot
+      .asks
+      .iter()
+      .map(|level| (Price(level.price), level.qty))
+      .collect();
+    self.last_update_id = Some(snapshot.last_update_id);
+  }
+
+  /// Apply the event by the Binance rules: events with `u` <= the book update id are old,
+  /// the next event should have `U` <= update id + 1
+  fn apply(&mut self, event: &DepthOrderBookEvent) -> Applied {
+    let Some(last_update_id) = self.last_update_id else {
+      return Applied::Skipped;
+    };
+    if event.final_update_id <= last_update_id {
+      return Applied::Skipped;
+    }
+    if event.first_update_id > last_update_id + 1 {
+      return Applied::Gap {
+        expected_first_update_id: last_update_id + 1,
+      };
+    }
+
+    for bid in event.bids.iter() {
+      Self::set_level(&mut self.bids, bid.price, bid.qty);
+    }
+    for ask in event.asks.iter() {
+      Self::set_level(&mut self.asks, ask.price, ask.qty);
+    }
+    self.last_update_id = Some(event.final_update_id);
+    Applied::Updated
+  }
+
+  /// Zero quantity removes the level
+  fn set_level(levels: &mut BTreeMap<Price, f64>, price: f64, qty: f64) {
+    if qty == 0.0 {
+      levels.remove(&Price(price));
+    } else {
+      levels.insert(Price(price), qty);
+    }
+  }
+
+  fn best_bid(&self) -> Option<OrderBookLevel> {
+    self
+      .bids
+      .iter()
+      .next_back()
+      .map(|(price, qty)| OrderBookLevel {
+        price: price.0,
+        qty: *qty,
+      })
+  }
+
+  fn best_ask(&self) -> Option<OrderBookLevel> {
+    self.asks.iter().next().map(|(price, qty)| OrderBookLevel {
+      price: price.0,
+      qty: *qty,
+    })
+  }
+}
+
+/// Local order books kept in sync from REST snapshots and `<symbol>@depth` streams.
+///
+/// Follows <https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#how-to-manage-a-local-order-book-correctly>.
+/// Feed it with events of the stream, e.g. with `spawn` for a broadcast stream or
+/// `handle_event` from an event stream. Books of several symbols can share one stream,
+/// snapshots are loaded in their own tasks so the other books keep up meanwhile.
+pub struct LocalOrderBook {
+  market: SpotMarketV3Manager,
+  /// Depth of the snapshots, up to 5000
+  snapshot_limit: u16,
+  books: Mutex<HashMap<String, SymbolBook>>,
+  updates_tx: broadcast::Sender<OrderBookUpdate>,
+  /// Id of the last started sync
+  last_sync_id: AtomicU64,
+}
+
+impl LocalOrderBook {
+  pub fn new(market: SpotMarketV3Manager) -> Self {
+    let (updates_tx, _) = broadcast::channel(1024);
+
+    Self {
+      market,
+      snapshot_limit: 5000,
+      books: Mutex::new(HashMap::new()),
+      updates_tx,
+      last_sync_id: AtomicU64::new(0),
+    }
+  }
+
+  pub fn set_snapshot_limit(mut self, snapshot_limit: u16) -> Self {
+    self.snapshot_limit = snapshot_limit;
+    self
+  }
+
+  /// Receiver of book changes from now on
+  pub fn updates(&self) -> broadcast::Receiver<OrderBookUpdate> {
+    self.updates_tx.subscribe()
+  }
+
+  /// Start keeping the book of the symbol and subscribe the stream to its diff depth
+  pub async fn track<S: Into<String>>(
+    &self,
+    web_socket: &WebSocketSpotStream,
+    symbol: S,
+    speed: DepthUpdateSpeed,
+  ) -> Result<()> {
+    let symbol = symbol.into().to_uppercase();
+    self.books.lock().await.entry(symbol.clone()).or_default();
+
+    web_socket
+      .subscribe(vec![StreamName::diff_depth(symbol, speed)])
+      .await
+  }
+
+  /// Stop keeping the book of the symbol and unsubscribe the stream from its diff depth
+  pub async fn untrack<S: Into<String>>(
+    &self,
+    web_socket: &WebSocketSpotStream,
+    symbol: S,
+    speed: DepthUpdateSpeed,
+  ) -> Result<()> {
+    let symbol = symbol.into().to_uppercase();
+    self.books.lock().await.remove(&symbol);
+
+    web_socket
+      .unsubscribe(vec![StreamName::diff_depth(symbol, speed)])
+      .await
+  }
+
+  /// Feed events of a broadcast stream until it ends.
+  /// Books are resynced if the receiver lags behind and skips events
+  pub fn spawn(
+    self: &Arc<Self>,
+    mut events: broadcast::Receiver<WebsocketSpotEvent>,
+  ) -> JoinHandle<()> {
+    let order_book = self.clone();
+
+    tokio::spawn(async move {
+      loop {
+        match events.recv().await {
+          Ok(event) => order_book.handle_event(&event).await,
+          Err(RecvError::Lagged(_)) => order_book.resync_all().await,
+          Err(RecvError::Closed) => return,
+        }
+      }
+    })
+  }
+
+  /// Apply a diff depth event of a tracked symbol, other events are ignored.
+  ///
+  /// The first event of a symbol starts loading its snapshot in a task, events are
+  /// buffered until it is loaded. A lost connection drops all books
+  pub async fn handle_event(self: &Arc<Self>, event: &WebsocketSpotEvent) {
+    let event = match event {
+      WebsocketSpotEvent::DepthOrderBook(event) => event,
+      WebsocketSpotEvent::ConnectionState(ConnectionState::Disconnected { .. }) => {
+        self.resync_all().await;
+        return;
+      }
+      _ => return,
+    };
+
+    let mut books = self.books.lock().await;
+    let Some(book) = books.get_mut(&event.symbol) else {
+      return;
+    };
+
+    if book.last_update_id.is_none() {
+      book.buffer.push(event.clone());
+      if book.sync_id.is_none() {
+        self.start_sync(book, &event.symbol);
+      }
+      return;
+    }
+
+    match book.apply(event) {
+      Applied::Updated => {
+        let update = OrderBookUpdate::Updated {
+          symbol: event.symbol.clone(),
+          last_update_id: event.final_update_id,
+          best_bid: book.best_bid(),
+          best_ask: book.best_ask(),
+        };
+        let _ = self.updates_tx.send(update);
+      }
+      Applied::Skipped => {}
+      Applied::Gap {
+        expected_first_update_id,
+      } => {
+        book.reset();
+        book.buffer.push(event.clone());
+        let _ = self.updates_tx.send(OrderBookUpdate::GapDetected {
+          symbol: event.symbol.clone(),
+          expected_first_update_id,
+          first_update_id: event.first_update_id,
+        });
+        self.start_sync(book, &event.symbol);
+      }
+    }
+  }
+
+  /// Drop all books, they are loaded again from snapshots with the next events.
+  /// Snapshots being loaded are discarded
+  pub async fn resync_all(&self) {
+    let mut books = self.books.lock().await;
+    for book in books.values_mut() {
+      book.reset();
+      book.buffer.clear();
+      book.sync_id = None;
+    }
+  }
+
+  /// Load the snapshot of the book in a task, a failure is reported as `SyncFailed`
+  fn start_sync(self: &Arc<Self>, book: &mut SymbolBook, symbol: &str) {
+    let sync_id = self.last_sync_id.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+    book.sync_id = Some(sync_id);
+
+    let order_book = self.clone();
+    let symbol = symbol.to_string();
+    tokio::spawn(async move {
+      if let Err(e) = order_book.synchronize(&symbol, sync_id).await {
+        let _ = order_book.updates_tx.send(OrderBookUpdate::SyncFailed {
+          symbol,
+          reason: e.to_string(),
+        });
+      }
+    });
+  }
+
+  /// Load the snapshot and apply buffered events, until the events continue the snapshot.
+  /// Stops once the sync is superseded, e.g. by `resync_all`
+  async fn synchronize(&self, symbol: &str, sync_id: u64) -> Result<()> {
+    for _ in 0..SYNC_ATTEMPTS {
+      let snapshot = match self
+        .market
+        .fetch_depth_with_limit(symbol, self.snapshot_limit)
+        .await
+      {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+          self.stop_syncing(symbol, sync_id).await;
+          return Err(e);
+        }
+      };
+
+      let mut books = self.books.lock().await;
+      let Some(book) = books
+        .get_mut(symbol)
+        .filter(|book| book.sync_id == Some(sync_id))
+      else {
+        // Untracked or resynced meanwhile
+        return Ok(());
+      };
+
+      // The snapshot should not be older than the first buffered event
+      if book
+        .buffer
+        .first()
+        .is_some_and(|event| snapshot.last_update_id < event.first_update_id)
+      {
+        continue;
+      }
+
+      book.load_snapshot(&snapshot);
+      let buffer = std::mem::take(&mut book.buffer);
+      let gap_at = buffer
+        .iter()
+        .position(|event| matches!(book.apply(event), Applied::Gap { .. }));
+      if let Some(gap_at) = gap_at {
+        // Events from the gap on need a newer snapshot
+        book.reset();
+        book.buffer = buffer[gap_at..].to_vec();
+        continue;
+      }
+
+      book.sync_id = None;
+      let _ = self.updates_tx.send(OrderBookUpdate::Synced {
+        symbol: symbol.to_string(),
+        last_update_id: book.last_update_id.unwrap_or(snapshot.last_update_id),
+      });
+      return Ok(());
+    }
+
+    self.stop_syncing(symbol, sync_id).await;
+    bail!(
+      "Order book of {} is not in sync after {} snapshots",
+      symbol,
+      SYNC_ATTEMPTS
+    )
+  }
+
+  /// Give up the sync, the next event starts a new one
+  async fn stop_syncing(&self, symbol: &str, sync_id: u64) {
+    if let Some(book) = self.books.lock().await.get_mut(symbol)
+      && book.sync_id == Some(sync_id)
+    {
+      book.reset();
+      book.buffer.clear();
+      book.sync_id = None;
+    }
+  }
+
+  /// Whether the book of the symbol is loaded and can be read
+  pub async fn is_synced(&self, symbol: &str) -> bool {
+    self
+      .books
+      .lock()
+      .await
+      .get(&symbol.to_uppercase())
+      .is_some_and(|book| book.last_update_id.is_some())
+  }
+
+  pub async fn best_bid(&self, symbol: &str) -> Option<OrderBookLevel> {
+    self
+      .books
+      .lock()
+      .await
+      .get(&symbol.to_uppercase())?
+      .best_bid()
+  }
+
+  pub async fn best_ask(&self, symbol: &str) -> Option<OrderBookLevel> {
+    self
+      .books
+      .lock()
+      .await
+      .get(&symbol.to_uppercase())?
+      .best_ask()
+  }
+
+  /// Up to `levels` best bids and asks, `None` if the book is not synced
+  pub async fn depth(&self, symbol: &str, levels: usize) -> Option<OrderBookDepth> {
+    let symbol = symbol.to_uppercase();
+    let books = self.books.lock().await;
+    let book = books.get(&symbol)?;
+
+    Some(OrderBookDepth {
+      symbol: symbol.clone(),
+      last_update_id: book.last_update_id?,
+      bids: book
+        .bids
+        .iter()
+        .rev()
+        .take(levels)
+        .map(|(price, qty)| OrderBookLevel {
+          price: price.0,
+          qty: *qty,
+        })
+        .collect(),
+      asks: book
+        .asks
+        .iter()
+        .take(levels)
+        .map(|(price, qty)| OrderBookLevel {
+          price: price.0,
+          qty: *qty,
+        })
+        .collect(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::Binance;
+  use crate::config::Config;
+  use crate::websocket_stream::spot::events::{Asks, Bids};
+  use std::time::Duration;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+  use tokio::time::timeout;
+
+  const SNAPSHOT: &str = r#"{"lastUpdateId":100,"bids":[["10.0","1.0"]],"asks":[["11.0","1.0"]]}"#;
+
+  /// REST server answering depth requests of ETHUSDT, those of other symbols never
+  async fn depth_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+      while let Ok((mut socket, _)) = listener.accept().await {
+        tokio::spawn(async move {
+          let mut request = vec![];
+          let mut buffer = [0u8; 1024];
+          while !request.ends_with(b"\r\n\r\n") {
+            match socket.read(&mut buffer).await {
+              Ok(0) | Err(_) => return,
+              Ok(read) => request.extend_from_slice(&buffer[..read]),
+            }
+          }
+          if !String::from_utf8_lossy(&request).contains("symbol=ETHUSDT") {
+            std::future::pending::<()>().await;
+          }
+          let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            SNAPSHOT.len(),
+            SNAPSHOT
+          );
+          let _ = socket.write_all(response.as_bytes()).await;
+        });
+      }
+    });
+    host
+  }
+
+  fn depth_event(symbol: &str, first_update_id: u64, final_update_id: u64) -> WebsocketSpotEvent {
+    WebsocketSpotEvent::DepthOrderBook(DepthOrderBookEvent {
+      event_type: "depthUpdate".into(),
+      event_time: 0,
+      symbol: symbol.into(),
+      first_update_id,
+      final_update_id,
+      previous_final_update_id: None,
+      bids: vec![Bids::new(10.0, 2.0)],
+      asks: vec![Asks {
+        price: 11.0,
+        qty: 0.0,
+      }],
+    })
+  }
+
+  #[tokio::test]
+  async fn syncs_books_without_waiting_for_other_snapshots() {
+    let config = Config::default().set_rest_api_endpoint(depth_server().await);
+    let order_book = Arc::new(LocalOrderBook::new(SpotMarketV3Manager::new_with_config(
+      None, None, &config,
+    )));
+    for symbol in ["BTCUSDT", "ETHUSDT"] {
+      order_book
+        .books
+        .lock()
+        .await
+        .insert(symbol.into(), SymbolBook::default());
+    }
+    let mut updates = order_book.updates();
+
+    // The snapshot of BTCUSDT never arrives, events of ETHUSDT are applied meanwhile
+    for event in [
+      depth_event("BTCUSDT", 1, 2),
+      depth_event("ETHUSDT", 99, 101),
+      depth_event("BTCUSDT", 3, 4),
+    ] {
+      timeout(Duration::from_secs(1), order_book.handle_event(&event))
+        .await
+        .unwrap();
+    }
+
+    let update = timeout(Duration::from_secs(5), updates.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(matches!(
+      update,
+      OrderBookUpdate::Synced { symbol, last_update_id: 101 } if symbol == "ETHUSDT"
+    ));
+    order_book
+      .handle_event(&depth_event("ETHUSDT", 102, 102))
+      .await;
+    let update = updates.recv().await.unwrap();
+    assert!(matches!(
+      update,
+      OrderBookUpdate::Updated { symbol, last_update_id: 102, best_ask: None, .. } if symbol == "ETHUSDT"
+    ));
+    assert_eq!(
+      order_book.best_bid("ethusdt").await.map(|bid| bid.qty),
+      Some(2.0)
+    );
+
+    assert!(!order_book.is_synced("BTCUSDT").await);
+    assert_eq!(order_book.books.lock().await["BTCUSDT"].buffer.len(), 2);
+  }
+
+  #[tokio::test]
+  async fn discards_snapshots_of_dropped_books() {
+    let config = Config::default().set_rest_api_endpoint(depth_server().await);
+    let order_book = Arc::new(LocalOrderBook::new(SpotMarketV3Manager::new_with_config(
+      None, None, &config,
+    )));
+    order_book
+      .books
+      .lock()
+      .await
+      .insert("BTCUSDT".into(), SymbolBook::default());
+
+    order_book.handle_event(&depth_event("BTCUSDT", 1, 2)).await;
+    order_book.resync_all().await;
+
+    let books = order_book.books.lock().await;
+    assert!(books["BTCUSDT"].sync_id.is_none());
+    assert!(books["BTCUSDT"].buffer.is_empty());
+  }
+}
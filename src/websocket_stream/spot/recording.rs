@@ -0,0 +1,465 @@
+use crate::websocket_stream::spot::dispatch::{self, IncomingMessage};
+use crate::websocket_stream::spot::enums::TimeUnit;
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use crate::websocket_stream::spot::rotation::{self, Rotation};
+use crate::websocket_stream::spot::sbe;
+use anyhow::{bail, Result};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+
+/// First bytes of a recording file
+const MAGIC: &[u8; 8] = b"BNWSREC2";
+/// First bytes of a recording of text frames only, without flags
+const MAGIC_V1: &[u8; 8] = b"BNWSREC1";
+
+/// Flags of a record
+const FLAG_BINARY: u8 = 1;
+const FLAG_ROTATING: u8 = 2;
+const FLAG_STANDBY: u8 = 4;
+
+/// Connection a frame was received on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameSource {
+  /// The connection while it isn't replaced
+  Primary,
+  /// The connection while the old and the replacement connections overlap
+  PrimaryRotating,
+  /// The replacement connection before it takes over
+  Standby,
+}
+
+impl FrameSource {
+  fn flags(&self) -> u8 {
+    match self {
+      FrameSource::Primary => 0,
+      FrameSource::PrimaryRotating => FLAG_ROTATING,
+      FrameSource::Standby => FLAG_ROTATING | FLAG_STANDBY,
+    }
+  }
+
+  fn from_flags(flags: u8) -> Self {
+    if flags & FLAG_STANDBY != 0 {
+      FrameSource::Standby
+    } else if flags & FLAG_ROTATING != 0 {
+      FrameSource::PrimaryRotating
+    } else {
+      FrameSource::Primary
+    }
+  }
+}
+
+/// Payload of a frame, JSON text or SBE binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedPayload {
+  Text(String),
+  Binary(Vec<u8>),
+}
+
+/// Raw frame as it was received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+  /// Receive time in microseconds since the UNIX epoch
+  pub received_at_us: u64,
+  pub source: FrameSource,
+  pub payload: RecordedPayload,
+}
+
+/// Writes every frame of the connections of a `WebSocketSpotStream` to a file,
+/// set it with `WebSocketSpotConfig::set_recorder`.
+///
+/// Records are appended as the receive time (u64), the flags (u8), the payload
+/// length (u32), all little endian, and the payload. Frames received while rotating
+/// are flagged with their connection, so a replay drops the events both connections
+/// delivered like the stream did
+#[derive(Clone)]
+pub struct WebsocketRecorder {
+  path: PathBuf,
+  writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl fmt::Debug for WebsocketRecorder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("WebsocketRecorder")
+      .field("path", &self.path)
+      .finish()
+  }
+}
+
+impl WebsocketRecorder {
+  /// Start a new recording, an existing file is replaced
+  pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let mut file = File::create(path.as_ref())?;
+    file.write_all(MAGIC)?;
+    Ok(Self::with_file(path.as_ref(), file))
+  }
+
+  /// Continue the recording of an existing file, or start a new one
+  pub fn append<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let mut file = OpenOptions::new()
+      .read(true)
+      .append(true)
+      .create(true)
+      .open(path.as_ref())?;
+
+    if file.metadata()?.len() == 0 {
+      file.write_all(MAGIC)?;
+    } else {
+      let mut magic = [0u8; 8];
+      file.read_exact(&mut magic)?;
+      match &magic {
+        MAGIC => {}
+        MAGIC_V1 => bail!(
+          "{} is a recording of text frames only, start a new one",
+          path.as_ref().display()
+        ),
+        _ => bail!("{} is not a websocket recording", path.as_ref().display()),
+      }
+    }
+    Ok(Self::with_file(path.as_ref(), file))
+  }
+
+  fn with_file(path: &Path, file: File) -> Self {
+    Self {
+      path: path.to_path_buf(),
+      writer: Arc::new(Mutex::new(BufWriter::new(file))),
+    }
+  }
+
+  /// Append a text frame received at the given time
+  pub fn record_text(
+    &self,
+    received_at: SystemTime,
+    source: FrameSource,
+    payload: &str,
+  ) -> Result<()> {
+    self.record(received_at, source.flags(), payload.as_bytes())
+  }
+
+  /// Append a binary frame received at the given time
+  pub fn record_binary(
+    &self,
+    received_at: SystemTime,
+    source: FrameSource,
+    payload: &[u8],
+  ) -> Result<()> {
+    self.record(received_at, source.flags() | FLAG_BINARY, payload)
+  }
+
+  fn record(&self, received_at: SystemTime, flags: u8, payload: &[u8]) -> Result<()> {
+    let received_at_us = received_at
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_micros() as u64;
+    let Ok(length) = u32::try_from(payload.len()) else {
+      bail!("Frame of {} bytes is too large to record", payload.len());
+    };
+
+    let mut writer = self.writer.lock().unwrap();
+    writer.write_all(&received_at_us.to_le_bytes())?;
+    writer.write_all(&[flags])?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(payload)?;
+    Ok(())
+  }
+
+  /// Write buffered frames to the file, also done when the last clone is dropped
+  pub fn flush(&self) -> Result<()> {
+    self.writer.lock().unwrap().flush()?;
+    Ok(())
+  }
+}
+
+/// Frames of a recording file in the order they were received
+pub struct WebsocketRecordReader {
+  reader: BufReader<File>,
+  /// Whether the records have flags, recordings of text frames only have none
+  flagged: bool,
+}
+
+impl WebsocketRecordReader {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let mut reader = BufReader::new(File::open(path.as_ref())?);
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    let flagged = match &magic {
+      MAGIC => true,
+      MAGIC_V1 => false,
+      _ => bail!("{} is not a websocket recording", path.as_ref().display()),
+    };
+    Ok(Self { reader, flagged })
+  }
+
+  /// Next frame, `None` at the end of the file
+  pub fn next_frame(&mut self) -> Result<Option<RecordedFrame>> {
+    let mut received_at_us = [0u8; 8];
+    match self.reader.read_exact(&mut received_at_us) {
+      Ok(()) => {}
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+      Err(e) => return Err(e.into()),
+    }
+
+    let mut flags = [0u8; 1];
+    if self.flagged {
+      self.reader.read_exact(&mut flags)?;
+    }
+    let mut length = [0u8; 4];
+    self.reader.read_exact(&mut length)?;
+    let mut payload = vec![0u8; u32::from_le_bytes(length) as usize];
+    self.reader.read_exact(&mut payload)?;
+
+    Ok(Some(RecordedFrame {
+      received_at_us: u64::from_le_bytes(received_at_us),
+      source: FrameSource::from_flags(flags[0]),
+      payload: if flags[0] & FLAG_BINARY != 0 {
+        RecordedPayload::Binary(payload)
+      } else {
+        RecordedPayload::Text(String::from_utf8(payload)?)
+      },
+    }))
+  }
+}
+
+impl Iterator for WebsocketRecordReader {
+  type Item = Result<RecordedFrame>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.next_frame().transpose()
+  }
+}
+
+/// Pace of `WebsocketReplayer`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+  /// Deliver the events without waiting
+  AsFastAsPossible,
+  /// Keep the time between frames as it was recorded
+  Recorded,
+  /// Recorded pace multiplied by the factor, e.g. 2.0 for twice as fast
+  Scaled(f64),
+}
+
+/// Feeds the frames of a recording through the parsing of the connection,
+/// producing the same events the handler got while recording.
+/// Events both connections delivered while rotating are dropped like the stream did
+pub struct WebsocketReplayer {
+  path: PathBuf,
+  speed: ReplaySpeed,
+  time_unit: Option<TimeUnit>,
+}
+
+impl WebsocketReplayer {
+  pub fn new<P: AsRef<Path>>(path: P) -> Self {
+    Self {
+      path: path.as_ref().to_path_buf(),
+      speed: ReplaySpeed::AsFastAsPossible,
+      time_unit: None,
+    }
+  }
+
+  pub fn set_speed(mut self, speed: ReplaySpeed) -> Self {
+    self.speed = speed;
+    self
+  }
+
+  /// Unit of the SBE timestamps, see `WebSocketSpotConfig::set_time_unit`
+  pub fn set_time_unit<T: Into<Option<TimeUnit>>>(mut self, time_unit: T) -> Self {
+    self.time_unit = time_unit.into();
+    self
+  }
+
+  /// Call the handler with every event of the recording, returns the number of events
+  pub async fn replay<Callback>(&self, mut handler: Callback) -> Result<u64>
+  where
+    Callback: FnMut(WebsocketSpotEvent) -> Result<()>,
+  {
+    let started_at = Instant::now();
+    let mut first_received_at_us = None;
+    // Deduplication while both connections were open
+    let mut rotation: Option<Rotation> = None;
+    let mut events = 0;
+
+    for frame in WebsocketRecordReader::open(&self.path)? {
+      let frame = frame?;
+      let first = *first_received_at_us.get_or_insert(frame.received_at_us);
+      let elapsed = Duration::from_micros(frame.received_at_us.saturating_sub(first));
+
+      match self.speed {
+        ReplaySpeed::AsFastAsPossible => {}
+        ReplaySpeed::Recorded => tokio::time::sleep_until(started_at + elapsed).await,
+        ReplaySpeed::Scaled(factor) if factor > 0.0 => {
+          tokio::time::sleep_until(started_at + elapsed.div_f64(factor)).await
+        }
+        ReplaySpeed::Scaled(_) => {}
+      }
+
+      match frame.source {
+        FrameSource::Primary => rotation = None,
+        FrameSource::PrimaryRotating | FrameSource::Standby => {
+          rotation.get_or_insert_with(Rotation::new);
+        }
+      }
+      let from_standby = frame.source == FrameSource::Standby;
+
+      let frame_events = match &frame.payload {
+        RecordedPayload::Text(payload) => text_frame_events(payload, None),
+        RecordedPayload::Binary(payload) => match sbe::decode(payload, self.time_unit.as_ref()) {
+          Ok(events) => events.into_iter().map(|event| (None, event)).collect(),
+          // The connection skips frames it can't decode too
+          Err(_) => vec![],
+        },
+      };
+      for (stream, event) in frame_events {
+        if let Some(rotation) = &mut rotation
+          && let Some(key) = rotation::event_key(stream.as_deref(), &event)
+          && rotation.is_duplicate(key, from_standby)
+        {
+          continue;
+        }
+        handler(event)?;
+        events += 1;
+      }
+    }
+    Ok(events)
+  }
+}
+
+/// Events the connection emits for a text frame with the stream name of combined payloads,
+/// responses to requests are not events
+fn text_frame_events(msg: &str, stream: Option<&str>) -> Vec<(Option<String>, WebsocketSpotEvent)> {
+  match dispatch::parse_message(stream, msg) {
+    IncomingMessage::Combined { stream, data } => text_frame_events(data.get(), stream.as_deref()),
+    IncomingMessage::Response { .. } => vec![],
+    IncomingMessage::Event(event) => vec![(stream.map(str::to_string), *event)],
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::websocket_stream::spot::sbe::tests::trades_frame;
+
+  /// Recording file removed when dropped
+  struct TempRecording(PathBuf);
+
+  impl TempRecording {
+    fn new(name: &str) -> Self {
+      Self(std::env::temp_dir().join(format!("{}-{}.rec", name, std::process::id())))
+    }
+  }
+
+  impl Drop for TempRecording {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_file(&self.0);
+    }
+  }
+
+  fn trade_json(trade_id: u64) -> String {
+    format!(
+      r#"{{"e":"trade","E":1700000000123,"s":"BTCUSDT","t":{},"p":"65000.12","q":"1.5","T":1700000000120,"m":true,"M":true}}"#,
+      trade_id
+    )
+  }
+
+  async fn replayed_trade_ids(path: &Path) -> Vec<u64> {
+    let mut trade_ids = vec![];
+    WebsocketReplayer::new(path)
+      .replay(|event| {
+        if let WebsocketSpotEvent::Trade(trade) = event {
+          trade_ids.push(trade.trade_id);
+        }
+        Ok(())
+      })
+      .await
+      .unwrap();
+    trade_ids
+  }
+
+  #[test]
+  fn reads_text_and_binary_frames() {
+    let recording = TempRecording::new("reads_text_and_binary_frames");
+    let recorder = WebsocketRecorder::create(&recording.0).unwrap();
+    let received_at = UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_001);
+    recorder
+      .record_text(received_at, FrameSource::Primary, &trade_json(9))
+      .unwrap();
+    recorder
+      .record_binary(received_at, FrameSource::Standby, &trades_frame())
+      .unwrap();
+    recorder.flush().unwrap();
+
+    let frames = WebsocketRecordReader::open(&recording.0)
+      .unwrap()
+      .collect::<Result<Vec<_>>>()
+      .unwrap();
+    assert_eq!(
+      frames,
+      vec![
+        RecordedFrame {
+          received_at_us: 1_700_000_000_000_001,
+          source: FrameSource::Primary,
+          payload: RecordedPayload::Text(trade_json(9)),
+        },
+        RecordedFrame {
+          received_at_us: 1_700_000_000_000_001,
+          source: FrameSource::Standby,
+          payload: RecordedPayload::Binary(trades_frame()),
+        },
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn replays_sbe_frames() {
+    let recording = TempRecording::new("replays_sbe_frames");
+    let recorder = WebsocketRecorder::create(&recording.0).unwrap();
+    recorder
+      .record_text(SystemTime::now(), FrameSource::Primary, &trade_json(9))
+      .unwrap();
+    recorder
+      .record_binary(SystemTime::now(), FrameSource::Primary, &trades_frame())
+      .unwrap();
+    recorder.flush().unwrap();
+
+    assert_eq!(replayed_trade_ids(&recording.0).await, vec![9, 10, 11]);
+  }
+
+  #[tokio::test]
+  async fn drops_events_of_both_connections_once_while_rotating() {
+    let recording = TempRecording::new("drops_events_of_both_connections_once_while_rotating");
+    let recorder = WebsocketRecorder::create(&recording.0).unwrap();
+    let frames = [
+      (
+        FrameSource::PrimaryRotating,
+        RecordedPayload::Text(trade_json(10)),
+      ),
+      (
+        FrameSource::Standby,
+        RecordedPayload::Binary(trades_frame()),
+      ),
+      (
+        FrameSource::PrimaryRotating,
+        RecordedPayload::Text(trade_json(11)),
+      ),
+      // Not rotating anymore, events are delivered as they come
+      (FrameSource::Primary, RecordedPayload::Text(trade_json(11))),
+    ];
+    for (source, payload) in frames {
+      match payload {
+        RecordedPayload::Text(payload) => recorder.record_text(SystemTime::now(), source, &payload),
+        RecordedPayload::Binary(payload) => {
+          recorder.record_binary(SystemTime::now(), source, &payload)
+        }
+      }
+      .unwrap();
+    }
+    recorder.flush().unwrap();
+
+    assert_eq!(replayed_trade_ids(&recording.0).await, vec![10, 11, 11]);
+  }
+}
@@ -0,0 +1,156 @@
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Events remembered to find duplicates while both connections are open
+const SEEN_EVENTS_CAPACITY: usize = 10_000;
+
+/// Replacement of a connection before Binance closes it at the 24h mark.
+///
+/// While the old and the new connection are both open, the same event can arrive twice,
+/// so events are deduplicated until shortly after the switch.
+pub(crate) struct Rotation {
+  pub(crate) started_at: Instant,
+  /// When the new connection took over
+  pub(crate) switched_at: Option<Instant>,
+  /// Whether an event was received from both connections, so the new one is caught up
+  pub(crate) overlapped: bool,
+  /// Keys of recent events and whether they came from the new connection
+  seen: HashMap<String, bool>,
+  seen_order: VecDeque<String>,
+}
+
+impl Rotation {
+  pub(crate) fn new() -> Self {
+    Self {
+      started_at: Instant::now(),
+      switched_at: None,
+      overlapped: false,
+      seen: HashMap::new(),
+      seen_order: VecDeque::new(),
+    }
+  }
+
+  /// Remember the event, returns true if it was received already
+  pub(crate) fn is_duplicate(&mut self, key: String, from_standby: bool) -> bool {
+    if let Some(seen_from_standby) = self.seen.get(&key) {
+      if *seen_from_standby != from_standby {
+        self.overlapped = true;
+      }
+      return true;
+    }
+
+    if self.seen_order.len() >= SEEN_EVENTS_CAPACITY
+      && let Some(oldest) = self.seen_order.pop_front()
+    {
+      self.seen.remove(&oldest);
+    }
+    self.seen.insert(key.clone(), from_standby);
+    self.seen_order.push_back(key);
+    false
+  }
+
+  /// Whether late events of the old connection can't arrive anymore
+  pub(crate) fn is_finished(&self, overlap: Duration) -> bool {
+    self
+      .switched_at
+      .is_some_and(|switched_at| switched_at.elapsed() >= overlap)
+  }
+}
+
+/// Identity of an event, the same on every connection.
+/// `stream` is the stream name of the combined payload, if known
+pub(crate) fn event_key(stream: Option<&str>, event: &WebsocketSpotEvent) -> Option<String> {
+  let (kind, symbol, id) = match event {
+    WebsocketSpotEvent::Trade(v) => ("trade", v.symbol.as_str(), v.trade_id.to_string()),
+    WebsocketSpotEvent::AggTrades(v) => (
+      "aggTrade",
+      v.symbol.as_str(),
+      v.aggregated_trade_id.to_string(),
+    ),
+    WebsocketSpotEvent::DepthOrderBook(v) => (
+      "depthUpdate",
+      v.symbol.as_str(),
+      v.final_update_id.to_string(),
+    ),
+    WebsocketSpotEvent::OrderBook(v) => ("depth", "", v.last_update_id.to_string()),
+    WebsocketSpotEvent::BookTicker(v) => ("bookTicker", v.symbol.as_str(), v.update_id.to_string()),
+    WebsocketSpotEvent::DayTicker(v) => ("24hrTicker", v.symbol.as_str(), v.event_time.to_string()),
+    WebsocketSpotEvent::WindowTicker(v) => (
+      v.event_type.as_str(),
+      v.symbol.as_str(),
+      v.event_time.to_string(),
+    ),
+    WebsocketSpotEvent::DayTickerAll(v) => (
+      "24hrTicker@arr",
+      "",
+      v.iter().map(|t| t.event_time).max()?.to_string(),
+    ),
+    WebsocketSpotEvent::WindowTickerAll(v) => (
+      "windowTicker@arr",
+      "",
+      v.iter().map(|t| t.event_time).max()?.to_string(),
+    ),
+    WebsocketSpotEvent::Kline(v) => (
+      "kline",
+      v.symbol.as_str(),
+      format!("{}|{}", v.kline.interval, v.event_time),
+    ),
+    WebsocketSpotEvent::AccountUpdate(v) => ("accountUpdate", "", v.event_time.to_string()),
+    WebsocketSpotEvent::OrderTrade(v) => (
+      "executionReport",
+      v.symbol.as_str(),
+      format!(
+        "{}|{}|{}|{}",
+        v.order_id, v.trade_id, v.order_status, v.event_time
+      ),
+    ),
+    WebsocketSpotEvent::OutboundAccountPosition(v) => (
+      "outboundAccountPosition",
+      "",
+      format!("{}|{}", v.last_account_update_time, v.event_time),
+    ),
+    WebsocketSpotEvent::SpotBalanceUpdate(v) => (
+      "balanceUpdate",
+      v.asset.as_str(),
+      format!("{}|{}", v.clear_time, v.event_time),
+    ),
+    WebsocketSpotEvent::ListStatus(v) => (
+      "listStatus",
+      v.symbol.as_str(),
+      format!("{}|{}|{}", v.order_list_id, v.list_status_type, v.event_time),
+    ),
+    WebsocketSpotEvent::ListenKeyExpired(v) => (
+      "listenKeyExpired",
+      v.listen_key.as_str(),
+      v.event_time.to_string(),
+    ),
+    WebsocketSpotEvent::MiniTicker(v) => (
+      "24hrMiniTicker",
+      v.symbol.as_str(),
+      v.event_time.to_string(),
+    ),
+    WebsocketSpotEvent::MiniTickerAll(v) => (
+      "24hrMiniTicker@arr",
+      "",
+      v.iter().map(|t| t.event_time).max()?.to_string(),
+    ),
+    WebsocketSpotEvent::AvgPrice(v) => ("avgPrice", v.symbol.as_str(), v.event_time.to_string()),
+    WebsocketSpotEvent::ContinuousKline(v) => (
+      "continuous_kline",
+      v.pair.as_str(),
+      format!("{}|{}|{}", v.contract_type, v.kline.interval, v.event_time),
+    ),
+    WebsocketSpotEvent::IndexKline(v) => (
+      "indexPriceKline",
+      v.pair.as_str(),
+      format!("{}|{}", v.kline.interval, v.event_time),
+    ),
+    WebsocketSpotEvent::ConnectionState(_) | WebsocketSpotEvent::Unknown(_) => return None,
+    #[allow(deprecated)]
+    WebsocketSpotEvent::BalanceUpdate(_) => return None,
+  };
+
+  Some(format!("{}|{}|{}", stream.unwrap_or(kind), symbol, id))
+}
@@ -0,0 +1,216 @@
+use crate::websocket_stream::spot::events::WebsocketSpotEvent;
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::{SharedWebSocketCallback, WebSocketSpotStream};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+
+/// Kind of the events a handler is registered for with `WebSocketSpotStream::route_events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+  Trade,
+  AggTrade,
+  Kline,
+  /// Diff depth updates
+  DepthUpdate,
+  BookTicker,
+  MiniTicker,
+  /// 24h rolling window ticker
+  DayTicker,
+  /// 1h, 4h and 1d rolling window tickers
+  WindowTicker,
+  AvgPrice,
+  /// executionReport of the user data stream
+  OrderTrade,
+  ListStatus,
+}
+
+impl EventKind {
+  /// Kind and symbol of the event, `None` for events without a symbol
+  pub fn of(event: &WebsocketSpotEvent) -> Option<(EventKind, &str)> {
+    match event {
+      WebsocketSpotEvent::Trade(v) => Some((EventKind::Trade, &v.symbol)),
+      WebsocketSpotEvent::AggTrades(v) => Some((EventKind::AggTrade, &v.symbol)),
+      WebsocketSpotEvent::Kline(v) => Some((EventKind::Kline, &v.symbol)),
+      WebsocketSpotEvent::DepthOrderBook(v) => Some((EventKind::DepthUpdate, &v.symbol)),
+      WebsocketSpotEvent::BookTicker(v) => Some((EventKind::BookTicker, &v.symbol)),
+      WebsocketSpotEvent::MiniTicker(v) => Some((EventKind::MiniTicker, &v.symbol)),
+      WebsocketSpotEvent::DayTicker(v) => Some((EventKind::DayTicker, &v.symbol)),
+      WebsocketSpotEvent::WindowTicker(v) => Some((EventKind::WindowTicker, &v.symbol)),
+      WebsocketSpotEvent::AvgPrice(v) => Some((EventKind::AvgPrice, &v.symbol)),
+      WebsocketSpotEvent::OrderTrade(v) => Some((EventKind::OrderTrade, &v.symbol)),
+      WebsocketSpotEvent::ListStatus(v) => Some((EventKind::ListStatus, &v.symbol)),
+      _ => None,
+    }
+  }
+}
+
+/// Events a route receives besides connection state changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RouteFilter {
+  Stream(StreamName),
+  Event { kind: EventKind, symbol: String },
+}
+
+impl RouteFilter {
+  fn matches(&self, stream: Option<&StreamName>, event: &WebsocketSpotEvent) -> bool {
+    match self {
+      RouteFilter::Stream(route_stream) => stream == Some(route_stream),
+      RouteFilter::Event { kind, symbol } => {
+        EventKind::of(event).is_some_and(|(event_kind, event_symbol)| {
+          event_kind == *kind && event_symbol.eq_ignore_ascii_case(symbol)
+        })
+      }
+    }
+  }
+}
+
+struct Route {
+  filter: RouteFilter,
+  handler: SharedWebSocketCallback,
+}
+
+#[derive(Default)]
+struct Routes {
+  last_id: u64,
+  routes: HashMap<u64, Route>,
+  /// Routes of each stream, it is unsubscribed once none is left
+  stream_routes: HashMap<StreamName, usize>,
+}
+
+/// Handlers of a `WebSocketSpotStream` created with `new_with_router`, by stream or event kind
+#[derive(Default)]
+pub(crate) struct EventRouter {
+  routes: Mutex<Routes>,
+  /// Held from the route count decision until the subscribe or unsubscribe is answered,
+  /// so that a stream dropped by its last route and routed again ends up subscribed
+  stream_changes: AsyncMutex<()>,
+}
+
+impl EventRouter {
+  /// Add a route for the stream, returns its id and whether it is the first one of the stream
+  pub(crate) fn add_stream_route(
+    &self,
+    stream: StreamName,
+    handler: SharedWebSocketCallback,
+  ) -> (u64, bool) {
+    let mut routes = self.routes.lock().unwrap();
+    let count = routes.stream_routes.entry(stream.clone()).or_default();
+    *count += 1;
+    let first = *count == 1;
+
+    (routes.add(RouteFilter::Stream(stream), handler), first)
+  }
+
+  pub(crate) fn add_event_route(
+    &self,
+    kind: EventKind,
+    symbol: String,
+    handler: SharedWebSocketCallback,
+  ) -> u64 {
+    let mut routes = self.routes.lock().unwrap();
+    routes.add(RouteFilter::Event { kind, symbol }, handler)
+  }
+
+  /// Remove a route, returns its stream if no other route is left for it
+  pub(crate) fn remove(&self, id: u64) -> Option<StreamName> {
+    let mut routes = self.routes.lock().unwrap();
+    let RouteFilter::Stream(stream) = routes.routes.remove(&id)?.filter else {
+      return None;
+    };
+
+    let count = routes.stream_routes.get_mut(&stream)?;
+    *count -= 1;
+    if *count > 0 {
+      return None;
+    }
+    routes.stream_routes.remove(&stream);
+    Some(stream)
+  }
+
+  /// Wait until no other route changes the subscribed streams
+  pub(crate) async fn lock_stream_changes(&self) -> AsyncMutexGuard<'_, ()> {
+    self.stream_changes.lock().await
+  }
+
+  /// Whether any route is registered for the stream
+  pub(crate) fn is_routed(&self, stream: &StreamName) -> bool {
+    self
+      .routes
+      .lock()
+      .unwrap()
+      .stream_routes
+      .contains_key(stream)
+  }
+
+  /// Call the handlers of the event. Connection state changes go to every handler.
+  /// A failing handler doesn't stop the others or the connection
+  pub(crate) async fn dispatch(&self, stream: Option<&StreamName>, event: WebsocketSpotEvent) {
+    let is_control = matches!(event, WebsocketSpotEvent::ConnectionState(_));
+
+    // Handlers are called without the lock, so they can register and drop routes
+    let handlers: Vec<SharedWebSocketCallback> = {
+      let routes = self.routes.lock().unwrap();
+      routes
+        .routes
+        .values()
+        .filter(|route| is_control || route.filter.matches(stream, &event))
+        .map(|route| route.handler.clone())
+        .collect()
+    };
+
+    for handler in handlers {
+      let mut handler = handler.lock().await;
+      if let Err(e) = (handler)(event.clone()) {
+        eprintln!("Route handler error: {:?}", e);
+      }
+    }
+  }
+}
+
+impl Routes {
+  fn add(&mut self, filter: RouteFilter, handler: SharedWebSocketCallback) -> u64 {
+    self.last_id += 1;
+    self.routes.insert(self.last_id, Route { filter, handler });
+    self.last_id
+  }
+}
+
+/// Registration of a route handler, dropping it removes the handler.
+/// The stream is unsubscribed once its last handler is dropped
+pub struct RouteHandle {
+  id: u64,
+  router: Arc<EventRouter>,
+  stream: Weak<WebSocketSpotStream>,
+}
+
+impl RouteHandle {
+  pub(crate) fn new(id: u64, router: Arc<EventRouter>, stream: Weak<WebSocketSpotStream>) -> Self {
+    Self { id, router, stream }
+  }
+}
+
+impl Drop for RouteHandle {
+  fn drop(&mut self) {
+    let Some(stream_name) = self.router.remove(self.id) else {
+      return;
+    };
+    let (Some(stream), Ok(runtime)) =
+      (self.stream.upgrade(), tokio::runtime::Handle::try_current())
+    else {
+      return;
+    };
+
+    let router = self.router.clone();
+    runtime.spawn(async move {
+      let _stream_changes = router.lock_stream_changes().await;
+      // A new route may have been added for the stream in the meantime
+      if router.is_routed(&stream_name) {
+        return;
+      }
+      if let Err(e) = stream.unsubscribe(vec![stream_name]).await {
+        eprintln!("Failed to unsubscribe a stream without routes: {:?}", e);
+      }
+    });
+  }
+}
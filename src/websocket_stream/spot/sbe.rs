@@ -0,0 +1,510 @@
+use crate::websocket_stream::spot::enums::TimeUnit;
+use crate::websocket_stream::spot::events::{
+  Asks, Bids, BookTickerEvent, DepthOrderBookEvent, OrderBook, TradeEvent, WebsocketSpotEvent,
+};
+use anyhow::{anyhow, bail, Result};
+
+/// Schema of the spot SBE market data streams, `spot_stream_1_0.xml`
+const SCHEMA_ID: u16 = 1;
+const TRADES_TEMPLATE_ID: u16 = 10000;
+const BEST_BID_ASK_TEMPLATE_ID: u16 = 10001;
+const DEPTH_SNAPSHOT_TEMPLATE_ID: u16 = 10002;
+const DEPTH_DIFF_TEMPLATE_ID: u16 = 10003;
+
+/// Decode a binary frame of the SBE market data streams.
+///
+/// Trades of a frame become one `Trade` event each, best bid/ask a `BookTicker`,
+/// depth snapshots an `OrderBook` and diff depth a `DepthOrderBook` event.
+/// SBE timestamps are in microseconds, they are converted to milliseconds
+/// unless `time_unit` is `Microsecond`
+pub fn decode(frame: &[u8], time_unit: Option<&TimeUnit>) -> Result<Vec<WebsocketSpotEvent>> {
+  let mut reader = Reader::new(frame);
+  let block_length = reader.u16()? as usize;
+  let template_id = reader.u16()?;
+  let schema_id = reader.u16()?;
+  let _version = reader.u16()?;
+
+  if schema_id != SCHEMA_ID {
+    bail!("Unknown SBE schema id {}", schema_id);
+  }
+  let time = |micros: i64| -> u64 {
+    let micros = micros.max(0) as u64;
+    match time_unit {
+      Some(TimeUnit::Microsecond) => micros,
+      _ => micros / 1000,
+    }
+  };
+
+  let block_end = reader.pos + block_length;
+  match template_id {
+    TRADES_TEMPLATE_ID => {
+      let event_time = time(reader.i64()?);
+      let transact_time = time(reader.i64()?);
+      let price_exponent = reader.i8()?;
+      let qty_exponent = reader.i8()?;
+      reader.seek(block_end)?;
+
+      let trades = reader.group(GroupSize::U32, |entry| {
+        Ok((entry.i64()?, entry.i64()?, entry.i64()?, entry.u8()? == 1))
+      })?;
+      let symbol = reader.var_string8()?;
+
+      trades
+        .into_iter()
+        .map(|(id, price, qty, is_buyer_maker)| {
+          Ok(WebsocketSpotEvent::Trade(TradeEvent {
+            event_type: "trade".to_string(),
+            event_time,
+            symbol: symbol.clone(),
+            trade_id: id as u64,
+            price: decimal(price, price_exponent).parse()?,
+            qty: decimal(qty, qty_exponent).parse()?,
+            trade_order_time: transact_time,
+            is_buyer_maker,
+            m_ignore: true,
+          }))
+        })
+        .collect()
+    }
+    BEST_BID_ASK_TEMPLATE_ID => {
+      let _event_time = reader.i64()?;
+      let update_id = reader.i64()?;
+      let price_exponent = reader.i8()?;
+      let qty_exponent = reader.i8()?;
+      let bid_price = reader.i64()?;
+      let bid_qty = reader.i64()?;
+      let ask_price = reader.i64()?;
+      let ask_qty = reader.i64()?;
+      reader.seek(block_end)?;
+      let symbol = reader.var_string8()?;
+
+      Ok(vec![WebsocketSpotEvent::BookTicker(BookTickerEvent {
+        update_id: update_id as u64,
+        symbol,
+        best_bid: decimal(bid_price, price_exponent),
+        best_bid_qty: decimal(bid_qty, qty_exponent),
+        best_ask: decimal(ask_price, price_exponent),
+        best_ask_qty: decimal(ask_qty, qty_exponent),
+      })])
+    }
+    DEPTH_SNAPSHOT_TEMPLATE_ID => {
+      let _event_time = reader.i64()?;
+      let update_id = reader.i64()?;
+      let price_exponent = reader.i8()?;
+      let qty_exponent = reader.i8()?;
+      reader.seek(block_end)?;
+
+      let bids = reader.levels(price_exponent, qty_exponent)?;
+      let asks = reader.levels(price_exponent, qty_exponent)?;
+      let symbol = reader.var_string8()?;
+
+      Ok(vec![WebsocketSpotEvent::OrderBook(OrderBook {
+        last_update_id: update_id as u64,
+        symbol: Some(symbol),
+        bids: bids
+          .into_iter()
+          .map(|(price, qty)| Bids { price, qty })
+          .collect(),
+        asks: asks
+          .into_iter()
+          .map(|(price, qty)| Asks { price, qty })
+          .collect(),
+      })])
+    }
+    DEPTH_DIFF_TEMPLATE_ID => {
+      let event_time = time(reader.i64()?);
+      let first_update_id = reader.i64()?;
+      let final_update_id = reader.i64()?;
+      let price_exponent = reader.i8()?;
+      let qty_exponent = reader.i8()?;
+      reader.seek(block_end)?;
+
+      let bids = reader.levels(price_exponent, qty_exponent)?;
+      let asks = reader.levels(price_exponent, qty_exponent)?;
+      let symbol = reader.var_string8()?;
+
+      Ok(vec![WebsocketSpotEvent::DepthOrderBook(
+        DepthOrderBookEvent {
+          event_type: "depthUpdate".to_string(),
+          event_time,
+          symbol,
+          first_update_id: first_update_id as u64,
+          final_update_id: final_update_id as u64,
+          previous_final_update_id: None,
+          bids: bids
+            .into_iter()
+            .map(|(price, qty)| Bids { price, qty })
+            .collect(),
+          asks: asks
+            .into_iter()
+            .map(|(price, qty)| Asks { price, qty })
+            .collect(),
+        },
+      )])
+    }
+    _ => bail!("Unknown SBE template id {}", template_id),
+  }
+}
+
+/// Decimal string of `mantissa * 10^exponent`, without float rounding
+fn decimal(mantissa: i64, exponent: i8) -> String {
+  if exponent >= 0 {
+    return format!("{}{}", mantissa, "0".repeat(exponent as usize));
+  }
+
+  let scale = exponent.unsigned_abs() as usize;
+  let digits = format!("{:0>width$}", mantissa.unsigned_abs(), width = scale + 1);
+  let (integer, fraction) = digits.split_at(digits.len() - scale);
+  let sign = if mantissa < 0 { "-" } else { "" };
+  format!("{}{}.{}", sign, integer, fraction)
+}
+
+/// Width of `numInGroup` in a group header
+enum GroupSize {
+  /// groupSize16Encoding
+  U16,
+  /// groupSizeEncoding
+  U32,
+}
+
+/// Little endian reader over a frame
+struct Reader<'a> {
+  frame: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn new(frame: &'a [u8]) -> Self {
+    Self { frame, pos: 0 }
+  }
+
+  fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+    let bytes = self.frame.get(self.pos..self.pos + N).ok_or_else(|| {
+      anyhow!(
+        "SBE frame ends at {} of {} bytes",
+        self.frame.len(),
+        self.pos + N
+      )
+    })?;
+    self.pos += N;
+    Ok(bytes.try_into()?)
+  }
+
+  fn u8(&mut self) -> Result<u8> {
+    Ok(u8::from_le_bytes(self.take()?))
+  }
+
+  fn i8(&mut self) -> Result<i8> {
+    Ok(i8::from_le_bytes(self.take()?))
+  }
+
+  fn u16(&mut self) -> Result<u16> {
+    Ok(u16::from_le_bytes(self.take()?))
+  }
+
+  fn u32(&mut self) -> Result<u32> {
+    Ok(u32::from_le_bytes(self.take()?))
+  }
+
+  fn i64(&mut self) -> Result<i64> {
+    Ok(i64::from_le_bytes(self.take()?))
+  }
+
+  /// Skip the fields of a newer schema version
+  fn seek(&mut self, pos: usize) -> Result<()> {
+    if pos < self.pos || pos > self.frame.len() {
+      bail!("SBE block ends at {}, outside of the frame", pos);
+    }
+    self.pos = pos;
+    Ok(())
+  }
+
+  /// Entries of a repeating group, each read from the start of its block
+  fn group<T, F>(&mut self, size: GroupSize, mut entry: F) -> Result<Vec<T>>
+  where
+    F: FnMut(&mut Self) -> Result<T>,
+  {
+    let block_length = self.u16()? as usize;
+    let count = match size {
+      GroupSize::U16 => self.u16()? as usize,
+      GroupSize::U32 => self.u32()? as usize,
+    };
+
+    let mut entries = Vec::with_capacity(count.min(self.frame.len()));
+    for _ in 0..count {
+      let block_end = self.pos + block_length;
+      entries.push(entry(self)?);
+      self.seek(block_end)?;
+    }
+    Ok(entries)
+  }
+
+  /// Price and quantity levels of a depth group
+  fn levels(&mut self, price_exponent: i8, qty_exponent: i8) -> Result<Vec<(f64, f64)>> {
+    self.group(GroupSize::U16, |entry| {
+      let price = entry.i64()?;
+      let qty = entry.i64()?;
+      Ok((
+        decimal(price, price_exponent).parse()?,
+        decimal(qty, qty_exponent).parse()?,
+      ))
+    })
+  }
+
+  /// varString8, a length byte followed by UTF-8
+  fn var_string8(&mut self) -> Result<String> {
+    let length = self.u8()? as usize;
+    let bytes = self
+      .frame
+      .get(self.pos..self.pos + length)
+      .ok_or_else(|| anyhow!("SBE frame ends inside a string"))?;
+    self.pos += length;
+    Ok(String::from_utf8(bytes.to_vec())?)
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// Little endian frame builder
+  #[derive(Default)]
+  struct Frame(Vec<u8>);
+
+  impl Frame {
+    fn header(template_id: u16, block_length: u16) -> Self {
+      Frame::default()
+        .u16(block_length)
+        .u16(template_id)
+        .u16(SCHEMA_ID)
+        .u16(0)
+    }
+
+    fn u8(mut self, value: u8) -> Self {
+      self.0.push(value);
+      self
+    }
+
+    fn i8(self, value: i8) -> Self {
+      self.u8(value as u8)
+    }
+
+    fn u16(mut self, value: u16) -> Self {
+      self.0.extend_from_slice(&value.to_le_bytes());
+      self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+      self.0.extend_from_slice(&value.to_le_bytes());
+      self
+    }
+
+    fn i64(mut self, value: i64) -> Self {
+      self.0.extend_from_slice(&value.to_le_bytes());
+      self
+    }
+
+    fn string(mut self, value: &str) -> Self {
+      self.0.push(value.len() as u8);
+      self.0.extend_from_slice(value.as_bytes());
+      self
+    }
+
+    /// Depth group of 16 byte price and quantity entries
+    fn levels(self, levels: &[(i64, i64)]) -> Self {
+      let mut frame = self.u16(16).u16(levels.len() as u16);
+      for (price, qty) in levels {
+        frame = frame.i64(*price).i64(*qty);
+      }
+      frame
+    }
+  }
+
+  /// Trades 10 and 11 of BTCUSDT
+  pub(crate) fn trades_frame() -> Vec<u8> {
+    Frame::header(TRADES_TEMPLATE_ID, 18)
+      .i64(1_700_000_000_123_456)
+      .i64(1_700_000_000_120_000)
+      .i8(-2)
+      .i8(-4)
+      // groupSizeEncoding, u32 count
+      .u16(25)
+      .u32(2)
+      .i64(10)
+      .i64(6_500_012)
+      .i64(15_000)
+      .u8(1)
+      .i64(11)
+      .i64(6_500_100)
+      .i64(5)
+      .u8(0)
+      .string("BTCUSDT")
+      .0
+  }
+
+  #[test]
+  fn decodes_trades() {
+    let events = decode(&trades_frame(), None).unwrap();
+    assert_eq!(events.len(), 2);
+
+    let WebsocketSpotEvent::Trade(first) = &events[0] else {
+      panic!("expected a trade, got {:?}", events[0]);
+    };
+    assert_eq!(first.symbol, "BTCUSDT");
+    assert_eq!(first.trade_id, 10);
+    assert_eq!(first.price, 65000.12);
+    assert_eq!(first.qty, 1.5);
+    assert!(first.is_buyer_maker);
+    assert_eq!(first.event_time, 1_700_000_000_123);
+    assert_eq!(first.trade_order_time, 1_700_000_000_120);
+
+    let WebsocketSpotEvent::Trade(second) = &events[1] else {
+      panic!("expected a trade, got {:?}", events[1]);
+    };
+    assert_eq!(second.trade_id, 11);
+    assert_eq!(second.qty, 0.0005);
+    assert!(!second.is_buyer_maker);
+  }
+
+  #[test]
+  fn keeps_microseconds_when_requested() {
+    let events = decode(&trades_frame(), Some(&TimeUnit::Microsecond)).unwrap();
+    let WebsocketSpotEvent::Trade(trade) = &events[0] else {
+      panic!("expected a trade, got {:?}", events[0]);
+    };
+    assert_eq!(trade.event_time, 1_700_000_000_123_456);
+  }
+
+  #[test]
+  fn decodes_best_bid_ask() {
+    let frame = Frame::header(BEST_BID_ASK_TEMPLATE_ID, 50)
+      .i64(1_700_000_000_000_000)
+      .i64(42)
+      .i8(-2)
+      .i8(3)
+      .i64(6_499_999)
+      .i64(7)
+      .i64(6_500_001)
+      .i64(12)
+      .string("BTCUSDT")
+      .0;
+
+    let events = decode(&frame, None).unwrap();
+    let [WebsocketSpotEvent::BookTicker(ticker)] = events.as_slice() else {
+      panic!("expected one book ticker, got {:?}", events);
+    };
+    assert_eq!(ticker.update_id, 42);
+    assert_eq!(ticker.symbol, "BTCUSDT");
+    assert_eq!(ticker.best_bid, "64999.99");
+    assert_eq!(ticker.best_ask, "65000.01");
+    // Positive exponents scale up
+    assert_eq!(ticker.best_bid_qty, "7000");
+    assert_eq!(ticker.best_ask_qty, "12000");
+  }
+
+  #[test]
+  fn decodes_depth_snapshot() {
+    let frame = Frame::header(DEPTH_SNAPSHOT_TEMPLATE_ID, 18)
+      .i64(1_700_000_000_000_000)
+      .i64(1000)
+      .i8(-1)
+      .i8(-3)
+      // groupSize16Encoding, u16 count
+      .levels(&[(650_000, 1_250), (649_990, 500)])
+      .levels(&[(650_010, 2_000)])
+      .string("ETHUSDT")
+      .0;
+
+    let events = decode(&frame, None).unwrap();
+    let [WebsocketSpotEvent::OrderBook(book)] = events.as_slice() else {
+      panic!("expected one order book, got {:?}", events);
+    };
+    assert_eq!(book.last_update_id, 1000);
+    assert_eq!(book.symbol.as_deref(), Some("ETHUSDT"));
+    assert_eq!(book.bids.len(), 2);
+    assert_eq!((book.bids[0].price, book.bids[0].qty), (65000.0, 1.25));
+    assert_eq!((book.bids[1].price, book.bids[1].qty), (64999.0, 0.5));
+    assert_eq!(book.asks.len(), 1);
+    assert_eq!((book.asks[0].price, book.asks[0].qty), (65001.0, 2.0));
+  }
+
+  #[test]
+  fn decodes_depth_diff() {
+    let frame = Frame::header(DEPTH_DIFF_TEMPLATE_ID, 26)
+      .i64(1_700_000_000_555_000)
+      .i64(101)
+      .i64(105)
+      .i8(-2)
+      .i8(-2)
+      .levels(&[])
+      .levels(&[(12_345, 0), (12_346, 1)])
+      .string("ETHUSDT")
+      .0;
+
+    let events = decode(&frame, None).unwrap();
+    let [WebsocketSpotEvent::DepthOrderBook(depth)] = events.as_slice() else {
+      panic!("expected one depth update, got {:?}", events);
+    };
+    assert_eq!(depth.symbol, "ETHUSDT");
+    assert_eq!(depth.event_time, 1_700_000_000_555);
+    assert_eq!(depth.first_update_id, 101);
+    assert_eq!(depth.final_update_id, 105);
+    assert!(depth.bids.is_empty());
+    assert_eq!(depth.asks.len(), 2);
+    assert_eq!((depth.asks[0].price, depth.asks[0].qty), (123.45, 0.0));
+    assert_eq!((depth.asks[1].price, depth.asks[1].qty), (123.46, 0.01));
+  }
+
+  #[test]
+  fn skips_fields_of_longer_blocks() {
+    // A newer schema version appends a field to the root block and to the group entries
+    let frame = Frame::header(TRADES_TEMPLATE_ID, 26)
+      .i64(1_700_000_000_000_000)
+      .i64(1_700_000_000_000_000)
+      .i8(0)
+      .i8(0)
+      .i64(-1)
+      .u16(33)
+      .u32(1)
+      .i64(7)
+      .i64(3)
+      .i64(4)
+      .u8(1)
+      .i64(-1)
+      .string("BNBUSDT")
+      .0;
+
+    let events = decode(&frame, None).unwrap();
+    let [WebsocketSpotEvent::Trade(trade)] = events.as_slice() else {
+      panic!("expected one trade, got {:?}", events);
+    };
+    assert_eq!((trade.trade_id, trade.price, trade.qty), (7, 3.0, 4.0));
+    assert_eq!(trade.symbol, "BNBUSDT");
+  }
+
+  #[test]
+  fn rejects_truncated_frames() {
+    let frame = trades_frame();
+    assert!(decode(&frame[..frame.len() - 3], None).is_err());
+    assert!(decode(&frame[..5], None).is_err());
+  }
+
+  #[test]
+  fn rejects_unknown_schema_and_template() {
+    let mut frame = trades_frame();
+    frame[4] = 9;
+    assert!(decode(&frame, None).is_err());
+
+    let frame = Frame::header(9999, 0).0;
+    assert!(decode(&frame, None).is_err());
+  }
+
+  #[test]
+  fn scales_decimals_exactly() {
+    assert_eq!(decimal(123_456, -2), "1234.56");
+    assert_eq!(decimal(5, -4), "0.0005");
+    assert_eq!(decimal(-5, -2), "-0.05");
+    assert_eq!(decimal(42, 0), "42");
+    assert_eq!(decimal(42, 2), "4200");
+  }
+}
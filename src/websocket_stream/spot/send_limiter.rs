@@ -0,0 +1,39 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Messages Binance accepts from the client per second on one connection
+const MESSAGES_PER_SECOND: usize = 5;
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Spaces out the messages sent on a connection, e.g. SUBSCRIBE and LIST_SUBSCRIPTIONS,
+/// so that the server doesn't drop it for exceeding the incoming message limit
+#[derive(Debug, Default)]
+pub(crate) struct SendLimiter {
+  /// When the messages of the last second were sent
+  sent_at: VecDeque<Instant>,
+}
+
+impl SendLimiter {
+  /// When the next message may be sent, `None` if right away
+  pub(crate) fn next_send_at(&self) -> Option<Instant> {
+    if self.sent_at.len() < MESSAGES_PER_SECOND {
+      return None;
+    }
+    let send_at = self.sent_at[self.sent_at.len() - MESSAGES_PER_SECOND] + WINDOW;
+    (send_at > Instant::now()).then_some(send_at)
+  }
+
+  /// Count a message sent now
+  pub(crate) fn sent(&mut self) {
+    let now = Instant::now();
+    while self
+      .sent_at
+      .front()
+      .is_some_and(|sent_at| now.duration_since(*sent_at) >= WINDOW)
+    {
+      self.sent_at.pop_front();
+    }
+    self.sent_at.push_back(now);
+  }
+}
@@ -0,0 +1,457 @@
+use crate::rest::spot::v3::market::responses::AggregatedTradeResponse;
+use crate::rest::spot::v3::market::SpotMarketV3Manager;
+use crate::rest::spot::v3::trade::responses::TradeRecordResponse;
+use crate::websocket_stream::spot::enums::DepthUpdateSpeed;
+use crate::websocket_stream::spot::events::{AggTradesEvent, TradeEvent, WebsocketSpotEvent};
+use crate::websocket_stream::spot::stream_name::StreamName;
+use anyhow::Result;
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Ids recovered from REST at most per gap, larger gaps are only reported
+const DEFAULT_MAX_BACKFILL: u64 = 10_000;
+
+/// Stream whose ids are checked for continuity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SequenceKind {
+  /// Trade ids of `<symbol>@trade`
+  Trade,
+  /// Aggregate trade ids of `<symbol>@aggTrade`
+  AggTrade,
+  /// Update ids of `<symbol>@depth` or `<symbol>@depth@100ms`.
+  /// Each speed has its own ids, the speed is `None` if the stream is not known
+  Depth(Option<DepthUpdateSpeed>),
+}
+
+/// Ids missing between two events of a stream, both ends included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceGap {
+  pub kind: SequenceKind,
+  pub symbol: String,
+  pub first_missing_id: u64,
+  pub last_missing_id: u64,
+}
+
+impl SequenceGap {
+  /// Number of missing ids
+  pub fn missing_count(&self) -> u64 {
+    self.last_missing_id - self.first_missing_id + 1
+  }
+}
+
+/// Output of `SequenceTracker`, in delivery order
+#[derive(Debug, Clone)]
+pub enum SequencedEvent {
+  /// Live event, or an event of a gap recovered from REST
+  Event(Box<WebsocketSpotEvent>),
+  /// Ids were skipped, reported before the event after the gap
+  Gap(SequenceGap),
+  /// Events of the gap were delivered, `recovered` may be less than the gap length
+  /// if Binance has no record of some ids
+  Backfilled { gap: SequenceGap, recovered: usize },
+  /// The gap could not be recovered, live delivery continues after it
+  BackfillFailed { gap: SequenceGap, reason: String },
+}
+
+/// Continuity tracking of trade, aggregate trade and diff depth streams.
+///
+/// Ids are remembered per stream across reconnections, so data missed while the
+/// connection was down is reported as a gap. Trade and aggregate trade gaps can be
+/// recovered from REST before live delivery resumes, events older than the last
+/// delivered id are dropped. Depth gaps can only be reported, the book has to be
+/// loaded again, e.g. by `LocalOrderBook`.
+///
+/// Depth events don't tell their update speed, feed them with `process_stream` if
+/// both speeds of a symbol are subscribed
+pub struct SequenceTracker {
+  /// Recovers trade gaps if set
+  market: Option<SpotMarketV3Manager>,
+  max_backfill: u64,
+  /// Last delivered id of each stream
+  last_ids: HashMap<(SequenceKind, String), u64>,
+}
+
+impl Default for SequenceTracker {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SequenceTracker {
+  pub fn new() -> Self {
+    Self {
+      market: None,
+      max_backfill: DEFAULT_MAX_BACKFILL,
+      last_ids: HashMap::new(),
+    }
+  }
+
+  /// Recover trade and aggregate trade gaps from REST.
+  /// Historical trades need the API key of the manager
+  pub fn set_backfill(mut self, market: SpotMarketV3Manager) -> Self {
+    self.market = Some(market);
+    self
+  }
+
+  /// Largest gap recovered from REST, default 10 000 ids
+  pub fn set_max_backfill(mut self, max_backfill: u64) -> Self {
+    self.max_backfill = max_backfill;
+    self
+  }
+
+  /// Last delivered id of the stream
+  pub fn last_id(&self, kind: SequenceKind, symbol: &str) -> Option<u64> {
+    self.last_ids.get(&(kind, symbol.to_uppercase())).copied()
+  }
+
+  /// Forget the ids of all streams, e.g. after unsubscribing
+  pub fn reset(&mut self) {
+    self.last_ids.clear();
+  }
+
+  /// Check the event, returns what to deliver in order: gap notices,
+  /// recovered events and the event itself unless it is older than the stream
+  pub async fn process(&mut self, event: WebsocketSpotEvent) -> Vec<SequencedEvent> {
+    self.process_stream(None, event).await
+  }
+
+  /// Check the event of the stream, e.g. from a route of `route_stream`.
+  /// Diff depth events are tracked per update speed of the stream
+  pub async fn process_stream(
+    &mut self,
+    stream: Option<&StreamName>,
+    event: WebsocketSpotEvent,
+  ) -> Vec<SequencedEvent> {
+    let Some((kind, symbol, first_id, last_id)) = sequence_of(stream, &event) else {
+      return vec![SequencedEvent::Event(Box::new(event))];
+    };
+
+    let key = (kind, symbol);
+    let mut delivered = vec![];
+
+    if let Some(&previous_id) = self.last_ids.get(&key) {
+      if last_id <= previous_id {
+        // Delivered already, e.g. recovered from REST or received twice
+        return delivered;
+      }
+      if first_id > previous_id + 1 {
+        let gap = SequenceGap {
+          kind,
+          symbol: key.1.clone(),
+          first_missing_id: previous_id + 1,
+          last_missing_id: first_id - 1,
+        };
+        delivered.push(SequencedEvent::Gap(gap.clone()));
+        delivered.extend(self.backfill(gap).await);
+      }
+    }
+
+    self.last_ids.insert(key, last_id);
+    delivered.push(SequencedEvent::Event(Box::new(event)));
+    delivered
+  }
+
+  /// Feed events of a broadcast stream until it ends, the output goes to the returned channel.
+  /// Events skipped by a lagging receiver show up as gaps
+  pub fn spawn(
+    mut self,
+    mut events: broadcast::Receiver<WebsocketSpotEvent>,
+    buffer: usize,
+  ) -> (mpsc::Receiver<SequencedEvent>, JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel(buffer);
+
+    let join_handle = tokio::spawn(async move {
+      loop {
+        let event = match events.recv().await {
+          Ok(event) => event,
+          Err(RecvError::Lagged(_)) => continue,
+          Err(RecvError::Closed) => return,
+        };
+
+        for sequenced in self.process(event).await {
+          if tx.send(sequenced).await.is_err() {
+            return;
+          }
+        }
+      }
+    });
+
+    (rx, join_handle)
+  }
+
+  /// Events of the gap from REST, if enabled and possible for the stream
+  async fn backfill(&self, gap: SequenceGap) -> Vec<SequencedEvent> {
+    let Some(market) = &self.market else {
+      return vec![];
+    };
+    if matches!(gap.kind, SequenceKind::Depth(_)) {
+      return vec![];
+    }
+    if gap.missing_count() > self.max_backfill {
+      let reason = format!(
+        "Gap of {} ids is larger than the backfill limit of {}",
+        gap.missing_count(),
+        self.max_backfill
+      );
+      return vec![SequencedEvent::BackfillFailed { gap, reason }];
+    }
+
+    let recovered = match gap.kind {
+      SequenceKind::Trade => fetch_trades(market, &gap).await,
+      SequenceKind::AggTrade => fetch_agg_trades(market, &gap).await,
+      SequenceKind::Depth(_) => Ok(vec![]),
+    };
+
+    match recovered {
+      Ok(recovered) => {
+        let count = recovered.len();
+        let mut delivered: Vec<SequencedEvent> = recovered
+          .into_iter()
+          .map(|event| SequencedEvent::Event(Box::new(event)))
+          .collect();
+        delivered.push(SequencedEvent::Backfilled {
+          gap,
+          recovered: count,
+        });
+        delivered
+      }
+      Err(e) => vec![SequencedEvent::BackfillFailed {
+        gap,
+        reason: e.to_string(),
+      }],
+    }
+  }
+}
+
+/// Stream, first and last id of an event that has a sequence
+fn sequence_of(
+  stream: Option<&StreamName>,
+  event: &WebsocketSpotEvent,
+) -> Option<(SequenceKind, String, u64, u64)> {
+  match event {
+    WebsocketSpotEvent::Trade(v) => Some((
+      SequenceKind::Trade,
+      v.symbol.clone(),
+      v.trade_id,
+      v.trade_id,
+    )),
+    WebsocketSpotEvent::AggTrades(v) => Some((
+      SequenceKind::AggTrade,
+      v.symbol.clone(),
+      v.aggregated_trade_id,
+      v.aggregated_trade_id,
+    )),
+    WebsocketSpotEvent::DepthOrderBook(v) => Some((
+      SequenceKind::Depth(match stream {
+        Some(StreamName::Depth {
+          levels: None,
+          speed,
+          ..
+        }) => Some(*speed),
+        _ => None,
+      }),
+      v.symbol.clone(),
+      v.first_update_id,
+      v.final_update_id,
+    )),
+    _ => None,
+  }
+}
+
+/// Trades of the gap, page by page
+async fn fetch_trades(
+  market: &SpotMarketV3Manager,
+  gap: &SequenceGap,
+) -> Result<Vec<WebsocketSpotEvent>> {
+  let mut recovered = vec![];
+  let mut from_id = gap.first_missing_id;
+
+  while from_id <= gap.last_missing_id {
+    let page: Vec<TradeRecordResponse> = market
+      .list_trades_history_from_id(gap.symbol.as_str(), from_id)
+      .await?;
+    let Some(last) = page.last() else {
+      break;
+    };
+    from_id = last.id + 1;
+
+    recovered.extend(
+      page
+        .into_iter()
+        .filter(|trade| trade.id <= gap.last_missing_id)
+        .map(|trade| {
+          WebsocketSpotEvent::Trade(TradeEvent {
+            event_type: "trade".to_string(),
+            event_time: trade.time,
+            symbol: gap.symbol.clone(),
+            trade_id: trade.id,
+            price: trade.price,
+            qty: trade.qty,
+            trade_order_time: trade.time,
+            is_buyer_maker: trade.is_buyer_maker,
+            m_ignore: trade.is_best_match,
+          })
+        }),
+    );
+  }
+  Ok(recovered)
+}
+
+/// Aggregate trades of the gap, page by page
+async fn fetch_agg_trades(
+  market: &SpotMarketV3Manager,
+  gap: &SequenceGap,
+) -> Result<Vec<WebsocketSpotEvent>> {
+  let mut recovered = vec![];
+  let mut from_id = gap.first_missing_id;
+
+  while from_id <= gap.last_missing_id {
+    let page: Vec<AggregatedTradeResponse> = market
+      .list_agg_trades_from_id(gap.symbol.as_str(), from_id)
+      .await?;
+    let Some(last) = page.last() else {
+      break;
+    };
+    from_id = last.agg_id + 1;
+
+    recovered.extend(
+      page
+        .into_iter()
+        .filter(|trade| trade.agg_id <= gap.last_missing_id)
+        .map(|trade| {
+          WebsocketSpotEvent::AggTrades(AggTradesEvent {
+            event_type: "aggTrade".to_string(),
+            event_time: trade.time,
+            symbol: gap.symbol.clone(),
+            aggregated_trade_id: trade.agg_id,
+            price: trade.price,
+            qty: trade.qty,
+            first_break_trade_id: trade.first_id,
+            last_break_trade_id: trade.last_id,
+            trade_order_time: trade.time,
+            is_buyer_maker: trade.maker,
+            m_ignore: trade.best_match,
+          })
+        }),
+    );
+  }
+  Ok(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::websocket_stream::spot::events::DepthOrderBookEvent;
+
+  fn depth_event(first_update_id: u64, final_update_id: u64) -> WebsocketSpotEvent {
+    WebsocketSpotEvent::DepthOrderBook(DepthOrderBookEvent {
+      event_type: "depthUpdate".into(),
+      event_time: 0,
+      symbol: "BTCUSDT".into(),
+      first_update_id,
+      final_update_id,
+      previous_final_update_id: None,
+      bids: vec![],
+      asks: vec![],
+    })
+  }
+
+  fn trade_event(trade_id: u64) -> WebsocketSpotEvent {
+    WebsocketSpotEvent::Trade(TradeEvent {
+      event_type: "trade".into(),
+      event_time: 0,
+      symbol: "BTCUSDT".into(),
+      trade_id,
+      price: 1.0,
+      qty: 1.0,
+      trade_order_time: 0,
+      is_buyer_maker: false,
+      m_ignore: false,
+    })
+  }
+
+  /// Final update ids of the delivered depth events and the gaps
+  fn summary(delivered: &[SequencedEvent]) -> Vec<String> {
+    delivered
+      .iter()
+      .map(|sequenced| match sequenced {
+        SequencedEvent::Event(event) => match event.as_ref() {
+          WebsocketSpotEvent::DepthOrderBook(v) => format!("depth {}", v.final_update_id),
+          WebsocketSpotEvent::Trade(v) => format!("trade {}", v.trade_id),
+          event => format!("{:?}", event),
+        },
+        SequencedEvent::Gap(gap) => format!(
+          "gap {:?} {}-{}",
+          gap.kind, gap.first_missing_id, gap.last_missing_id
+        ),
+        sequenced => format!("{:?}", sequenced),
+      })
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn tracks_both_depth_speeds_of_a_symbol() {
+    let depth_1000ms = StreamName::diff_depth("BTCUSDT", DepthUpdateSpeed::Ms1000);
+    let depth_100ms = StreamName::diff_depth("BTCUSDT", DepthUpdateSpeed::Ms100);
+    let mut tracker = SequenceTracker::new();
+
+    let mut delivered = vec![];
+    for (stream, event) in [
+      (&depth_1000ms, depth_event(1, 10)),
+      (&depth_100ms, depth_event(1, 2)),
+      (&depth_100ms, depth_event(3, 5)),
+      (&depth_100ms, depth_event(6, 12)),
+      (&depth_1000ms, depth_event(11, 20)),
+      (&depth_100ms, depth_event(13, 15)),
+      // Ids 16 to 17 are missing from the 100ms stream only
+      (&depth_100ms, depth_event(18, 21)),
+    ] {
+      delivered.extend(tracker.process_stream(Some(stream), event).await);
+    }
+
+    assert_eq!(
+      summary(&delivered),
+      vec![
+        "depth 10",
+        "depth 2",
+        "depth 5",
+        "depth 12",
+        "depth 20",
+        "depth 15",
+        "gap Depth(Some(Ms100)) 16-17",
+        "depth 21",
+      ]
+    );
+    assert_eq!(
+      tracker.last_id(
+        SequenceKind::Depth(Some(DepthUpdateSpeed::Ms1000)),
+        "btcusdt"
+      ),
+      Some(20)
+    );
+    assert_eq!(
+      tracker.last_id(
+        SequenceKind::Depth(Some(DepthUpdateSpeed::Ms100)),
+        "btcusdt"
+      ),
+      Some(21)
+    );
+  }
+
+  #[tokio::test]
+  async fn reports_gaps_and_drops_delivered_events() {
+    let mut tracker = SequenceTracker::new();
+
+    let mut delivered = vec![];
+    for trade_id in [1, 2, 5, 4, 6] {
+      delivered.extend(tracker.process(trade_event(trade_id)).await);
+    }
+
+    assert_eq!(
+      summary(&delivered),
+      vec!["trade 1", "trade 2", "gap Trade 3-4", "trade 5", "trade 6"]
+    );
+  }
+}
@@ -0,0 +1,105 @@
+use crate::websocket_stream::spot::config::WebSocketSpotConfig;
+use crate::websocket_stream::spot::event_queue::EventQueueMetrics;
+use crate::websocket_stream::spot::event_stream::EventSink;
+use crate::websocket_stream::spot::feed_health::FeedLatencyMetrics;
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::{Command, RequestResponder, WebSocketActor};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// One connection of a `WebSocketSpotStream` and the streams assigned to it
+pub(crate) struct Shard {
+  command_tx: mpsc::Sender<Command>,
+  pub(crate) join_handle: JoinHandle<Result<()>>,
+  /// Streams assigned to this connection
+  pub(crate) streams: HashSet<StreamName>,
+}
+
+impl Shard {
+  /// Start the actor of a new connection, it connects on the first subscription
+  pub(crate) fn spawn(
+    sink: EventSink,
+    config: WebSocketSpotConfig,
+    queue_metrics: Arc<EventQueueMetrics>,
+    latency_metrics: Arc<FeedLatencyMetrics>,
+  ) -> Self {
+    // Create the command channel
+    let (tx, rx) = mpsc::channel(32);
+
+    // Create and start the actor with a boxed version of our handler
+    let actor = WebSocketActor::new(sink, config, queue_metrics, latency_metrics);
+
+    // Spawn the actor in a background task
+    let join_handle = tokio::spawn(async move {
+      let result = actor.run(rx).await;
+      if let Err(ref e) = result {
+        eprintln!("WebSocket actor error: {:?}", e);
+      }
+      result
+    });
+
+    Self {
+      command_tx: tx,
+      join_handle,
+      streams: HashSet::new(),
+    }
+  }
+
+  /// Streams that can still be assigned to this connection
+  pub(crate) fn free_slots(&self, max_streams: usize) -> usize {
+    max_streams.saturating_sub(self.streams.len())
+  }
+
+  /// Send a command to the actor and wait for the server response
+  pub(crate) async fn request<F>(&self, command: F, timeout: Duration) -> Result<Value>
+  where
+    F: FnOnce(RequestResponder) -> Command,
+  {
+    let (tx, rx) = oneshot::channel();
+    self
+      .command_tx
+      .send(command(tx))
+      .await
+      .map_err(|_| anyhow!("Actor task ended"))?;
+
+    match tokio::time::timeout(timeout, rx).await {
+      Ok(Ok(response)) => response,
+      Ok(Err(_)) => Err(anyhow!("Actor task ended or response channel closed")),
+      Err(_) => Err(anyhow!("No response from the server within {:?}", timeout)),
+    }
+  }
+
+  /// Subscriptions the actor holds
+  pub(crate) async fn list_subscriptions(&self) -> Result<Vec<StreamName>> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .command_tx
+      .send(Command::ListSubscriptions(tx))
+      .await
+      .map_err(|_| anyhow!("Actor task ended"))?;
+
+    rx.await
+      .map_err(|_| anyhow!("Actor task ended or response channel closed"))
+  }
+
+  /// Take the streams from the actor, e.g. after the server rejected some of them
+  pub(crate) async fn sync_streams(&mut self) -> Result<()> {
+    self.streams = self.list_subscriptions().await?.into_iter().collect();
+    Ok(())
+  }
+
+  /// Ask the actor to shut down
+  pub(crate) async fn shutdown(&self) -> Result<()> {
+    self
+      .command_tx
+      .send(Command::Shutdown)
+      .await
+      .map_err(|_| anyhow!("Actor task ended"))?;
+    Ok(())
+  }
+}
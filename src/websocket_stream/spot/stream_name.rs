@@ -0,0 +1,294 @@
+use crate::rest::spot::v3::market::enums::KlineInterval;
+use crate::websocket_stream::spot::enums::{DepthLevels, DepthUpdateSpeed, TickerWindowSize};
+use anyhow::{anyhow, bail};
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Name of a spot market stream, e.g. `btcusdt@trade`
+///
+/// Symbols are lowercased, as Binance expects them in stream names.
+///
+/// <https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md>
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StreamName {
+  /// \<symbol\>@trade
+  Trade { symbol: String },
+  /// \<symbol\>@aggTrade
+  AggTrade { symbol: String },
+  /// \<symbol\>@kline_\<interval\>
+  Kline {
+    symbol: String,
+    interval: KlineInterval,
+  },
+  /// \<symbol\>@depth\<levels\> or \<symbol\>@depth\<levels\>@100ms for partial book depth,
+  /// \<symbol\>@depth or \<symbol\>@depth@100ms for diff depth
+  Depth {
+    symbol: String,
+    levels: Option<DepthLevels>,
+    speed: DepthUpdateSpeed,
+  },
+  /// \<symbol\>@bookTicker
+  BookTicker { symbol: String },
+  /// \<symbol\>@bestBidAsk, SBE streams only
+  BestBidAsk { symbol: String },
+  /// \<symbol\>@miniTicker
+  MiniTicker { symbol: String },
+  /// \<symbol\>@ticker
+  Ticker { symbol: String },
+  /// \<symbol\>@ticker_\<window_size\>
+  WindowTicker {
+    symbol: String,
+    window_size: TickerWindowSize,
+  },
+  /// \<symbol\>@avgPrice
+  AvgPrice { symbol: String },
+  /// !miniTicker@arr
+  AllMiniTickers,
+  /// !ticker@arr
+  AllTickers,
+  /// !ticker_\<window_size\>@arr
+  AllWindowTickers { window_size: TickerWindowSize },
+  /// \<listenKey\> of the user data stream
+  UserData { listen_key: String },
+}
+
+impl StreamName {
+  pub fn trade<S: Into<String>>(symbol: S) -> Self {
+    StreamName::Trade {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn agg_trade<S: Into<String>>(symbol: S) -> Self {
+    StreamName::AggTrade {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn kline<S: Into<String>>(symbol: S, interval: KlineInterval) -> Self {
+    StreamName::Kline {
+      symbol: symbol.into().to_lowercase(),
+      interval,
+    }
+  }
+
+  /// Diff depth stream, updates to apply on a local order book
+  pub fn diff_depth<S: Into<String>>(symbol: S, speed: DepthUpdateSpeed) -> Self {
+    StreamName::Depth {
+      symbol: symbol.into().to_lowercase(),
+      levels: None,
+      speed,
+    }
+  }
+
+  /// Partial book depth stream, top bids and asks
+  pub fn partial_depth<S: Into<String>>(
+    symbol: S,
+    levels: DepthLevels,
+    speed: DepthUpdateSpeed,
+  ) -> Self {
+    StreamName::Depth {
+      symbol: symbol.into().to_lowercase(),
+      levels: Some(levels),
+      speed,
+    }
+  }
+
+  pub fn book_ticker<S: Into<String>>(symbol: S) -> Self {
+    StreamName::BookTicker {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  /// Best bid and ask of the SBE streams, see `WebSocketSpotConfig::sbe`
+  pub fn best_bid_ask<S: Into<String>>(symbol: S) -> Self {
+    StreamName::BestBidAsk {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn mini_ticker<S: Into<String>>(symbol: S) -> Self {
+    StreamName::MiniTicker {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn ticker<S: Into<String>>(symbol: S) -> Self {
+    StreamName::Ticker {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn window_ticker<S: Into<String>>(symbol: S, window_size: TickerWindowSize) -> Self {
+    StreamName::WindowTicker {
+      symbol: symbol.into().to_lowercase(),
+      window_size,
+    }
+  }
+
+  pub fn avg_price<S: Into<String>>(symbol: S) -> Self {
+    StreamName::AvgPrice {
+      symbol: symbol.into().to_lowercase(),
+    }
+  }
+
+  pub fn all_mini_tickers() -> Self {
+    StreamName::AllMiniTickers
+  }
+
+  pub fn all_tickers() -> Self {
+    StreamName::AllTickers
+  }
+
+  pub fn all_window_tickers(window_size: TickerWindowSize) -> Self {
+    StreamName::AllWindowTickers { window_size }
+  }
+
+  pub fn user_data<S: Into<String>>(listen_key: S) -> Self {
+    StreamName::UserData {
+      listen_key: listen_key.into(),
+    }
+  }
+
+  /// Symbol of the stream, lowercased. `None` for all-market and user data streams
+  pub fn symbol(&self) -> Option<&str> {
+    match self {
+      StreamName::Trade { symbol }
+      | StreamName::AggTrade { symbol }
+      | StreamName::Kline { symbol, .. }
+      | StreamName::Depth { symbol, .. }
+      | StreamName::BookTicker { symbol }
+      | StreamName::BestBidAsk { symbol }
+      | StreamName::MiniTicker { symbol }
+      | StreamName::Ticker { symbol }
+      | StreamName::WindowTicker { symbol, .. }
+      | StreamName::AvgPrice { symbol } => Some(symbol),
+      StreamName::AllMiniTickers
+      | StreamName::AllTickers
+      | StreamName::AllWindowTickers { .. }
+      | StreamName::UserData { .. } => None,
+    }
+  }
+}
+
+impl Display for StreamName {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StreamName::Trade { symbol } => write!(f, "{}@trade", symbol),
+      StreamName::AggTrade { symbol } => write!(f, "{}@aggTrade", symbol),
+      StreamName::Kline { symbol, interval } => write!(f, "{}@kline_{}", symbol, interval),
+      StreamName::Depth {
+        symbol,
+        levels,
+        speed,
+      } => {
+        write!(f, "{}@depth", symbol)?;
+        if let Some(levels) = levels {
+          write!(f, "{}", levels)?;
+        }
+        // 1000ms is the default speed and is not part of the canonical name
+        match speed {
+          DepthUpdateSpeed::Ms1000 => Ok(()),
+          DepthUpdateSpeed::Ms100 => write!(f, "@{}", speed),
+        }
+      }
+      StreamName::BookTicker { symbol } => write!(f, "{}@bookTicker", symbol),
+      StreamName::BestBidAsk { symbol } => write!(f, "{}@bestBidAsk", symbol),
+      StreamName::MiniTicker { symbol } => write!(f, "{}@miniTicker", symbol),
+      StreamName::Ticker { symbol } => write!(f, "{}@ticker", symbol),
+      StreamName::WindowTicker {
+        symbol,
+        window_size,
+      } => write!(f, "{}@ticker_{}", symbol, window_size),
+      StreamName::AvgPrice { symbol } => write!(f, "{}@avgPrice", symbol),
+      StreamName::AllMiniTickers => write!(f, "!miniTicker@arr"),
+      StreamName::AllTickers => write!(f, "!ticker@arr"),
+      StreamName::AllWindowTickers { window_size } => write!(f, "!ticker_{}@arr", window_size),
+      StreamName::UserData { listen_key } => write!(f, "{}", listen_key),
+    }
+  }
+}
+
+impl FromStr for StreamName {
+  type Err = anyhow::Error;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    if let Some(all_market) = name.strip_prefix('!') {
+      return match all_market {
+        "miniTicker@arr" => Ok(StreamName::AllMiniTickers),
+        "ticker@arr" => Ok(StreamName::AllTickers),
+        _ => {
+          let window_size = all_market
+            .strip_prefix("ticker_")
+            .and_then(|rest| rest.strip_suffix("@arr"))
+            .ok_or_else(|| anyhow!("Unknown stream name: {}", name))?;
+
+          Ok(StreamName::AllWindowTickers {
+            window_size: window_size.parse()?,
+          })
+        }
+      };
+    }
+
+    let Some((symbol, stream)) = name.split_once('@') else {
+      if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("Unknown stream name: {}", name);
+      }
+      return Ok(StreamName::user_data(name));
+    };
+
+    if symbol.is_empty() || symbol.chars().any(|c| c.is_ascii_uppercase()) {
+      bail!("Stream symbol should be lowercase: {}", name);
+    }
+    let symbol = symbol.to_string();
+
+    let stream_name = match stream {
+      "trade" => StreamName::Trade { symbol },
+      "aggTrade" => StreamName::AggTrade { symbol },
+      "bookTicker" => StreamName::BookTicker { symbol },
+      "bestBidAsk" => StreamName::BestBidAsk { symbol },
+      "miniTicker" => StreamName::MiniTicker { symbol },
+      "ticker" => StreamName::Ticker { symbol },
+      "avgPrice" => StreamName::AvgPrice { symbol },
+      _ => {
+        if let Some(interval) = stream.strip_prefix("kline_") {
+          StreamName::Kline {
+            symbol,
+            interval: interval.parse()?,
+          }
+        } else if let Some(window_size) = stream.strip_prefix("ticker_") {
+          StreamName::WindowTicker {
+            symbol,
+            window_size: window_size.parse()?,
+          }
+        } else if let Some(depth) = stream.strip_prefix("depth") {
+          let (levels, speed) = match depth.split_once('@') {
+            Some((levels, speed)) => (levels, speed.parse()?),
+            None => (depth, DepthUpdateSpeed::Ms1000),
+          };
+          let levels = match levels {
+            "" => None,
+            levels => Some(levels.parse()?),
+          };
+
+          StreamName::Depth {
+            symbol,
+            levels,
+            speed,
+          }
+        } else {
+          bail!("Unknown stream name: {}", name);
+        }
+      }
+    };
+
+    Ok(stream_name)
+  }
+}
+
+impl From<StreamName> for String {
+  fn from(stream_name: StreamName) -> Self {
+    stream_name.to_string()
+  }
+}
@@ -0,0 +1,279 @@
+use crate::rest::core::rate_limiter::unfilled_order_rate_limit_manager::UnfilledOrderRateLimitManager;
+use crate::rest::spot::v3::user_stream::SpotUserStreamManagerV3;
+use crate::websocket_stream::spot::config::{ReconnectPolicy, WebSocketSpotConfig};
+use crate::websocket_stream::spot::events::{ConnectionState, WebsocketSpotEvent};
+use crate::websocket_stream::spot::stream_name::StreamName;
+use crate::websocket_stream::spot::WebSocketSpotStream;
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+/// Binance closes a listen key that was not kept alive for 60 minutes
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Events kept for the slowest receiver
+const DEFAULT_EVENTS_CAPACITY: usize = 1024;
+
+/// Account events of the spot user data stream.
+///
+/// Creates the listen key, subscribes to it and keeps it alive.
+/// The key is recreated when Binance reports it expired or a keepalive fails,
+/// failed creations are retried with the backoff of `set_listen_key_retry_policy`.
+/// Failures and the recreation are reported as `ConnectionState` events.
+/// Events are `OrderTrade` (executionReport), `OutboundAccountPosition`,
+/// `SpotBalanceUpdate`, `ListStatus` and `ListenKeyExpired`.
+/// Fills of execution reports release orders from the unfilled order count
+/// of the manager's client, if it has an `UnfilledOrderRateLimitManager`
+pub struct UserDataStream {
+  manager: SpotUserStreamManagerV3,
+  stream: WebSocketSpotStream,
+  unfilled_order_rate_limit_manager: Option<Arc<UnfilledOrderRateLimitManager>>,
+  listen_key: Mutex<Option<String>>,
+  keep_alive_interval: Duration,
+  retry_policy: ReconnectPolicy,
+  task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl UserDataStream {
+  pub fn new(manager: SpotUserStreamManagerV3) -> Self {
+    Self::new_with_config(manager, WebSocketSpotConfig::default())
+  }
+
+  /// - `config` defines connection settings, e.g. reconnection policy
+  pub fn new_with_config(manager: SpotUserStreamManagerV3, config: WebSocketSpotConfig) -> Self {
+    Self {
+      unfilled_order_rate_limit_manager: manager.client.unfilled_order_rate_limiter_manager(),
+      manager,
+      stream: WebSocketSpotStream::new_with_broadcast_and_config(DEFAULT_EVENTS_CAPACITY, config),
+      listen_key: Mutex::new(None),
+      keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+      retry_policy: ReconnectPolicy::default(),
+      task: Mutex::new(None),
+    }
+  }
+
+  /// Interval of the listen key keepalive, default 30 minutes
+  pub fn set_keep_alive_interval(mut self, interval: Duration) -> Self {
+    self.keep_alive_interval = interval;
+    self
+  }
+
+  /// Backoff of the listen key creation after it failed, retries forever by default
+  pub fn set_listen_key_retry_policy(mut self, retry_policy: ReconnectPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+
+  /// New receiver of all events from now on
+  pub fn event_receiver(&self) -> Result<broadcast::Receiver<WebsocketSpotEvent>> {
+    self.stream.event_receiver()
+  }
+
+  /// Listen key in use, `None` before `start`
+  pub async fn listen_key(&self) -> Option<String> {
+    self.listen_key.lock().await.clone()
+  }
+
+  /// Create the listen key, connect and start keeping the key alive
+  pub async fn start(self: &Arc<Self>) -> Result<()> {
+    let mut task = self.task.lock().await;
+    if task.is_some() {
+      bail!("User data stream is started already");
+    }
+
+    // Subscribe before the first event can arrive
+    let events = self.stream.event_receiver()?;
+    self.recreate_listen_key().await?;
+
+    let this = Arc::clone(self);
+    *task = Some(tokio::spawn(async move { this.run(events).await }));
+    Ok(())
+  }
+
+  /// Stop the keepalive, close the connection and the listen key
+  pub async fn stop(&self) -> Result<()> {
+    if let Some(task) = self.task.lock().await.take() {
+      task.abort();
+    }
+    self.stream.shutdown().await?;
+
+    if let Some(listen_key) = self.listen_key.lock().await.take() {
+      self.manager.close(&listen_key).await?;
+    }
+    Ok(())
+  }
+
+  /// Keep the listen key alive and replace it once it's no longer valid,
+  /// pass execution reports on to the unfilled order count
+  async fn run(&self, mut events: broadcast::Receiver<WebsocketSpotEvent>) {
+    // `None` once the listen key creation gave up
+    let mut keep_alive_at = Some(tokio::time::Instant::now() + self.keep_alive_interval);
+    let mut failed_attempts = 0;
+
+    loop {
+      tokio::select! {
+        _ = tokio::time::sleep_until(keep_alive_at.unwrap_or_else(tokio::time::Instant::now)),
+          if keep_alive_at.is_some() =>
+        {
+          keep_alive_at = self
+            .keep_alive(&mut failed_attempts)
+            .await
+            .map(|delay| tokio::time::Instant::now() + delay);
+        }
+        event = events.recv() => match event {
+          Ok(WebsocketSpotEvent::ListenKeyExpired(event)) => {
+            if self.listen_key().await.as_deref() == Some(event.listen_key.as_str()) {
+              keep_alive_at = self
+                .recreate(&mut failed_attempts)
+                .await
+                .map(|delay| tokio::time::Instant::now() + delay);
+            }
+          }
+          Ok(WebsocketSpotEvent::OrderTrade(event)) => {
+            if let Some(order_rate_limiter) = &self.unfilled_order_rate_limit_manager {
+              order_rate_limiter.handle_execution_report(&(&event).into()).await;
+            }
+          }
+          Ok(_) | Err(RecvError::Lagged(_)) => {}
+          Err(RecvError::Closed) => return,
+        },
+      }
+    }
+  }
+
+  /// Extend the validity of the listen key, returns the delay until the next keepalive
+  /// or the retry of a failed creation, `None` once the creation gave up
+  async fn keep_alive(&self, failed_attempts: &mut u32) -> Option<Duration> {
+    // The last creation failed
+    if *failed_attempts > 0 {
+      return self.recreate(failed_attempts).await;
+    }
+    let Some(listen_key) = self.listen_key().await else {
+      return self.recreate(failed_attempts).await;
+    };
+
+    match self.manager.keep_alive(&listen_key).await {
+      Ok(_) => Some(self.keep_alive_interval),
+      Err(e) => {
+        self
+          .emit(ConnectionState::ListenKeyKeepAliveFailed {
+            reason: e.to_string(),
+          })
+          .await;
+        self.recreate(failed_attempts).await
+      }
+    }
+  }
+
+  /// Replace the listen key, returns the delay until the next keepalive
+  /// or the retry of a failed creation, `None` once the creation gave up
+  async fn recreate(&self, failed_attempts: &mut u32) -> Option<Duration> {
+    match self.recreate_listen_key().await {
+      Ok(()) => {
+        *failed_attempts = 0;
+        self.emit(ConnectionState::ListenKeyRecreated).await;
+        Some(self.keep_alive_interval)
+      }
+      Err(e) => {
+        *failed_attempts += 1;
+        let attempt = *failed_attempts;
+        let retry = self
+          .retry_policy
+          .allows_attempt(attempt)
+          .then(|| self.retry_policy.delay_for_attempt(attempt));
+        self
+          .emit(ConnectionState::ListenKeyFailed {
+            attempt,
+            reason: e.to_string(),
+            retry_ms: retry.map(|delay| delay.as_millis() as u64),
+          })
+          .await;
+        retry
+      }
+    }
+  }
+
+  async fn emit(&self, state: ConnectionState) {
+    // Only fails for dropped channels, a broadcast has none
+    let _ = self
+      .stream
+      .emit(WebsocketSpotEvent::ConnectionState(state))
+      .await;
+  }
+
+  /// Create a new listen key and move the subscription over to it
+  async fn recreate_listen_key(&self) -> Result<()> {
+    let new_key = self.manager.start().await?.listen_key;
+    self
+      .stream
+      .subscribe(vec![StreamName::user_data(new_key.clone())])
+      .await?;
+
+    let old_key = self.listen_key.lock().await.replace(new_key.clone());
+    if let Some(old_key) = old_key.filter(|old_key| *old_key != new_key) {
+      // The old key is usually expired already
+      let _ = self
+        .stream
+        .unsubscribe(vec![StreamName::user_data(old_key)])
+        .await;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::Binance;
+  use crate::config::Config;
+  use tokio::net::TcpListener;
+
+  /// Host of a closed port, every request fails
+  async fn closed_host() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+  }
+
+  #[tokio::test]
+  async fn reports_failed_listen_key_creations_and_backs_off() {
+    let config = Config::default().set_rest_api_endpoint(closed_host().await);
+    let manager = SpotUserStreamManagerV3::new_with_config(Some("key".into()), None, &config);
+    let user_data = UserDataStream::new(manager).set_listen_key_retry_policy(
+      ReconnectPolicy::default()
+        .set_initial_delay(Duration::from_millis(10))
+        .set_max_attempts(2),
+    );
+    let mut events = user_data.event_receiver().unwrap();
+
+    let mut failed_attempts = 0;
+    let retries: Vec<Option<Duration>> = vec![
+      user_data.recreate(&mut failed_attempts).await,
+      user_data.recreate(&mut failed_attempts).await,
+      user_data.recreate(&mut failed_attempts).await,
+    ];
+    assert_eq!(
+      retries,
+      vec![
+        Some(Duration::from_millis(10)),
+        Some(Duration::from_millis(20)),
+        None
+      ]
+    );
+
+    for (expected_attempt, expected_retry_ms) in [(1, Some(10)), (2, Some(20)), (3, None)] {
+      let Ok(WebsocketSpotEvent::ConnectionState(ConnectionState::ListenKeyFailed {
+        attempt,
+        retry_ms,
+        ..
+      })) = events.recv().await
+      else {
+        panic!("no ListenKeyFailed event");
+      };
+      assert_eq!(attempt, expected_attempt);
+      assert_eq!(retry_ms, expected_retry_ms);
+    }
+    assert!(user_data.listen_key().await.is_none());
+  }
+}